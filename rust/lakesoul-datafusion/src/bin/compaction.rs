@@ -0,0 +1,63 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compacts partitions of a LakeSoul table.
+//!
+//! Usage: `lakesoul-compaction <pg config> <namespace> <table name> [partition desc]...`,
+//! where the pg config is a postgres connection string like
+//! "host=127.0.0.1 port=5432 dbname=lakesoul_test user=lakesoul_test password=lakesoul_test".
+//! Every partition of the table is compacted if no partition desc is given.
+
+use datafusion::error::{DataFusionError, Result};
+use lakesoul_datafusion::compaction::{compact_partition, compact_table, CompactionResult};
+use lakesoul_datafusion::metadata_error;
+use lakesoul_io::lakesoul_io_config::LakeSoulIOConfigBuilder;
+use lakesoul_metadata::error::LakeSoulMetaDataError;
+use lakesoul_metadata::MetaDataClient;
+
+fn print_result(partition_desc: &str, result: &CompactionResult) {
+    println!(
+        "compacted {} files of version {} of partition {} into {} files",
+        result.compacted_files.len(),
+        result.read_version,
+        partition_desc,
+        result.files.len()
+    );
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let [pg_config, namespace, table_name, partition_descs @ ..] = args.as_slice() else {
+        return Err(DataFusionError::Configuration(
+            "usage: lakesoul-compaction <pg config> <namespace> <table name> [partition desc]...".to_string(),
+        ));
+    };
+    let client = MetaDataClient::from_config(pg_config.clone())
+        .await
+        .map_err(metadata_error)?;
+    let table_info = client
+        .get_table_info_by_name(table_name, namespace)
+        .await
+        .map_err(metadata_error)?
+        .ok_or_else(|| {
+            metadata_error(LakeSoulMetaDataError::NotFound(format!(
+                "table {}.{}",
+                namespace, table_name
+            )))
+        })?;
+
+    if partition_descs.is_empty() {
+        for (partition_desc, result) in compact_table(&client, &table_info, LakeSoulIOConfigBuilder::new()).await? {
+            print_result(&partition_desc, &result);
+        }
+    }
+    for partition_desc in partition_descs {
+        match compact_partition(&client, &table_info, partition_desc, LakeSoulIOConfigBuilder::new()).await? {
+            Some(result) => print_result(partition_desc, &result),
+            None => println!("skipped partition {}", partition_desc),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,204 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::any::Any;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::catalog::schema::SchemaProvider;
+use datafusion::catalog::CatalogProvider;
+use datafusion::datasource::TableProvider;
+use datafusion::error::Result;
+use lakesoul_io::lakesoul_io_config::LakeSoulIOConfig;
+use lakesoul_metadata::snapshot::SnapshotMode;
+use lakesoul_metadata::MetaDataClient;
+
+use crate::metadata_error;
+use crate::table_provider::LakeSoulTableProvider;
+
+/// Maps LakeSoul namespaces to datafusion schemas.
+///
+/// Catalog and schema listing apis of datafusion are sync while metadata access is async, so namespaces
+/// and their table names are loaded when the catalog is created. Create a new catalog to see namespaces
+/// and tables created later.
+pub struct LakeSoulCatalog {
+    namespaces: Vec<Arc<LakeSoulNamespace>>,
+}
+
+impl LakeSoulCatalog {
+    /// `io_config` is the base config of readers of all tables, e.g. object store options and batch size.
+    pub async fn try_new(client: Arc<MetaDataClient>, io_config: LakeSoulIOConfig) -> Result<Self> {
+        let mut namespaces = vec![];
+        for namespace in client.list_namespaces().await.map_err(metadata_error)? {
+            namespaces.push(Arc::new(
+                LakeSoulNamespace::try_new(client.clone(), namespace.namespace, io_config.clone()).await?,
+            ));
+        }
+        Ok(LakeSoulCatalog { namespaces })
+    }
+}
+
+impl CatalogProvider for LakeSoulCatalog {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema_names(&self) -> Vec<String> {
+        self.namespaces
+            .iter()
+            .map(|namespace| namespace.namespace.clone())
+            .collect()
+    }
+
+    fn schema(&self, name: &str) -> Option<Arc<dyn SchemaProvider>> {
+        self.namespaces
+            .iter()
+            .find(|namespace| namespace.namespace == name)
+            .map(|namespace| namespace.clone() as Arc<dyn SchemaProvider>)
+    }
+}
+
+/// Maps tables of a LakeSoul namespace to [`LakeSoulTableProvider`]s reading their latest snapshots.
+///
+/// Table names are loaded when the namespace is created, while table infos are read when a table is queried.
+pub struct LakeSoulNamespace {
+    client: Arc<MetaDataClient>,
+    namespace: String,
+    io_config: LakeSoulIOConfig,
+    table_names: Vec<String>,
+}
+
+impl LakeSoulNamespace {
+    pub async fn try_new(client: Arc<MetaDataClient>, namespace: String, io_config: LakeSoulIOConfig) -> Result<Self> {
+        let table_names = client
+            .list_table_name_by_namespace(&namespace)
+            .await
+            .map_err(metadata_error)?
+            .into_iter()
+            .map(|table_name_id| table_name_id.table_name)
+            .collect();
+        Ok(LakeSoulNamespace {
+            client,
+            namespace,
+            io_config,
+            table_names,
+        })
+    }
+}
+
+#[async_trait]
+impl SchemaProvider for LakeSoulNamespace {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        self.table_names.clone()
+    }
+
+    async fn table(&self, name: &str) -> Option<Arc<dyn TableProvider>> {
+        let table_info = match self.client.get_table_info_by_name(name, &self.namespace).await {
+            Ok(Some(table_info)) => table_info,
+            Ok(None) => return None,
+            Err(e) => {
+                log::warn!("get table {}.{} error: {}", self.namespace, name, e);
+                return None;
+            }
+        };
+        match LakeSoulTableProvider::try_new(
+            self.client.clone(),
+            table_info,
+            self.io_config.clone(),
+            SnapshotMode::Latest,
+        ) {
+            Ok(provider) => Some(Arc::new(provider)),
+            Err(e) => {
+                log::warn!("create table provider of {}.{} error: {}", self.namespace, name, e);
+                None
+            }
+        }
+    }
+
+    fn table_exist(&self, name: &str) -> bool {
+        self.table_names.iter().any(|table_name| table_name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_eq;
+    use datafusion::parquet::arrow::ArrowWriter;
+    use datafusion::prelude::SessionContext;
+    use lakesoul_metadata::test_utils::{create_test_table, test_client};
+    use proto::proto::entity::{CommitOp, DataCommitInfo, DataFileOp, FileOp, Uuid};
+
+    #[tokio::test]
+    async fn test_query_lakesoul_table() -> Result<()> {
+        let Some(client) = test_client().await.map_err(crate::metadata_error)? else {
+            return Ok(());
+        };
+        let client = Arc::new(client);
+        let temp_dir = tempfile::tempdir()?;
+        let table_schema = r#"{"type":"struct","fields":[
+            {"name":"date","type":"string","nullable":true,"metadata":{}},
+            {"name":"id","type":"long","nullable":false,"metadata":{}},
+            {"name":"name","type":"string","nullable":true,"metadata":{}}
+        ]}"#;
+        let table_id = create_test_table(&client, temp_dir.path().to_str().unwrap(), table_schema, "date;")
+            .await
+            .map_err(crate::metadata_error)?
+            .table_id;
+
+        for (date, ids, names) in [("20230101", vec![1, 2], vec!["a", "b"]), ("20230102", vec![3], vec!["c"])] {
+            let path = temp_dir.path().join(format!("date={}.parquet", date));
+            let batch = RecordBatch::try_from_iter(vec![
+                ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+                ("name", Arc::new(StringArray::from(names)) as ArrayRef),
+            ])?;
+            let mut writer = ArrowWriter::try_new(std::fs::File::create(&path)?, batch.schema(), None)?;
+            writer.write(&batch)?;
+            writer.close()?;
+            let (high, low) = uuid::Uuid::new_v4().as_u64_pair();
+            client
+                .commit_data_commit_info(DataCommitInfo {
+                    table_id: table_id.clone(),
+                    partition_desc: format!("date={}", date),
+                    commit_id: Some(Uuid { high, low }),
+                    file_ops: vec![DataFileOp {
+                        path: path.to_str().unwrap().to_string(),
+                        file_op: FileOp::Add as i32,
+                        size: 0,
+                        file_exist_cols: "id,name".to_string(),
+                    }],
+                    commit_op: CommitOp::AppendCommit as i32,
+                    domain: "public".to_string(),
+                    ..Default::default()
+                })
+                .await
+                .map_err(crate::metadata_error)?;
+        }
+
+        let ctx = SessionContext::new();
+        ctx.register_catalog(
+            "lakesoul",
+            Arc::new(LakeSoulCatalog::try_new(client, Default::default()).await?),
+        );
+        let batches = ctx
+            .sql(&format!(
+                "select id, name from lakesoul.default.{} where date = '20230101' and id > 1",
+                table_id
+            ))
+            .await?
+            .collect()
+            .await?;
+        assert_batches_eq!(
+            vec!["+----+------+", "| id | name |", "+----+------+", "| 2  | b    |", "+----+------+",],
+            &batches
+        );
+        Ok(())
+    }
+}
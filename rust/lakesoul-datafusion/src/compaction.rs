@@ -0,0 +1,360 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compaction of partitions into new files sorted by primary keys, without Spark.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use arrow::record_batch::RecordBatch;
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::error::{DataFusionError, Result};
+use lakesoul_io::lakesoul_io_config::{create_session_context, LakeSoulIOConfigBuilder};
+use lakesoul_io::lakesoul_reader::LakeSoulReader;
+use lakesoul_io::lakesoul_writer::{FlushResult, SyncSendableMutableLakeSoulWriter};
+use lakesoul_io::sorted_merge::merge_operator::MergeOperator;
+use lakesoul_metadata::error::LakeSoulMetaDataError;
+use lakesoul_metadata::{parse_table_info_partitions, MetaDataClient, LAKESOUL_HASH_BUCKET_NUM};
+use proto::proto::entity::{CommitOp, DataCommitInfo, DataFileOp, FileOp, MetaInfo, PartitionInfo, TableInfo, Uuid};
+
+use crate::incremental::cdc_column;
+use crate::metadata_error;
+use crate::snapshot::create_io_config_builder;
+use crate::spark_schema::spark_schema_to_arrow_schema;
+
+// number of merged batches buffered ahead of the writer
+const COMPACTION_BUFFER_SIZE: usize = 8;
+
+/// Files replaced and written by a compaction.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompactionResult {
+    /// version of the partition that has been compacted
+    pub read_version: i32,
+    /// paths of the compacted files
+    pub compacted_files: Vec<String>,
+    /// the new files, in the order they were written
+    pub files: Vec<FlushResult>,
+}
+
+fn hash_bucket_num(table_info: &TableInfo) -> Result<usize> {
+    if table_info.properties.is_empty() {
+        return Ok(0);
+    }
+    let properties: serde_json::Value =
+        serde_json::from_str(&table_info.properties).map_err(|e| DataFusionError::External(Box::new(e)))?;
+    match properties.get(LAKESOUL_HASH_BUCKET_NUM) {
+        None => Ok(0),
+        // written as a string by the java client
+        Some(serde_json::Value::String(num)) => {
+            num.parse::<usize>().map_err(|e| DataFusionError::External(Box::new(e)))
+        }
+        Some(num) => num.as_u64().map(|num| num as usize).ok_or_else(|| {
+            DataFusionError::Configuration(format!(
+                "invalid {} of table {}",
+                LAKESOUL_HASH_BUCKET_NUM, table_info.table_path
+            ))
+        }),
+    }
+}
+
+/// Removes files through the object stores registered for them with the options of `base`.
+async fn delete_files(base: LakeSoulIOConfigBuilder, files: Vec<String>) -> Result<()> {
+    let mut config = base.with_files(files).build();
+    let sess_ctx = create_session_context(&mut config)?;
+    for file in &config.files {
+        let url = ListingTableUrl::parse(file)?;
+        sess_ctx.runtime_env().object_store(&url)?.delete(url.prefix()).await?;
+    }
+    Ok(())
+}
+
+/// Compacts the current snapshot of a partition into new files.
+///
+/// All files of the partition are read by a [`LakeSoulReader`], merging rows by primary keys with the merge
+/// operators set on `base`, and dropping deleted keys of cdc tables. `Count` merge operators are rejected, since
+/// a compacted count would be counted as a single row by later reads. The merged rows are written through the sorted
+/// writer under the table path, into hash buckets if the table has any. `base` carries the other reader and writer
+/// options, e.g. object store options and compression, and must not have files.
+///
+/// The new files are committed as a `CompactionCommit` deleting the compacted files, which keeps the commits appended
+/// to the partition concurrently. Returns None if the partition has less than two files, or if the compaction has
+/// been skipped since the partition has been compacted or updated concurrently, in which case its commit info and
+/// written files are removed.
+pub async fn compact_partition(
+    client: &MetaDataClient,
+    table_info: &TableInfo,
+    partition_desc: &str,
+    base: LakeSoulIOConfigBuilder,
+) -> Result<Option<CompactionResult>> {
+    for (field, name) in base.clone().build().merge_operators() {
+        if let MergeOperator::Count = MergeOperator::from_name(name)? {
+            return Err(DataFusionError::Configuration(format!(
+                "merge operator Count of field {} can't be used in compaction",
+                field
+            )));
+        }
+    }
+    let latest = client
+        .get_partition_info(&table_info.table_id, partition_desc)
+        .await
+        .map_err(metadata_error)?
+        .ok_or_else(|| {
+            metadata_error(LakeSoulMetaDataError::NotFound(format!(
+                "partition {} of table {}",
+                partition_desc, table_info.table_path
+            )))
+        })?;
+    let snapshot = client
+        .resolve_partition_snapshot(latest)
+        .await
+        .map_err(metadata_error)?;
+    if snapshot.files.len() < 2 {
+        return Ok(None);
+    }
+
+    let mut reader_builder = create_io_config_builder(base.clone(), table_info, &snapshot)?;
+    if let Some(cdc_column) = cdc_column(table_info)? {
+        reader_builder = reader_builder.with_cdc_column(cdc_column);
+    }
+    let mut reader = LakeSoulReader::new(reader_builder.build())?;
+    reader.start().await?;
+
+    let (range_partitions, primary_keys) = parse_table_info_partitions(&table_info.partitions);
+    let writer_config = base
+        .clone()
+        .with_prefix(table_info.table_path.clone())
+        .with_range_partitions(range_partitions)
+        .with_hash_bucket_num(hash_bucket_num(table_info)?)
+        .with_primary_keys(primary_keys)
+        .with_schema(spark_schema_to_arrow_schema(&table_info.table_schema)?)
+        .build();
+    // the writer blocks on a runtime of its own, so it runs on a blocking thread fed by the reader
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Result<RecordBatch>>(COMPACTION_BUFFER_SIZE);
+    let writer_task = tokio::task::spawn_blocking(move || {
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+        let writer = SyncSendableMutableLakeSoulWriter::try_new(writer_config, runtime)?;
+        while let Some(batch) = rx.blocking_recv() {
+            if let Err(e) = batch.and_then(|batch| writer.write_batch(batch)) {
+                writer.abort_and_close()?;
+                return Err(e);
+            }
+        }
+        writer.flush_and_close()
+    });
+    while let Some(batch) = reader.next_rb().await {
+        let failed = batch.is_err();
+        // the receiver is only dropped if the writer has failed, which is reported by its task
+        if tx.send(batch).await.is_err() || failed {
+            break;
+        }
+    }
+    drop(tx);
+    let files = writer_task
+        .await
+        .map_err(|e| DataFusionError::External(Box::new(e)))??;
+
+    let (high, low) = uuid::Uuid::new_v4().as_u64_pair();
+    let commit_id = Uuid { high, low };
+    let file_ops = files
+        .iter()
+        .map(|file| DataFileOp {
+            path: file.path.clone(),
+            file_op: FileOp::Add as i32,
+            size: file.size as i64,
+            file_exist_cols: file.file_exist_cols.clone(),
+        })
+        .chain(snapshot.files.iter().map(|file| DataFileOp {
+            file_op: FileOp::Del as i32,
+            ..file.clone()
+        }))
+        .collect();
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| DataFusionError::External(Box::new(e)))?
+        .as_millis() as i64;
+    client
+        .insert_data_commit_info(DataCommitInfo {
+            table_id: table_info.table_id.clone(),
+            partition_desc: partition_desc.to_string(),
+            commit_id: Some(commit_id.clone()),
+            file_ops,
+            commit_op: CommitOp::CompactionCommit as i32,
+            timestamp,
+            committed: false,
+            domain: table_info.domain.clone(),
+        })
+        .await
+        .map_err(metadata_error)?;
+    let meta_info = MetaInfo {
+        list_partition: vec![PartitionInfo {
+            table_id: table_info.table_id.clone(),
+            partition_desc: partition_desc.to_string(),
+            snapshot: vec![commit_id.clone()],
+            commit_op: CommitOp::CompactionCommit as i32,
+            domain: table_info.domain.clone(),
+            ..Default::default()
+        }],
+        table_info: Some(table_info.clone()),
+        read_partition_info: vec![snapshot.partition_info.clone()],
+    };
+    // an obsolete compaction is skipped by the commit without an error
+    let committed = client
+        .commit_data(meta_info, CommitOp::CompactionCommit)
+        .await
+        .map_err(metadata_error)?;
+    if committed.is_empty() {
+        client
+            .delete_data_commit_info_list(&table_info.table_id, partition_desc, &[commit_id])
+            .await
+            .map_err(metadata_error)?;
+        delete_files(base, files.into_iter().map(|file| file.path).collect()).await?;
+        return Ok(None);
+    }
+    Ok(Some(CompactionResult {
+        read_version: snapshot.partition_info.version,
+        compacted_files: snapshot.file_paths(),
+        files,
+    }))
+}
+
+/// Compacts the current snapshot of every partition of a table, see [`compact_partition`].
+/// Returns the partitions compacted along with their results.
+pub async fn compact_table(
+    client: &MetaDataClient,
+    table_info: &TableInfo,
+    base: LakeSoulIOConfigBuilder,
+) -> Result<Vec<(String, CompactionResult)>> {
+    let mut results = vec![];
+    for partition_info in client
+        .list_partitions(&table_info.table_id)
+        .await
+        .map_err(metadata_error)?
+    {
+        if let Some(result) =
+            compact_partition(client, table_info, &partition_info.partition_desc, base.clone()).await?
+        {
+            results.push((partition_info.partition_desc, result));
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use datafusion::assert_batches_eq;
+    use datafusion::parquet::arrow::ArrowWriter;
+    use lakesoul_metadata::test_utils::{create_test_table, test_client};
+
+    #[test]
+    fn test_hash_bucket_num() -> Result<()> {
+        let table_info = |properties: &str| TableInfo {
+            properties: properties.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(hash_bucket_num(&table_info(""))?, 0);
+        assert_eq!(hash_bucket_num(&table_info("{}"))?, 0);
+        assert_eq!(hash_bucket_num(&table_info(r#"{"hashBucketNum":"4"}"#))?, 4);
+        assert_eq!(hash_bucket_num(&table_info(r#"{"hashBucketNum":2}"#))?, 2);
+        assert!(hash_bucket_num(&table_info(r#"{"hashBucketNum":"-1"}"#)).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_compact_partition() -> Result<()> {
+        let Some(client) = test_client().await.map_err(metadata_error)? else {
+            return Ok(());
+        };
+        let temp_dir = tempfile::tempdir()?;
+        let table_schema = r#"{"type":"struct","fields":[
+            {"name":"date","type":"string","nullable":true,"metadata":{}},
+            {"name":"id","type":"long","nullable":false,"metadata":{}},
+            {"name":"name","type":"string","nullable":true,"metadata":{}}
+        ]}"#;
+        let table_info = create_test_table(&client, temp_dir.path().to_str().unwrap(), table_schema, "date;id")
+            .await
+            .map_err(metadata_error)?;
+        let table_id = table_info.table_id.clone();
+
+        let partition_desc = "date=20230101";
+        for (file_idx, ids, names) in [(0, vec![1, 2], vec!["a", "b"]), (1, vec![2, 3], vec!["B", "c"])] {
+            let path = temp_dir.path().join(format!("part-{}.parquet", file_idx));
+            let batch = RecordBatch::try_from_iter(vec![
+                ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+                ("name", Arc::new(StringArray::from(names)) as ArrayRef),
+            ])?;
+            let mut writer = ArrowWriter::try_new(std::fs::File::create(&path)?, batch.schema(), None)?;
+            writer.write(&batch)?;
+            writer.close()?;
+            let (high, low) = uuid::Uuid::new_v4().as_u64_pair();
+            client
+                .commit_data_commit_info(DataCommitInfo {
+                    table_id: table_id.clone(),
+                    partition_desc: partition_desc.to_string(),
+                    commit_id: Some(Uuid { high, low }),
+                    file_ops: vec![DataFileOp {
+                        path: path.to_str().unwrap().to_string(),
+                        file_op: FileOp::Add as i32,
+                        size: 0,
+                        file_exist_cols: "id,name".to_string(),
+                    }],
+                    commit_op: CommitOp::AppendCommit as i32,
+                    domain: "public".to_string(),
+                    ..Default::default()
+                })
+                .await
+                .map_err(metadata_error)?;
+        }
+
+        let result = compact_partition(&client, &table_info, partition_desc, LakeSoulIOConfigBuilder::new())
+            .await?
+            .unwrap();
+        assert_eq!(result.compacted_files.len(), 2);
+        let latest = client
+            .get_partition_info(&table_id, partition_desc)
+            .await
+            .map_err(metadata_error)?
+            .unwrap();
+        let snapshot = client
+            .resolve_partition_snapshot(latest)
+            .await
+            .map_err(metadata_error)?;
+        assert_eq!(
+            snapshot.file_paths(),
+            result.files.iter().map(|file| file.path.clone()).collect::<Vec<_>>()
+        );
+
+        let mut reader = LakeSoulReader::new(
+            create_io_config_builder(LakeSoulIOConfigBuilder::new(), &table_info, &snapshot)?.build(),
+        )?;
+        reader.start().await?;
+        let mut batches = vec![];
+        while let Some(batch) = reader.next_rb().await {
+            batches.push(batch?);
+        }
+        assert_batches_eq!(
+            &[
+                "+----------+----+------+",
+                "| date     | id | name |",
+                "+----------+----+------+",
+                "| 20230101 | 1  | a    |",
+                "| 20230101 | 2  | B    |",
+                "| 20230101 | 3  | c    |",
+                "+----------+----+------+",
+            ],
+            &batches
+        );
+
+        // Count and invalid merge operators are rejected before reading
+        for merge_op in ["Count", "Unknown"] {
+            let base = LakeSoulIOConfigBuilder::new().with_merge_op("name".to_string(), merge_op.to_string());
+            assert!(compact_partition(&client, &table_info, partition_desc, base)
+                .await
+                .is_err());
+        }
+        Ok(())
+    }
+}
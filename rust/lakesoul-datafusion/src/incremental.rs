@@ -0,0 +1,104 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Incremental reads of the data committed to a partition within a window of versions.
+
+use datafusion::error::{DataFusionError, Result};
+use lakesoul_io::constant::LAKESOUL_CDC_CHANGE_COLUMN;
+use lakesoul_io::lakesoul_io_config::LakeSoulIOConfigBuilder;
+use lakesoul_io::lakesoul_reader::LakeSoulReader;
+use lakesoul_metadata::snapshot::IncrementalRange;
+use lakesoul_metadata::MetaDataClient;
+use proto::proto::entity::TableInfo;
+
+use crate::metadata_error;
+use crate::snapshot::create_io_config_builder;
+
+/// Gets the cdc column of row kinds from the table properties, if the table has one.
+pub fn cdc_column(table_info: &TableInfo) -> Result<Option<String>> {
+    if table_info.properties.is_empty() {
+        return Ok(None);
+    }
+    let properties: serde_json::Value =
+        serde_json::from_str(&table_info.properties).map_err(|e| DataFusionError::External(Box::new(e)))?;
+    Ok(properties
+        .get(LAKESOUL_CDC_CHANGE_COLUMN)
+        .and_then(serde_json::Value::as_str)
+        .map(str::to_string))
+}
+
+/// Creates the reader config of the data files added to a partition within `range` on top of `base`,
+/// or None if no data has been added within `range`.
+///
+/// With `merge_on_read`, rows are merged by primary keys so that only the last version of each key is read.
+/// For tables with a cdc column, keys whose last version is a delete are then dropped, unless keep deletes
+/// is set on `base`, in which case they are read with their row kinds. Without `merge_on_read`,
+/// every version of a key is read in commit order.
+pub async fn create_incremental_io_config_builder(
+    client: &MetaDataClient,
+    table_info: &TableInfo,
+    partition_desc: &str,
+    range: IncrementalRange,
+    base: LakeSoulIOConfigBuilder,
+    merge_on_read: bool,
+) -> Result<Option<LakeSoulIOConfigBuilder>> {
+    let snapshot = client
+        .resolve_partition_incremental(&table_info.table_id, partition_desc, range)
+        .await
+        .map_err(metadata_error)?;
+    let Some(snapshot) = snapshot.filter(|snapshot| !snapshot.files.is_empty()) else {
+        return Ok(None);
+    };
+    let builder = create_io_config_builder(base, table_info, &snapshot)?;
+    if !merge_on_read {
+        return Ok(Some(builder.with_primary_keys(vec![])));
+    }
+    Ok(Some(match cdc_column(table_info)? {
+        Some(cdc_column) => builder.with_cdc_column(cdc_column),
+        None => builder,
+    }))
+}
+
+/// Creates and starts a reader over the data files added to a partition within `range`,
+/// or returns None if no data has been added within `range`. See [`create_incremental_io_config_builder`].
+pub async fn create_incremental_reader(
+    client: &MetaDataClient,
+    table_info: &TableInfo,
+    partition_desc: &str,
+    range: IncrementalRange,
+    base: LakeSoulIOConfigBuilder,
+    merge_on_read: bool,
+) -> Result<Option<LakeSoulReader>> {
+    let Some(builder) =
+        create_incremental_io_config_builder(client, table_info, partition_desc, range, base, merge_on_read).await?
+    else {
+        return Ok(None);
+    };
+    let mut reader = LakeSoulReader::new(builder.build())?;
+    reader.start().await?;
+    Ok(Some(reader))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cdc_column() -> Result<()> {
+        let table_info = |properties: &str| TableInfo {
+            properties: properties.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(cdc_column(&table_info(""))?, None);
+        assert_eq!(cdc_column(&table_info("{}"))?, None);
+        assert_eq!(
+            cdc_column(&table_info(
+                r#"{"hashBucketNum":"2","lakesoul_cdc_change_column":"rowKinds"}"#
+            ))?,
+            Some("rowKinds".to_string())
+        );
+        assert!(cdc_column(&table_info("{")).is_err());
+        Ok(())
+    }
+}
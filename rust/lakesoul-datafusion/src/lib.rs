@@ -0,0 +1,22 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+pub mod catalog;
+pub mod compaction;
+pub mod incremental;
+pub mod snapshot;
+pub mod spark_schema;
+pub mod table_provider;
+pub mod vacuum;
+
+pub use catalog::{LakeSoulCatalog, LakeSoulNamespace};
+pub use table_provider::LakeSoulTableProvider;
+
+use datafusion::error::DataFusionError;
+use lakesoul_metadata::error::LakeSoulMetaDataError;
+
+/// Wraps an error of the metadata client into a DataFusion error.
+pub fn metadata_error(err: LakeSoulMetaDataError) -> DataFusionError {
+    DataFusionError::External(Box::new(err))
+}
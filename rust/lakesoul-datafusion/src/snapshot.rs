@@ -0,0 +1,83 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use datafusion::error::Result;
+use lakesoul_io::lakesoul_io_config::LakeSoulIOConfigBuilder;
+use lakesoul_metadata::snapshot::{PartitionSnapshot, SnapshotMode};
+use lakesoul_metadata::{parse_partition_desc, parse_table_info_partitions, MetaDataClient};
+use proto::proto::entity::TableInfo;
+
+use crate::metadata_error;
+use crate::spark_schema::spark_schema_to_arrow_schema;
+
+/// Creates the reader config of one partition snapshot on top of `base`, with table schema, primary keys
+/// and range partition values filled in.
+pub fn create_io_config_builder(
+    base: LakeSoulIOConfigBuilder,
+    table_info: &TableInfo,
+    snapshot: &PartitionSnapshot,
+) -> Result<LakeSoulIOConfigBuilder> {
+    let schema = spark_schema_to_arrow_schema(&table_info.table_schema)?;
+    let (_, primary_keys) = parse_table_info_partitions(&table_info.partitions);
+    // range partition columns are not stored in data files
+    Ok(parse_partition_desc(snapshot.partition_desc()).into_iter().fold(
+        base.with_files(snapshot.file_paths())
+            .with_primary_keys(primary_keys)
+            .with_schema(schema),
+        |builder, (column, value)| builder.with_default_column_value(column, value),
+    ))
+}
+
+/// Resolves the snapshot of a table according to `mode` and creates a reader config for each non-empty partition.
+pub async fn create_io_config_builders(
+    client: &MetaDataClient,
+    table_info: &TableInfo,
+    mode: SnapshotMode,
+    base: LakeSoulIOConfigBuilder,
+) -> Result<Vec<LakeSoulIOConfigBuilder>> {
+    client
+        .resolve_table_snapshot(table_info, mode)
+        .await
+        .map_err(metadata_error)?
+        .iter()
+        .filter(|snapshot| !snapshot.files.is_empty())
+        .map(|snapshot| create_io_config_builder(base.clone(), table_info, snapshot))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::proto::entity::{DataFileOp, PartitionInfo};
+
+    #[test]
+    fn test_create_io_config_builder() -> Result<()> {
+        let table_info = TableInfo {
+            table_schema: r#"{"type":"struct","fields":[
+                {"name":"date","type":"string","nullable":true,"metadata":{}},
+                {"name":"id","type":"long","nullable":false,"metadata":{}},
+                {"name":"value","type":"double","nullable":true,"metadata":{}}
+            ]}"#
+            .to_string(),
+            partitions: "date;id".to_string(),
+            ..Default::default()
+        };
+        let snapshot = PartitionSnapshot {
+            partition_info: PartitionInfo {
+                partition_desc: "date=20230101".to_string(),
+                ..Default::default()
+            },
+            files: vec![DataFileOp {
+                path: "s3://bucket/table/date=20230101/part-0.parquet".to_string(),
+                ..Default::default()
+            }],
+        };
+        let config = create_io_config_builder(LakeSoulIOConfigBuilder::new(), &table_info, &snapshot)?.build();
+        assert_eq!(config.files(), &["s3://bucket/table/date=20230101/part-0.parquet".to_string()]);
+        assert_eq!(config.primary_keys(), &["id".to_string()]);
+        assert_eq!(config.schema().fields().len(), 3);
+        assert_eq!(config.default_column_value().get("date"), Some(&"20230101".to_string()));
+        Ok(())
+    }
+}
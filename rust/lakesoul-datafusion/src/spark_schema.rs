@@ -0,0 +1,148 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Conversion of Spark StructType json, the format of `TableInfo.table_schema`, into arrow schema.
+
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef, TimeUnit};
+use datafusion::error::{DataFusionError, Result};
+use lakesoul_io::constant::LAKESOUL_TIMEZONE;
+use serde_json::Value;
+
+pub fn spark_schema_to_arrow_schema(table_schema: &str) -> Result<SchemaRef> {
+    let value: Value = serde_json::from_str(table_schema)
+        .map_err(|e| DataFusionError::Plan(format!("invalid table schema {}: {}", table_schema, e)))?;
+    match spark_type_to_arrow_type(&value)? {
+        DataType::Struct(fields) => Ok(Arc::new(Schema::new(fields))),
+        data_type => Err(DataFusionError::Plan(format!(
+            "table schema should be a struct type, but got {}",
+            data_type
+        ))),
+    }
+}
+
+fn spark_type_to_arrow_type(value: &Value) -> Result<DataType> {
+    match value {
+        Value::String(name) => spark_primitive_type_to_arrow_type(name),
+        Value::Object(obj) => match obj.get("type").and_then(Value::as_str) {
+            Some("struct") => {
+                let fields = obj
+                    .get("fields")
+                    .and_then(Value::as_array)
+                    .ok_or_else(|| invalid_type(value))?
+                    .iter()
+                    .map(spark_field_to_arrow_field)
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(DataType::Struct(Fields::from(fields)))
+            }
+            Some("array") => {
+                let element_type = spark_type_to_arrow_type(obj.get("elementType").ok_or_else(|| invalid_type(value))?)?;
+                let contains_null = obj.get("containsNull").and_then(Value::as_bool).unwrap_or(true);
+                Ok(DataType::List(Arc::new(Field::new("element", element_type, contains_null))))
+            }
+            Some("map") => {
+                let key_type = spark_type_to_arrow_type(obj.get("keyType").ok_or_else(|| invalid_type(value))?)?;
+                let value_type = spark_type_to_arrow_type(obj.get("valueType").ok_or_else(|| invalid_type(value))?)?;
+                let value_contains_null = obj.get("valueContainsNull").and_then(Value::as_bool).unwrap_or(true);
+                let entries = Field::new(
+                    "entries",
+                    DataType::Struct(Fields::from(vec![
+                        Field::new("key", key_type, false),
+                        Field::new("value", value_type, value_contains_null),
+                    ])),
+                    false,
+                );
+                Ok(DataType::Map(Arc::new(entries), false))
+            }
+            _ => Err(invalid_type(value)),
+        },
+        _ => Err(invalid_type(value)),
+    }
+}
+
+fn spark_field_to_arrow_field(value: &Value) -> Result<Field> {
+    let name = value
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid_type(value))?;
+    let data_type = spark_type_to_arrow_type(value.get("type").ok_or_else(|| invalid_type(value))?)?;
+    let nullable = value.get("nullable").and_then(Value::as_bool).unwrap_or(true);
+    Ok(Field::new(name, data_type, nullable))
+}
+
+fn spark_primitive_type_to_arrow_type(name: &str) -> Result<DataType> {
+    match name {
+        "null" => Ok(DataType::Null),
+        "boolean" => Ok(DataType::Boolean),
+        "byte" => Ok(DataType::Int8),
+        "short" => Ok(DataType::Int16),
+        "integer" => Ok(DataType::Int32),
+        "long" => Ok(DataType::Int64),
+        "float" => Ok(DataType::Float32),
+        "double" => Ok(DataType::Float64),
+        "string" => Ok(DataType::Utf8),
+        "binary" => Ok(DataType::Binary),
+        "date" => Ok(DataType::Date32),
+        "timestamp" => Ok(DataType::Timestamp(TimeUnit::Microsecond, Some(LAKESOUL_TIMEZONE.into()))),
+        "timestamp_ntz" => Ok(DataType::Timestamp(TimeUnit::Microsecond, None)),
+        decimal if decimal.starts_with("decimal(") && decimal.ends_with(')') => {
+            let (precision, scale) = decimal["decimal(".len()..decimal.len() - 1]
+                .split_once(',')
+                .ok_or_else(|| DataFusionError::Plan(format!("invalid spark type {}", name)))?;
+            let precision = precision
+                .trim()
+                .parse::<u8>()
+                .map_err(|e| DataFusionError::Plan(format!("invalid spark type {}: {}", name, e)))?;
+            let scale = scale
+                .trim()
+                .parse::<i8>()
+                .map_err(|e| DataFusionError::Plan(format!("invalid spark type {}: {}", name, e)))?;
+            Ok(DataType::Decimal128(precision, scale))
+        }
+        _ => Err(DataFusionError::NotImplemented(format!("unsupported spark type {}", name))),
+    }
+}
+
+fn invalid_type(value: &Value) -> DataFusionError {
+    DataFusionError::Plan(format!("invalid spark type {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spark_schema_to_arrow_schema() -> Result<()> {
+        let table_schema = r#"{"type":"struct","fields":[
+            {"name":"id","type":"long","nullable":false,"metadata":{}},
+            {"name":"name","type":"string","nullable":true,"metadata":{}},
+            {"name":"price","type":"decimal(10,2)","nullable":true,"metadata":{}},
+            {"name":"ts","type":"timestamp","nullable":true,"metadata":{}},
+            {"name":"tags","type":{"type":"array","elementType":"string","containsNull":true},"nullable":true,"metadata":{}},
+            {"name":"attrs","type":{"type":"map","keyType":"string","valueType":"integer","valueContainsNull":false},"nullable":true,"metadata":{}},
+            {"name":"point","type":{"type":"struct","fields":[{"name":"x","type":"double","nullable":true,"metadata":{}}]},"nullable":true,"metadata":{}}
+        ]}"#;
+        let schema = spark_schema_to_arrow_schema(table_schema)?;
+        assert_eq!(schema.fields().len(), 7);
+        assert_eq!(schema.field(0), &Field::new("id", DataType::Int64, false));
+        assert_eq!(schema.field(2).data_type(), &DataType::Decimal128(10, 2));
+        assert_eq!(
+            schema.field(3).data_type(),
+            &DataType::Timestamp(TimeUnit::Microsecond, Some(LAKESOUL_TIMEZONE.into()))
+        );
+        assert_eq!(
+            schema.field(4).data_type(),
+            &DataType::List(Arc::new(Field::new("element", DataType::Utf8, true)))
+        );
+        assert!(matches!(schema.field(5).data_type(), DataType::Map(_, false)));
+        assert_eq!(
+            schema.field(6).data_type(),
+            &DataType::Struct(Fields::from(vec![Field::new("x", DataType::Float64, true)]))
+        );
+
+        assert!(spark_schema_to_arrow_schema(r#"{"type":"struct","fields":[{"name":"c","type":"unknown"}]}"#).is_err());
+        Ok(())
+    }
+}
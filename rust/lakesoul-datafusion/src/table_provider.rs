@@ -0,0 +1,372 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::any::Any;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use arrow::array::{as_boolean_array, Array};
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use datafusion::common::DFSchema;
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::{SessionState, TaskContext};
+use datafusion::logical_expr::expr_rewriter::unnormalize_col;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::optimizer::simplify_expressions::{ExprSimplifier, SimplifyContext};
+use datafusion::physical_expr::execution_props::ExecutionProps;
+use datafusion::physical_expr::{create_physical_expr, PhysicalSortExpr};
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics};
+use futures::{StreamExt, TryStreamExt};
+use lakesoul_io::lakesoul_io_config::{LakeSoulIOConfig, LakeSoulIOConfigBuilder};
+use lakesoul_io::lakesoul_reader::LakeSoulReader;
+use lakesoul_io::transform::make_default_array;
+use lakesoul_metadata::snapshot::SnapshotMode;
+use lakesoul_metadata::{parse_partition_desc, parse_table_info_partitions, MetaDataClient};
+use proto::proto::entity::TableInfo;
+
+use crate::metadata_error;
+use crate::snapshot::create_io_config_builder;
+use crate::spark_schema::spark_schema_to_arrow_schema;
+
+/// A LakeSoul table read through [`LakeSoulReader`], one DataFusion partition per LakeSoul partition.
+pub struct LakeSoulTableProvider {
+    client: Arc<MetaDataClient>,
+    table_info: TableInfo,
+    schema: SchemaRef,
+    range_partition_columns: Vec<String>,
+    primary_keys: Vec<String>,
+    // base config of readers, e.g. object store options and batch size
+    io_config: LakeSoulIOConfig,
+    mode: SnapshotMode,
+}
+
+impl Debug for LakeSoulTableProvider {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LakeSoulTableProvider")
+            .field("table_info", &self.table_info)
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+impl LakeSoulTableProvider {
+    pub fn try_new(
+        client: Arc<MetaDataClient>,
+        table_info: TableInfo,
+        io_config: LakeSoulIOConfig,
+        mode: SnapshotMode,
+    ) -> Result<Self> {
+        let schema = spark_schema_to_arrow_schema(&table_info.table_schema)?;
+        let (range_partition_columns, primary_keys) = parse_table_info_partitions(&table_info.partitions);
+        Ok(LakeSoulTableProvider {
+            client,
+            table_info,
+            schema,
+            range_partition_columns,
+            primary_keys,
+            io_config,
+            mode,
+        })
+    }
+
+    pub fn table_info(&self) -> &TableInfo {
+        &self.table_info
+    }
+
+    pub fn range_partition_columns(&self) -> &[String] {
+        &self.range_partition_columns
+    }
+
+    pub fn primary_keys(&self) -> &[String] {
+        &self.primary_keys
+    }
+
+    fn is_partition_filter(&self, filter: &Expr) -> bool {
+        match filter.to_columns() {
+            Ok(columns) => {
+                !columns.is_empty()
+                    && columns
+                        .iter()
+                        .all(|column| self.range_partition_columns.contains(&column.name))
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn range_partition_schema(&self) -> Result<SchemaRef> {
+        Ok(Arc::new(Schema::new(
+            self.range_partition_columns
+                .iter()
+                .map(|column| self.schema.field_with_name(column).cloned())
+                .collect::<std::result::Result<Vec<_>, _>>()?,
+        )))
+    }
+
+    // schema to read with projected columns and primary keys needed for merging, in table schema order
+    fn read_schema(&self, projection: Option<&Vec<usize>>) -> SchemaRef {
+        match projection {
+            None => self.schema.clone(),
+            Some(projection) => Arc::new(Schema::new(
+                self.schema
+                    .fields()
+                    .iter()
+                    .enumerate()
+                    .filter(|(idx, field)| projection.contains(idx) || self.primary_keys.contains(field.name()))
+                    .map(|(_, field)| field.clone())
+                    .collect::<Vec<_>>(),
+            )),
+        }
+    }
+}
+
+/// Returns whether the partition of `partition_desc` may contain rows satisfying all `filters`,
+/// which must only refer to columns of `range_partition_schema`.
+pub fn prune_partition(range_partition_schema: SchemaRef, partition_desc: &str, filters: &[Expr]) -> Result<bool> {
+    if filters.is_empty() {
+        return Ok(true);
+    }
+    let partition_values = parse_partition_desc(partition_desc);
+    let columns = range_partition_schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let value = partition_values
+                .iter()
+                .find(|(column, _)| column == field.name())
+                .map(|(_, value)| value.clone())
+                .ok_or_else(|| {
+                    DataFusionError::Internal(format!(
+                        "range partition column {} not found in partition desc {}",
+                        field.name(),
+                        partition_desc
+                    ))
+                })?;
+            make_default_array(field.data_type(), &value, 1)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let batch = RecordBatch::try_new(range_partition_schema.clone(), columns)?;
+    let df_schema = Arc::new(DFSchema::try_from(range_partition_schema.as_ref().clone())?);
+    let props = ExecutionProps::new();
+    let simplifier = ExprSimplifier::new(SimplifyContext::new(&props).with_schema(df_schema.clone()));
+    for filter in filters {
+        let filter = simplifier.coerce(filter.clone(), df_schema.clone())?;
+        let predicate = create_physical_expr(&filter, &df_schema, &range_partition_schema, &props)?;
+        let result = predicate.evaluate(&batch)?.into_array(1);
+        let result = as_boolean_array(&result);
+        if result.is_null(0) || !result.value(0) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+#[async_trait]
+impl TableProvider for LakeSoulTableProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        _state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        // readers resolve columns by unqualified names
+        let (partition_filters, data_filters): (Vec<Expr>, Vec<Expr>) = filters
+            .iter()
+            .cloned()
+            .map(unnormalize_col)
+            .partition(|filter| self.is_partition_filter(filter));
+        // filters mixing range partition columns and data columns are left to datafusion
+        let data_filters = data_filters
+            .into_iter()
+            .filter(|filter| match filter.to_columns() {
+                Ok(columns) => columns
+                    .iter()
+                    .all(|column| !self.range_partition_columns.contains(&column.name)),
+                Err(_) => false,
+            })
+            .collect::<Vec<_>>();
+        // readers may stop early only if they evaluate all filters, otherwise rows would be counted before filtering
+        let limit = limit.filter(|_| partition_filters.len() + data_filters.len() == filters.len());
+        let range_partition_schema = self.range_partition_schema()?;
+
+        let read_schema = self.read_schema(projection);
+        let projected_schema = match projection {
+            None => self.schema.clone(),
+            Some(projection) => Arc::new(self.schema.project(projection)?),
+        };
+        let output_indices = projected_schema
+            .fields()
+            .iter()
+            .map(|field| read_schema.index_of(field.name()))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut configs = vec![];
+        for latest in self
+            .client
+            .list_partitions(&self.table_info.table_id)
+            .await
+            .map_err(metadata_error)?
+        {
+            if !prune_partition(range_partition_schema.clone(), &latest.partition_desc, &partition_filters)? {
+                continue;
+            }
+            let Some(partition_info) = self
+                .client
+                .resolve_partition_version(latest, self.mode)
+                .await
+                .map_err(metadata_error)?
+            else {
+                continue;
+            };
+            let snapshot = self
+                .client
+                .resolve_partition_snapshot(partition_info)
+                .await
+                .map_err(metadata_error)?;
+            if snapshot.files.is_empty() {
+                continue;
+            }
+            let mut builder = create_io_config_builder(
+                LakeSoulIOConfigBuilder::from(self.io_config.clone()),
+                &self.table_info,
+                &snapshot,
+            )?;
+            if let Some(limit) = limit {
+                builder = builder.with_limit(limit);
+            }
+            configs.push(
+                builder
+                    .with_schema(read_schema.clone())
+                    .with_filters(data_filters.clone())
+                    .build(),
+            );
+        }
+
+        Ok(Arc::new(LakeSoulScanExec {
+            configs,
+            schema: projected_schema,
+            output_indices,
+        }))
+    }
+
+    fn supports_filter_pushdown(&self, _filter: &Expr) -> Result<TableProviderFilterPushDown> {
+        // partition values are pruned and rows are filtered by readers,
+        // but datafusion still needs to filter rows with the filters readers cannot evaluate
+        Ok(TableProviderFilterPushDown::Inexact)
+    }
+}
+
+/// Reads each LakeSoul partition with a [`LakeSoulReader`].
+pub struct LakeSoulScanExec {
+    configs: Vec<LakeSoulIOConfig>,
+    schema: SchemaRef,
+    // indices of output columns in the schema of readers
+    output_indices: Vec<usize>,
+}
+
+impl Debug for LakeSoulScanExec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LakeSoulScanExec")
+            .field("partitions", &self.configs.len())
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl ExecutionPlan for LakeSoulScanExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.configs.len())
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(self: Arc<Self>, _children: Vec<Arc<dyn ExecutionPlan>>) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(self)
+    }
+
+    fn execute(&self, partition: usize, _context: Arc<TaskContext>) -> Result<SendableRecordBatchStream> {
+        let config = self
+            .configs
+            .get(partition)
+            .cloned()
+            .ok_or_else(|| DataFusionError::Internal(format!("invalid partition {} of LakeSoulScanExec", partition)))?;
+        let output_indices = self.output_indices.clone();
+        let stream = futures::stream::once(async move {
+            let mut reader = LakeSoulReader::new(config)?;
+            reader.start().await?;
+            Ok::<_, DataFusionError>(futures::stream::unfold(reader, |mut reader| async move {
+                reader.next_rb().await.map(|batch| (batch, reader))
+            }))
+        })
+        .try_flatten()
+        .map(move |batch: Result<RecordBatch>| -> Result<RecordBatch> {
+            Ok(batch?.project(&output_indices)?)
+        });
+        Ok(Box::pin(RecordBatchStreamAdapter::new(self.schema.clone(), stream)))
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::{DataType, Field};
+    use datafusion::logical_expr::{col, lit};
+
+    #[test]
+    fn test_prune_partition() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("date", DataType::Utf8, true),
+            Field::new("hour", DataType::Int32, true),
+        ]));
+        let partition_desc = "date=20230101,hour=12";
+        assert!(prune_partition(schema.clone(), partition_desc, &[])?);
+        assert!(prune_partition(schema.clone(), partition_desc, &[col("date").eq(lit("20230101"))])?);
+        assert!(prune_partition(
+            schema.clone(),
+            partition_desc,
+            &[col("date").eq(lit("20230101")), col("hour").gt_eq(lit(12))]
+        )?);
+        assert!(!prune_partition(schema.clone(), partition_desc, &[col("hour").lt(lit(12i64))])?);
+        assert!(!prune_partition(
+            schema,
+            "date=__L@KE$OUL_NULL__,hour=1",
+            &[col("date").eq(lit("20230101"))]
+        )?);
+        Ok(())
+    }
+}
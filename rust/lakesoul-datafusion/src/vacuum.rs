@@ -0,0 +1,176 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Expiry of old versions of a table and removal of the data files no longer referred to by its versions.
+
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::error::{DataFusionError, Result};
+use futures::StreamExt;
+use lakesoul_io::lakesoul_io_config::{create_session_context, LakeSoulIOConfigBuilder};
+use lakesoul_metadata::expiry::ExpiredPartition;
+use lakesoul_metadata::MetaDataClient;
+use proto::proto::entity::TableInfo;
+
+use crate::metadata_error;
+
+/// Options of [`vacuum_table`].
+#[derive(Clone, Copy, Debug)]
+pub struct VacuumOptions {
+    /// snapshots within the retention period stay readable by time travel, older versions are expired
+    pub retention: Duration,
+    /// unreferenced files modified within the grace period are kept, since they may belong to ongoing commits
+    pub grace_period: Duration,
+    /// only reports what would be removed
+    pub dry_run: bool,
+}
+
+/// Versions and files removed by [`vacuum_table`], or to be removed in dry run.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VacuumResult {
+    pub expired: Vec<ExpiredPartition>,
+    /// urls of the removed files
+    pub orphan_files: Vec<String>,
+}
+
+fn millis_before(now: Duration, duration: Duration) -> i64 {
+    now.saturating_sub(duration).as_millis() as i64
+}
+
+/// Expires the versions of a table older than the retention period, then removes the parquet files under the
+/// table path which are referred to by none of the remaining versions and are older than the grace period.
+/// Files are listed and removed through the object store registered for the table path with the options of `base`.
+pub async fn vacuum_table(
+    client: &MetaDataClient,
+    table_info: &TableInfo,
+    options: VacuumOptions,
+    base: LakeSoulIOConfigBuilder,
+) -> Result<VacuumResult> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+    let expired = client
+        .expire_table_versions(table_info, millis_before(now, options.retention), options.dry_run)
+        .await
+        .map_err(metadata_error)?;
+    let referenced = client
+        .list_referenced_files(table_info, &expired)
+        .await
+        .map_err(metadata_error)?;
+
+    // the table path and referenced files are resolved into object store paths the same way as by readers
+    let mut config = base
+        .with_files(
+            std::iter::once(table_info.table_path.clone())
+                .chain(referenced)
+                .collect(),
+        )
+        .build();
+    let sess_ctx = create_session_context(&mut config)?;
+    let table_url = ListingTableUrl::parse(&config.files[0])?;
+    let store_url = table_url.object_store();
+    let object_store = sess_ctx.runtime_env().object_store(&store_url)?;
+    let referenced = config.files[1..]
+        .iter()
+        .map(|file| Ok(ListingTableUrl::parse(file)?.prefix().clone()))
+        .collect::<Result<HashSet<_>>>()?;
+
+    let orphan_before = millis_before(now, options.grace_period);
+    let mut orphan_files = vec![];
+    let mut listing = object_store.list(Some(table_url.prefix())).await?;
+    while let Some(meta) = listing.next().await {
+        let meta = meta?;
+        if meta.location.as_ref().ends_with(".parquet")
+            && !referenced.contains(&meta.location)
+            && meta.last_modified.timestamp_millis() < orphan_before
+        {
+            orphan_files.push(meta.location);
+        }
+    }
+    if !options.dry_run {
+        for location in &orphan_files {
+            object_store.delete(location).await?;
+        }
+    }
+    Ok(VacuumResult {
+        expired,
+        orphan_files: orphan_files
+            .iter()
+            .map(|location| format!("{}{}", store_url, location))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lakesoul_metadata::test_utils::{create_test_table, test_client};
+    use proto::proto::entity::{CommitOp, DataCommitInfo, DataFileOp, FileOp, Uuid};
+
+    #[tokio::test]
+    async fn test_vacuum_table() -> Result<()> {
+        let Some(client) = test_client().await.map_err(metadata_error)? else {
+            return Ok(());
+        };
+        let temp_dir = tempfile::tempdir()?;
+        let table_schema = r#"{"type":"struct","fields":[
+            {"name":"date","type":"string","nullable":true,"metadata":{}},
+            {"name":"id","type":"long","nullable":false,"metadata":{}}
+        ]}"#;
+        let table_info = create_test_table(&client, temp_dir.path().to_str().unwrap(), table_schema, "date;")
+            .await
+            .map_err(metadata_error)?;
+        let table_id = table_info.table_id.clone();
+
+        // partition values are escaped in directory names, which are escaped again in file urls
+        let partition_desc = "date=2023-01-01 00%3A00%3A00";
+        let partition_dir = temp_dir.path().join(partition_desc);
+        std::fs::create_dir(&partition_dir)?;
+        let live_file = partition_dir.join("live.parquet");
+        let orphan_file = partition_dir.join("orphan.parquet");
+        std::fs::write(&live_file, b"live")?;
+        std::fs::write(&orphan_file, b"orphan")?;
+        let (high, low) = uuid::Uuid::new_v4().as_u64_pair();
+        client
+            .commit_data_commit_info(DataCommitInfo {
+                table_id: table_id.clone(),
+                partition_desc: partition_desc.to_string(),
+                commit_id: Some(Uuid { high, low }),
+                file_ops: vec![DataFileOp {
+                    path: url::Url::from_file_path(&live_file).unwrap().to_string(),
+                    file_op: FileOp::Add as i32,
+                    size: 4,
+                    file_exist_cols: "id".to_string(),
+                }],
+                commit_op: CommitOp::AppendCommit as i32,
+                domain: "public".to_string(),
+                ..Default::default()
+            })
+            .await
+            .map_err(metadata_error)?;
+        // file modification times are compared in milliseconds
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let vacuum = |dry_run: bool| {
+            let options = VacuumOptions {
+                retention: Duration::from_secs(3600),
+                grace_period: Duration::ZERO,
+                dry_run,
+            };
+            vacuum_table(&client, &table_info, options, LakeSoulIOConfigBuilder::new())
+        };
+        let result = vacuum(true).await?;
+        assert!(result.expired.is_empty());
+        assert_eq!(result.orphan_files.len(), 1);
+        assert!(result.orphan_files[0].ends_with("/orphan.parquet"));
+        assert!(orphan_file.exists());
+
+        assert_eq!(vacuum(false).await?, result);
+        assert!(live_file.exists());
+        assert!(!orphan_file.exists());
+        Ok(())
+    }
+}
@@ -41,6 +41,9 @@ using ResultCallback = void(*)(bool, const char*);
 
 using DataResultCallback = void(*)(bool, const char*, const void*);
 
+/// Called with the written file metadata in json, see [`flush_result_to_json`].
+using FlushResultCallback = void(*)(bool, const char*, const char*);
+
 using I32ResultCallback = void(*)(int32_t, const char*);
 
 using I32DataResultCallback = void(*)(int32_t, const char*, const void*);
@@ -68,6 +71,20 @@ IOConfigBuilder *lakesoul_config_builder_add_single_aux_sort_column(IOConfigBuil
 
 IOConfigBuilder *lakesoul_config_builder_add_filter(IOConfigBuilder *builder, const char *filter);
 
+/// Adds a filter in SQL, e.g. `amount > 100 AND region IN ('eu', 'us')`.
+IOConfigBuilder *lakesoul_config_builder_add_sql_filter(IOConfigBuilder *builder,
+                                                        const char *sql_filter);
+
+/// Adds an output column computed in SQL, e.g. `amount * 2 AS doubled`.
+IOConfigBuilder *lakesoul_config_builder_add_sql_projection(IOConfigBuilder *builder,
+                                                            const char *sql_projection);
+
+/// Adds filters from a serialized substrait `ExtendedExpression` of `len` bytes,
+/// which are converted against the read schema when the reader is created.
+IOConfigBuilder *lakesoul_config_builder_add_substrait_filter(IOConfigBuilder *builder,
+                                                              const uint8_t *extended_expression,
+                                                              c_size_t len);
+
 IOConfigBuilder *lakesoul_config_builder_set_schema(IOConfigBuilder *builder,
                                                     c_ptrdiff_t schema_addr);
 
@@ -77,9 +94,56 @@ IOConfigBuilder *lakesoul_config_builder_set_thread_num(IOConfigBuilder *builder
 IOConfigBuilder *lakesoul_config_builder_set_batch_size(IOConfigBuilder *builder,
                                                         c_size_t batch_size);
 
+/// Stops reading after `limit` rows, counted after merging primary keys and filtering.
+IOConfigBuilder *lakesoul_config_builder_set_limit(IOConfigBuilder *builder, c_size_t limit);
+
+/// Merges at most `merge_parallelism` ranges of the first primary key in parallel.
+IOConfigBuilder *lakesoul_config_builder_set_merge_parallelism(IOConfigBuilder *builder,
+                                                               c_size_t merge_parallelism);
+
 IOConfigBuilder *lakesoul_config_builder_set_max_row_group_size(IOConfigBuilder *builder,
                                                                 c_size_t max_row_group_size);
 
+/// `level` < 0 means the default level of `codec`.
+IOConfigBuilder *lakesoul_config_builder_set_compression(IOConfigBuilder *builder,
+                                                         const char *codec,
+                                                         int level);
+
+IOConfigBuilder *lakesoul_config_builder_set_dictionary_enabled(IOConfigBuilder *builder,
+                                                                bool enabled);
+
+IOConfigBuilder *lakesoul_config_builder_set_column_dictionary_enabled(IOConfigBuilder *builder,
+                                                                       const char *column,
+                                                                       bool enabled);
+
+IOConfigBuilder *lakesoul_config_builder_set_statistics_enabled(IOConfigBuilder *builder,
+                                                                const char *statistics_enabled);
+
+IOConfigBuilder *lakesoul_config_builder_set_data_page_size_limit(IOConfigBuilder *builder,
+                                                                  c_size_t data_page_size_limit);
+
+/// `fpp` <= 0 or `ndv` == 0 means the parquet default.
+IOConfigBuilder *lakesoul_config_builder_add_bloom_filter(IOConfigBuilder *builder,
+                                                          const char *column,
+                                                          double fpp,
+                                                          uint64_t ndv);
+
+/// Writes rolling files under `prefix` instead of the single file added to the builder.
+IOConfigBuilder *lakesoul_config_builder_set_prefix(IOConfigBuilder *builder, const char *prefix);
+
+IOConfigBuilder *lakesoul_config_builder_set_max_file_size(IOConfigBuilder *builder,
+                                                           uint64_t max_file_size);
+
+IOConfigBuilder *lakesoul_config_builder_set_max_file_rows(IOConfigBuilder *builder,
+                                                           uint64_t max_file_rows);
+
+/// Writes rows into hash buckets of primary keys, which requires a prefix set.
+IOConfigBuilder *lakesoul_config_builder_set_hash_bucket_num(IOConfigBuilder *builder,
+                                                             c_size_t hash_bucket_num);
+
+IOConfigBuilder *lakesoul_config_builder_set_writer_version(IOConfigBuilder *builder,
+                                                            const char *writer_version);
+
 IOConfigBuilder *lakesoul_config_builder_set_buffer_size(IOConfigBuilder *builder,
                                                          c_size_t buffer_size);
 
@@ -94,10 +158,22 @@ IOConfigBuilder *lakesoul_config_builder_add_files(IOConfigBuilder *builder,
 IOConfigBuilder *lakesoul_config_builder_add_single_primary_key(IOConfigBuilder *builder,
                                                                 const char *pk);
 
+/// Splits written rows by the range partition column into `col=value` directories under the prefix.
+IOConfigBuilder *lakesoul_config_builder_add_single_range_partition(IOConfigBuilder *builder,
+                                                                    const char *column);
+
 IOConfigBuilder *lakesoul_config_builder_add_merge_op(IOConfigBuilder *builder,
                                                       const char *field,
                                                       const char *merge_op);
 
+/// Drops keys whose last version is a delete by the cdc column of row kinds, e.g. `rowKinds`.
+IOConfigBuilder *lakesoul_config_builder_set_cdc_column(IOConfigBuilder *builder,
+                                                        const char *cdc_column);
+
+/// Keeps keys whose last version is a delete, for incremental reads.
+IOConfigBuilder *lakesoul_config_builder_set_keep_deletes(IOConfigBuilder *builder,
+                                                          bool keep_deletes);
+
 IOConfigBuilder *lakesoul_config_builder_add_primary_keys(IOConfigBuilder *builder,
                                                           const char *const *pks,
                                                           c_size_t pk_num);
@@ -142,6 +218,8 @@ void write_record_batch(CResult<Writer> *writer,
 
 void flush_and_close_writer(CResult<Writer> *writer, ResultCallback callback);
 
+void flush_and_close_writer_with_result(CResult<Writer> *writer, FlushResultCallback callback);
+
 void abort_and_close_writer(CResult<Writer> *writer, ResultCallback callback);
 
 TokioRuntimeBuilder *new_tokio_runtime_builder();
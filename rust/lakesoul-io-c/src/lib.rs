@@ -7,7 +7,7 @@
 extern crate core;
 
 use core::ffi::{c_ptrdiff_t, c_size_t};
-use std::ffi::{c_char, c_void, CStr, CString};
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
 use std::ptr::NonNull;
 use std::slice;
 use std::sync::Arc;
@@ -22,7 +22,7 @@ use lakesoul_io::lakesoul_io_config::{LakeSoulIOConfig, LakeSoulIOConfigBuilder}
 use tokio::runtime::{Builder, Runtime};
 
 use lakesoul_io::lakesoul_reader::{LakeSoulReader, RecordBatch, Result, SyncSendableMutableLakeSoulReader};
-use lakesoul_io::lakesoul_writer::SyncSendableMutableLakeSoulWriter;
+use lakesoul_io::lakesoul_writer::{FlushResult, SyncSendableMutableLakeSoulWriter};
 
 #[repr(C)]
 pub struct CResult<OpaqueT> {
@@ -149,6 +149,48 @@ pub extern "C" fn lakesoul_config_builder_add_filter(
     }
 }
 
+/// Adds a filter in SQL, e.g. `amount > 100 AND region IN ('eu', 'us')`.
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_add_sql_filter(
+    builder: NonNull<IOConfigBuilder>,
+    sql_filter: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let sql_filter = CStr::from_ptr(sql_filter).to_str().unwrap().to_string();
+        convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_sql_filter(sql_filter))
+    }
+}
+
+/// Adds an output column computed in SQL, e.g. `amount * 2 AS doubled`.
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_add_sql_projection(
+    builder: NonNull<IOConfigBuilder>,
+    sql_projection: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let sql_projection = CStr::from_ptr(sql_projection).to_str().unwrap().to_string();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_sql_projection(sql_projection),
+        )
+    }
+}
+
+/// Adds filters from a serialized substrait `ExtendedExpression` of `len` bytes,
+/// which are converted against the read schema when the reader is created.
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_add_substrait_filter(
+    builder: NonNull<IOConfigBuilder>,
+    extended_expression: *const u8,
+    len: c_size_t,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let extended_expression = slice::from_raw_parts(extended_expression, len).to_vec();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_substrait_filter(extended_expression),
+        )
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn lakesoul_config_builder_set_schema(
     builder: NonNull<IOConfigBuilder>,
@@ -180,6 +222,26 @@ pub extern "C" fn lakesoul_config_builder_set_batch_size(
     convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_batch_size(batch_size))
 }
 
+/// Stops reading after `limit` rows, counted after merging primary keys and filtering.
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_limit(
+    builder: NonNull<IOConfigBuilder>,
+    limit: c_size_t,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_limit(limit))
+}
+
+/// Merges at most `merge_parallelism` ranges of the first primary key in parallel.
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_merge_parallelism(
+    builder: NonNull<IOConfigBuilder>,
+    merge_parallelism: c_size_t,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(
+        from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_merge_parallelism(merge_parallelism),
+    )
+}
+
 #[no_mangle]
 pub extern "C" fn lakesoul_config_builder_set_max_row_group_size(
     builder: NonNull<IOConfigBuilder>,
@@ -190,6 +252,144 @@ pub extern "C" fn lakesoul_config_builder_set_max_row_group_size(
     )
 }
 
+/// `level` < 0 means the default level of `codec`.
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_compression(
+    builder: NonNull<IOConfigBuilder>,
+    codec: *const c_char,
+    level: c_int,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let codec = CStr::from_ptr(codec).to_str().unwrap().to_string();
+        let level = if level < 0 { None } else { Some(level) };
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_compression(codec, level),
+        )
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_dictionary_enabled(
+    builder: NonNull<IOConfigBuilder>,
+    enabled: bool,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_dictionary_enabled(enabled))
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_column_dictionary_enabled(
+    builder: NonNull<IOConfigBuilder>,
+    column: *const c_char,
+    enabled: bool,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let column = CStr::from_ptr(column).to_str().unwrap().to_string();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder)
+                .with_column_dictionary_enabled(column, enabled),
+        )
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_statistics_enabled(
+    builder: NonNull<IOConfigBuilder>,
+    statistics_enabled: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let statistics_enabled = CStr::from_ptr(statistics_enabled).to_str().unwrap().to_string();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder)
+                .with_statistics_enabled(statistics_enabled),
+        )
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_data_page_size_limit(
+    builder: NonNull<IOConfigBuilder>,
+    data_page_size_limit: c_size_t,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(
+        from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder)
+            .with_data_page_size_limit(data_page_size_limit),
+    )
+}
+
+/// `fpp` <= 0 or `ndv` == 0 means the parquet default.
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_add_bloom_filter(
+    builder: NonNull<IOConfigBuilder>,
+    column: *const c_char,
+    fpp: f64,
+    ndv: u64,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let column = CStr::from_ptr(column).to_str().unwrap().to_string();
+        let fpp = if fpp > 0.0 { Some(fpp) } else { None };
+        let ndv = if ndv > 0 { Some(ndv) } else { None };
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_bloom_filter(column, fpp, ndv),
+        )
+    }
+}
+
+/// Writes rolling files under `prefix` instead of the single file added to the builder.
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_prefix(
+    builder: NonNull<IOConfigBuilder>,
+    prefix: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let prefix = CStr::from_ptr(prefix).to_str().unwrap().to_string();
+        convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_prefix(prefix))
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_max_file_size(
+    builder: NonNull<IOConfigBuilder>,
+    max_file_size: u64,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(
+        from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_max_file_size(max_file_size),
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_max_file_rows(
+    builder: NonNull<IOConfigBuilder>,
+    max_file_rows: u64,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(
+        from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_max_file_rows(max_file_rows),
+    )
+}
+
+/// Writes rows into hash buckets of primary keys, which requires a prefix set.
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_hash_bucket_num(
+    builder: NonNull<IOConfigBuilder>,
+    hash_bucket_num: c_size_t,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(
+        from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_hash_bucket_num(hash_bucket_num),
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_writer_version(
+    builder: NonNull<IOConfigBuilder>,
+    writer_version: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let writer_version = CStr::from_ptr(writer_version).to_str().unwrap().to_string();
+        convert_to_opaque(
+            from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_writer_version(writer_version),
+        )
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn lakesoul_config_builder_set_buffer_size(
     builder: NonNull<IOConfigBuilder>,
@@ -242,6 +442,18 @@ pub extern "C" fn lakesoul_config_builder_add_single_primary_key(
     }
 }
 
+/// Splits written rows by the range partition column into `col=value` directories under the prefix.
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_add_single_range_partition(
+    builder: NonNull<IOConfigBuilder>,
+    column: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let column = CStr::from_ptr(column).to_str().unwrap().to_string();
+        convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_range_partition(column))
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn lakesoul_config_builder_add_merge_op(
     builder: NonNull<IOConfigBuilder>,
@@ -257,6 +469,27 @@ pub extern "C" fn lakesoul_config_builder_add_merge_op(
     }
 }
 
+/// Drops keys whose last version is a delete by the cdc column of row kinds, e.g. `rowKinds`.
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_cdc_column(
+    builder: NonNull<IOConfigBuilder>,
+    cdc_column: *const c_char,
+) -> NonNull<IOConfigBuilder> {
+    unsafe {
+        let cdc_column = CStr::from_ptr(cdc_column).to_str().unwrap().to_string();
+        convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_cdc_column(cdc_column))
+    }
+}
+
+/// Keeps keys whose last version is a delete, for incremental reads.
+#[no_mangle]
+pub extern "C" fn lakesoul_config_builder_set_keep_deletes(
+    builder: NonNull<IOConfigBuilder>,
+    keep_deletes: bool,
+) -> NonNull<IOConfigBuilder> {
+    convert_to_opaque(from_opaque::<IOConfigBuilder, LakeSoulIOConfigBuilder>(builder).with_keep_deletes(keep_deletes))
+}
+
 #[no_mangle]
 pub extern "C" fn lakesoul_config_builder_add_primary_keys(
     builder: NonNull<IOConfigBuilder>,
@@ -350,6 +583,21 @@ fn call_data_result_callback(
     }
 }
 
+/// Called with the written file metadata in json, see [`flush_result_to_json`].
+pub type FlushResultCallback = extern "C" fn(bool, *const c_char, *const c_char);
+
+fn call_flush_result_callback(callback: FlushResultCallback, status: bool, err: *const c_char, result: *const c_char) {
+    callback(status, err, result);
+    // release error and result string
+    for s in [err, result] {
+        if !s.is_null() {
+            unsafe {
+                let _ = CString::from_raw(s as *mut c_char);
+            }
+        }
+    }
+}
+
 pub type I32ResultCallback = extern "C" fn(i32, *const c_char);
 pub type I32DataResultCallback = extern "C" fn(i32, *const c_char, *const c_void);
 
@@ -605,6 +853,38 @@ pub extern "C" fn write_record_batch(
     }
 }
 
+// serializes the metadata of written files as
+// [{"path": "...", "size": 1024, "num_rows": 10, "file_exist_cols": "a,b", "partition_desc": "date=20230101",
+//   "column_statistics": [{"name": "a", "min": "1", "max": "9", "null_count": 0}, ...]}, ...]
+// min and max are displayed as strings, and are null if unknown
+fn flush_result_to_json(results: &[FlushResult]) -> String {
+    results
+        .iter()
+        .map(|result| {
+            serde_json::json!({
+                "path": result.path,
+                "size": result.size,
+                "num_rows": result.num_rows,
+                "file_exist_cols": result.file_exist_cols,
+                "partition_desc": result.partition_desc,
+                "column_statistics": result
+                    .column_statistics
+                    .iter()
+                    .map(|stats| {
+                        serde_json::json!({
+                            "name": stats.name,
+                            "min": stats.min.as_ref().map(|min| min.to_string()),
+                            "max": stats.max.as_ref().map(|max| max.to_string()),
+                            "null_count": stats.null_count,
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect::<serde_json::Value>()
+        .to_string()
+}
+
 // consumes the writer pointer
 // this writer cannot be used again
 #[no_mangle]
@@ -624,6 +904,32 @@ pub extern "C" fn flush_and_close_writer(writer: NonNull<CResult<Writer>>, callb
     }
 }
 
+// same as flush_and_close_writer, but calls back with the metadata of written files
+// consumes the writer pointer
+// this writer cannot be used again
+#[no_mangle]
+pub extern "C" fn flush_and_close_writer_with_result(writer: NonNull<CResult<Writer>>, callback: FlushResultCallback) {
+    unsafe {
+        let writer =
+            from_opaque::<Writer, SyncSendableMutableLakeSoulWriter>(NonNull::new_unchecked(writer.as_ref().ptr));
+        let result = writer.flush_and_close();
+        match result {
+            Ok(result) => call_flush_result_callback(
+                callback,
+                true,
+                std::ptr::null(),
+                CString::new(flush_result_to_json(&result)).unwrap().into_raw(),
+            ),
+            Err(e) => call_flush_result_callback(
+                callback,
+                false,
+                CString::new(format!("{}", e).as_str()).unwrap().into_raw(),
+                std::ptr::null(),
+            ),
+        }
+    }
+}
+
 // consumes the writer pointer
 // this writer cannot be used again
 #[no_mangle]
@@ -693,11 +999,12 @@ pub extern "C" fn free_tokio_runtime(runtime: NonNull<CResult<TokioRuntime>>) {
 mod tests {
     use crate::{
         create_lakesoul_io_config_from_builder, create_lakesoul_reader_from_config, create_lakesoul_writer_from_config,
-        flush_and_close_writer, free_lakesoul_reader, lakesoul_config_builder_add_single_file,
-        lakesoul_config_builder_add_single_primary_key, lakesoul_config_builder_set_batch_size,
-        lakesoul_config_builder_set_max_row_group_size, lakesoul_config_builder_set_object_store_option,
-        lakesoul_config_builder_set_schema, lakesoul_config_builder_set_thread_num, lakesoul_reader_get_schema,
-        next_record_batch, start_reader, tokio_runtime_builder_set_thread_num, write_record_batch, IOConfigBuilder,
+        flush_and_close_writer, flush_and_close_writer_with_result, free_lakesoul_reader,
+        lakesoul_config_builder_add_single_file, lakesoul_config_builder_add_single_primary_key,
+        lakesoul_config_builder_set_batch_size, lakesoul_config_builder_set_max_row_group_size,
+        lakesoul_config_builder_set_object_store_option, lakesoul_config_builder_set_schema,
+        lakesoul_config_builder_set_thread_num, lakesoul_reader_get_schema, next_record_batch, start_reader,
+        tokio_runtime_builder_set_thread_num, write_record_batch, IOConfigBuilder,
     };
     use arrow::ffi::{FFI_ArrowArray, FFI_ArrowSchema};
     use core::ffi::c_ptrdiff_t;
@@ -791,6 +1098,17 @@ mod tests {
         }
     }
 
+    #[no_mangle]
+    pub extern "C" fn flush_callback(status: bool, err: *const c_char, result: *const c_char) {
+        unsafe {
+            assert!(status, "Writer {}", CStr::from_ptr(err).to_str().unwrap());
+            let results: serde_json::Value = serde_json::from_str(CStr::from_ptr(result).to_str().unwrap()).unwrap();
+            let result = &results.as_array().unwrap()[0];
+            assert!(result["path"].as_str().unwrap().ends_with(".parquet"));
+            assert!(result["num_rows"].as_u64().unwrap() > 0);
+        }
+    }
+
     #[test]
     fn test_native_read_write() {
         let mut reader_config_builder = crate::new_lakesoul_io_config_builder();
@@ -1047,7 +1365,7 @@ mod tests {
             }
         }
 
-        flush_and_close_writer(writer, writer_callback);
+        flush_and_close_writer_with_result(writer, flush_callback);
         free_lakesoul_reader(reader);
     }
 }
@@ -14,6 +14,11 @@ use lazy_static::lazy_static;
 pub static LAKESOUL_TIMEZONE: &str = "UTC";
 pub static LAKESOUL_NULL_STRING: &str = "__L@KE$OUL_NULL__";
 pub static LAKESOUL_EMPTY_STRING: &str = "__L@KE$OUL_EMPTY_STRING__";
+pub static LAKESOUL_NON_PARTITION_TABLE_PART_DESC: &str = "-5";
+pub static LAKESOUL_RANGE_PARTITION_SPLITTER: &str = ",";
+pub static LAKESOUL_PARTITION_DESC_KV_DELIM: &str = "=";
+pub static LAKESOUL_CDC_DELETE: &str = "delete";
+pub static LAKESOUL_CDC_CHANGE_COLUMN: &str = "lakesoul_cdc_change_column";
 
 lazy_static! {
     pub static ref ARROW_CAST_OPTIONS: CastOptions<'static> = CastOptions::default();
@@ -3,4 +3,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod parser;
-pub use parser::Parser;
\ No newline at end of file
+mod sql;
+pub(crate) mod stream;
+mod substrait;
+pub use parser::Parser;
+pub use sql::parse_sql_expr;
+pub use substrait::parse_substrait_filters;
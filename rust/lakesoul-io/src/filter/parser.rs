@@ -2,200 +2,541 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use std::ops::Not;
-use arrow_schema::{DataType, Field, SchemaRef};
-use datafusion::logical_expr::Expr;
-use datafusion::prelude::ident;
+use arrow::datatypes::i256;
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::{Expr, GetIndexedField};
+use datafusion::prelude::{ident, isnan, lit, starts_with};
 use datafusion::scalar::ScalarValue;
+use std::ops::Not;
 
 pub struct Parser {}
 
 impl Parser {
-    pub fn parse(filter_str: String, schema: SchemaRef) -> Expr {
-        let (op, left, right) = Parser::parse_filter_str(filter_str);
-        if op.eq("or") {
-            let left_expr = Parser::parse(left, schema.clone());
-            let right_expr = Parser::parse(right, schema);
-            left_expr.or(right_expr)
-        } else if op.eq("and") {
-            let left_expr = Parser::parse(left, schema.clone());
-            let right_expr = Parser::parse(right, schema);
-            left_expr.and(right_expr)
-        } else if op.eq("not") {
-            let inner = Parser::parse(right, schema);
-            Expr::not(inner)
-        } else {
-            let column = qualified_col_name(left.as_str(), schema.clone());
-            match schema.column_with_name(column) {
-                None => Expr::Literal(ScalarValue::Boolean(Some(true))),
-                Some((_, field)) => {
-                    if matches!(field.data_type(), DataType::Struct(_)) {
-                        ident(column).is_not_null()
-                    } else if right == "null" {
-                        match op.as_str() {
-                            "eq" => ident(column).is_null(),
-                            "noteq" => ident(column).is_not_null(),
-                            _ => Expr::Literal(ScalarValue::Boolean(Some(true))),
-                        }
-                    } else {
-                        match op.as_str() {
-                            "eq" => {
-                                let value = Parser::parse_literal(field, right);
-                                ident(column).eq(value)
-                            }
-                            "noteq" => {
-                                let value = Parser::parse_literal(field, right);
-                                ident(column).not_eq(value)
-                            }
-                            "gt" => {
-                                let value = Parser::parse_literal(field, right);
-                                ident(column).gt(value)
-                            }
-                            "gteq" => {
-                                let value = Parser::parse_literal(field, right);
-                                ident(column).gt_eq(value)
-                            }
-                            "lt" => {
-                                let value = Parser::parse_literal(field, right);
-                                ident(column).lt(value)
-                            }
-                            "lteq" => {
-                                let value = Parser::parse_literal(field, right);
-                                ident(column).lt_eq(value)
-                            }
-
-                            _ => Expr::Literal(ScalarValue::Boolean(Some(true))),
-                        }
-                    }
+    /// Parses a filter string like `and(gt(a, 1), not(in(b.c, ["x", "y"])))` into an expr on columns of `schema`.
+    ///
+    /// Supported operators are `and`, `or`, `not`, `eq`, `noteq`, `gt`, `gteq`, `lt`, `lteq`, `in`, `between`,
+    /// `startsWith`, `like` and `isNaN`. Columns of nested structs are referenced as `a.b.c`.
+    /// Strings are double quoted with `\` escapes, or wrapped as `Binary{"..."}` as printed by parquet-mr.
+    /// Errors carry the position in `filter_str` where parsing failed.
+    ///
+    /// User-defined predicates of parquet-mr, `userdefinedbyinstance` and `userdefinedbyclass`, can't be evaluated
+    /// here and keep every row they may match, since the engines evaluate them again.
+    pub fn parse(filter_str: String, schema: SchemaRef) -> Result<Expr> {
+        Parser::parse_at(&filter_str, 0, &filter_str, &schema, false)
+    }
+
+    // parses `filter`, which starts at `offset` of the whole filter string `origin`,
+    // under an odd number of `not` if `negated`
+    fn parse_at(filter: &str, offset: usize, origin: &str, schema: &Schema, negated: bool) -> Result<Expr> {
+        let (op, args) = Parser::parse_filter_str(filter, offset, origin)?;
+        let expect_args = |num: usize| {
+            if args.len() == num {
+                Ok(())
+            } else {
+                Err(filter_error(
+                    origin,
+                    offset,
+                    format!("{} expects {} arguments but got {}", op, num, args.len()),
+                ))
+            }
+        };
+        let literal = |field: &Field, (value_offset, value): (usize, &str)| {
+            Parser::parse_literal(field, value).map_err(|e| filter_error(origin, value_offset, e))
+        };
+        match op {
+            "or" | "and" => {
+                expect_args(2)?;
+                let left_expr = Parser::parse_at(args[0].1, args[0].0, origin, schema, negated)?;
+                let right_expr = Parser::parse_at(args[1].1, args[1].0, origin, schema, negated)?;
+                Ok(if op == "or" {
+                    left_expr.or(right_expr)
+                } else {
+                    left_expr.and(right_expr)
+                })
+            }
+            "not" => {
+                expect_args(1)?;
+                let inner = Parser::parse_at(args[0].1, args[0].0, origin, schema, !negated)?;
+                Ok(Expr::not(inner))
+            }
+            "eq" | "noteq" | "gt" | "gteq" | "lt" | "lteq" => {
+                expect_args(2)?;
+                let (column, field) = resolve_column(args[0], origin, schema)?;
+                if args[1].1 == "null" {
+                    return match op {
+                        "eq" => Ok(column.is_null()),
+                        "noteq" => Ok(column.is_not_null()),
+                        // parquet-mr rejects null values of ordering predicates, so they are never sent
+                        _ => Err(filter_error(
+                            origin,
+                            args[1].0,
+                            format!("null is not supported by {}", op),
+                        )),
+                    };
                 }
+                let value = literal(field, args[1])?;
+                Ok(match op {
+                    "eq" => column.eq(value),
+                    "noteq" => column.not_eq(value),
+                    "gt" => column.gt(value),
+                    "gteq" => column.gt_eq(value),
+                    "lt" => column.lt(value),
+                    _ => column.lt_eq(value),
+                })
             }
+            "in" => {
+                expect_args(2)?;
+                let (column, field) = resolve_column(args[0], origin, schema)?;
+                let (list_offset, list) = args[1];
+                let Some(items) = list.strip_prefix('[').and_then(|list| list.strip_suffix(']')) else {
+                    return Err(filter_error(origin, list_offset, "expected a list like [v1, v2]"));
+                };
+                let values = split_top_level(items)
+                    .map_err(|(pos, e)| filter_error(origin, list_offset + 1 + pos, e))?
+                    .into_iter()
+                    .map(|(pos, item)| match item {
+                        "null" => Ok(Expr::Literal(ScalarValue::try_from(field.data_type())?)),
+                        _ => literal(field, (list_offset + 1 + pos, item)),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                if values.is_empty() {
+                    return Ok(lit(false));
+                }
+                Ok(column.in_list(values, false))
+            }
+            "between" => {
+                expect_args(3)?;
+                let (column, field) = resolve_column(args[0], origin, schema)?;
+                let low = literal(field, args[1])?;
+                let high = literal(field, args[2])?;
+                Ok(column.between(low, high))
+            }
+            "startsWith" | "like" => {
+                expect_args(2)?;
+                let (column, field) = resolve_column(args[0], origin, schema)?;
+                let pattern = literal(field, args[1])?;
+                Ok(if op == "like" {
+                    column.like(pattern)
+                } else {
+                    starts_with(column, pattern)
+                })
+            }
+            "isNaN" => {
+                expect_args(1)?;
+                let (column, _) = resolve_column(args[0], origin, schema)?;
+                Ok(isnan(column))
+            }
+            // true keeps all rows, or false once negated, so that rows the predicate may match are never dropped
+            "userdefinedbyinstance" | "userdefinedbyclass" => Ok(lit(!negated)),
+            _ => Err(filter_error(
+                origin,
+                offset,
+                format!("unsupported filter operator {}", op),
+            )),
         }
     }
 
-    fn parse_filter_str(filter: String) -> (String, String, String) {
-        let op_offset = filter.find('(').unwrap();
-        let (op, filter) = filter.split_at(op_offset);
+    // splits `op(arg1, arg2, ...)` into the operator and its arguments with their offsets in `origin`
+    fn parse_filter_str<'a>(filter: &'a str, offset: usize, origin: &str) -> Result<(&'a str, Vec<(usize, &'a str)>)> {
+        let Some(open) = filter.find('(') else {
+            return Err(filter_error(origin, offset + filter.len(), "expected '('"));
+        };
         if !filter.ends_with(')') {
-            panic!("Invalid filter string");
-        }
-        let filter = &filter[1..filter.len() - 1];
-        let mut k: i8 = 0;
-        let mut left_offset: usize = 0;
-        for (i, ch) in filter.chars().enumerate() {
-            match ch {
-                '(' => k += 1,
-                ')' => k -= 1,
-                ',' => {
-                    if k == 0 && left_offset == 0 {
-                        left_offset = i
-                    }
-                }
-                _ => {}
-            }
-        }
-        if k != 0 {
-            panic!("Invalid filter string");
-        }
-        let (left, right) = filter.split_at(left_offset);
-        if op.eq("not") {
-            (op.to_string(), left.to_string(), right[0..].to_string())
-        } else {
-            (op.to_string(), left.to_string(), right[2..].to_string())
+            return Err(filter_error(origin, offset + filter.len(), "expected ')'"));
         }
+        let op = filter[..open].trim();
+        let inner_offset = offset + open + 1;
+        let args = split_top_level(&filter[open + 1..filter.len() - 1])
+            .map_err(|(pos, e)| filter_error(origin, inner_offset + pos, e))?
+            .into_iter()
+            .map(|(pos, arg)| (inner_offset + pos, arg))
+            .collect();
+        Ok((op, args))
     }
 
-    fn parse_literal(field: &Field, value: String) -> Expr {
+    fn parse_literal(field: &Field, value: &str) -> std::result::Result<Expr, String> {
         let data_type = field.data_type().clone();
-        match data_type {
+        let invalid = |e: &dyn std::fmt::Display| format!("invalid {} literal {}: {}", data_type, value, e);
+        macro_rules! parse_scalar {
+            ($scalar:ident, $native:ty) => {
+                ScalarValue::$scalar(Some(value.parse::<$native>().map_err(|e| invalid(&e))?))
+            };
+        }
+        let scalar = match &data_type {
             DataType::Decimal128(precision, scale) => {
-                if precision <= 18 {
-                    Expr::Literal(ScalarValue::Decimal128(
-                        Some(value.parse::<i128>().unwrap()),
-                        precision,
-                        scale,
-                    ))
-                } else {
-                    let binary_vec = Parser::parse_binary_array(value.as_str()).unwrap();
-                    let mut arr = [0u8; 16];
-                    for idx in 0..binary_vec.len() {
-                        arr[idx + 16 - binary_vec.len()] = binary_vec[idx];
+                // unscaled value, or the bytes of java's BigInteger.toByteArray for large precisions
+                let unscaled = match Parser::parse_binary_array(value)? {
+                    Some(bytes) => i128::from_be_bytes(sign_extend(&bytes).ok_or_else(|| invalid(&"too many bytes"))?),
+                    None => value.parse::<i128>().map_err(|e| invalid(&e))?,
+                };
+                ScalarValue::Decimal128(Some(unscaled), *precision, *scale)
+            }
+            DataType::Decimal256(precision, scale) => {
+                let unscaled = match Parser::parse_binary_array(value)? {
+                    Some(bytes) => i256::from_be_bytes(sign_extend(&bytes).ok_or_else(|| invalid(&"too many bytes"))?),
+                    None => i256::from_string(value).ok_or_else(|| invalid(&"not an integer"))?,
+                };
+                ScalarValue::Decimal256(Some(unscaled), *precision, *scale)
+            }
+            DataType::Boolean => parse_scalar!(Boolean, bool),
+            DataType::Binary => ScalarValue::Binary(Parser::parse_binary_array(value)?),
+            DataType::Float32 => parse_scalar!(Float32, f32),
+            DataType::Float64 => parse_scalar!(Float64, f64),
+            DataType::Int8 => parse_scalar!(Int8, i8),
+            DataType::Int16 => parse_scalar!(Int16, i16),
+            DataType::Int32 => parse_scalar!(Int32, i32),
+            DataType::Int64 => parse_scalar!(Int64, i64),
+            DataType::UInt8 => parse_scalar!(UInt8, u8),
+            DataType::UInt16 => parse_scalar!(UInt16, u16),
+            DataType::UInt32 => parse_scalar!(UInt32, u32),
+            DataType::UInt64 => parse_scalar!(UInt64, u64),
+            // days since epoch
+            DataType::Date32 => parse_scalar!(Date32, i32),
+            // milliseconds since epoch
+            DataType::Date64 => parse_scalar!(Date64, i64),
+            // microseconds since epoch as sent by the java engines, converted into the unit of the column
+            DataType::Timestamp(unit, time_zone) => {
+                let micros = value.parse::<i64>().map_err(|e| invalid(&e))?;
+                match unit {
+                    TimeUnit::Second => {
+                        ScalarValue::TimestampSecond(Some(micros.div_euclid(1_000_000)), time_zone.clone())
+                    }
+                    TimeUnit::Millisecond => {
+                        ScalarValue::TimestampMillisecond(Some(micros.div_euclid(1_000)), time_zone.clone())
                     }
-                    Expr::Literal(ScalarValue::Decimal128(
-                        Some(i128::from_be_bytes(arr)),
-                        precision,
-                        scale,
-                    ))
+                    TimeUnit::Microsecond => ScalarValue::TimestampMicrosecond(Some(micros), time_zone.clone()),
+                    TimeUnit::Nanosecond => ScalarValue::TimestampNanosecond(
+                        Some(micros.checked_mul(1_000).ok_or_else(|| invalid(&"out of range"))?),
+                        time_zone.clone(),
+                    ),
                 }
             }
-            DataType::Boolean => Expr::Literal(ScalarValue::Boolean(Some(value.parse::<bool>().unwrap()))),
-            DataType::Binary => Expr::Literal(ScalarValue::Binary(Parser::parse_binary_array(value.as_str()))),
-            DataType::Float32 => Expr::Literal(ScalarValue::Float32(Some(value.parse::<f32>().unwrap()))),
-            DataType::Float64 => Expr::Literal(ScalarValue::Float64(Some(value.parse::<f64>().unwrap()))),
-            DataType::Int8 => Expr::Literal(ScalarValue::Int8(Some(value.parse::<i8>().unwrap()))),
-            DataType::Int16 => Expr::Literal(ScalarValue::Int16(Some(value.parse::<i16>().unwrap()))),
-            DataType::Int32 => Expr::Literal(ScalarValue::Int32(Some(value.parse::<i32>().unwrap()))),
-            DataType::Int64 => Expr::Literal(ScalarValue::Int64(Some(value.parse::<i64>().unwrap()))),
-            DataType::Date32 => Expr::Literal(ScalarValue::Date32(Some(value.parse::<i32>().unwrap()))),
-            DataType::Timestamp(_, time_zone) => Expr::Literal(ScalarValue::TimestampMicrosecond(
-                Some(value.parse::<i64>().unwrap()),
-                time_zone,
-            )),
-            DataType::Utf8 => {
-                let value = value.as_str()[8..value.len() - 2].to_string();
-                Expr::Literal(ScalarValue::Utf8(Some(value)))
+            DataType::Utf8 => ScalarValue::Utf8(Some(parse_string(value).map_err(|e| invalid(&e))?)),
+            DataType::LargeUtf8 => ScalarValue::LargeUtf8(Some(parse_string(value).map_err(|e| invalid(&e))?)),
+            _ => {
+                return Err(format!(
+                    "unsupported literal type {} of column {}",
+                    data_type,
+                    field.name()
+                ))
             }
-            _ => Expr::Literal(ScalarValue::Utf8(Some(value))),
-        }
+        };
+        Ok(Expr::Literal(scalar))
     }
 
-    fn parse_binary_array(value: &str) -> Option<Vec<u8>> {
-        let left_bracket_pos = value.find('[').unwrap_or(0);
-        let right_bracket_pos = value.find(']').unwrap_or(0);
-        if left_bracket_pos == 0 {
-            None
+    // parses bytes like `Binary{3 bytes, [1, 2, -1]}`, returning None if there are no brackets
+    fn parse_binary_array(value: &str) -> std::result::Result<Option<Vec<u8>>, String> {
+        let Some(left_bracket_pos) = value.find('[') else {
+            return Ok(None);
+        };
+        let right_bracket_pos = value.rfind(']').unwrap_or(0);
+        if right_bracket_pos < left_bracket_pos {
+            Err(format!("unclosed binary array {}", value))
         } else if left_bracket_pos + 1 == right_bracket_pos {
-            Some(Vec::<u8>::new())
+            Ok(Some(Vec::<u8>::new()))
         } else {
-            Some(
-                value[left_bracket_pos + 1..right_bracket_pos]
-                    .to_string()
-                    .replace(' ', "")
-                    .split(',')
-                    .collect::<Vec<&str>>()
-                    .iter()
-                    .map(|s| s.parse::<i16>().unwrap())
-                    .map(|s: i16| if s < 0 { (s + 256) as u8 } else { s as u8 })
-                    .collect::<Vec<u8>>(),
-            )
+            value[left_bracket_pos + 1..right_bracket_pos]
+                .split(',')
+                .map(|s| {
+                    s.trim()
+                        .parse::<i16>()
+                        .map_err(|e| format!("invalid byte {} in binary array {}: {}", s, value, e))
+                })
+                .map(|s| s.map(|s: i16| if s < 0 { (s + 256) as u8 } else { s as u8 }))
+                .collect::<std::result::Result<Vec<u8>, String>>()
+                .map(Some)
         }
     }
 }
 
-fn qualified_col_name(column: &str, schema: SchemaRef) -> &str {
-    if let Ok(_field) = schema.field_with_name(column) {
-        return column;
-    } else if let Some(dot) = column.find('.') {
-        if let Ok(field) = schema.field_with_name(&column[..dot]) {
-            if matches!(field.data_type(), DataType::Struct(_)) {
-                return &column[..dot];
+fn filter_error(origin: &str, position: usize, message: impl std::fmt::Display) -> DataFusionError {
+    DataFusionError::Plan(format!(
+        "Invalid filter \"{}\" at position {}: {}",
+        origin, position, message
+    ))
+}
+
+// resolves `a.b.c` to a top level column named `a.b.c` if there is one, otherwise to field `c` of struct field `b`
+// of struct column `a`
+fn resolve_column<'a>((offset, name): (usize, &str), origin: &str, schema: &'a Schema) -> Result<(Expr, &'a Field)> {
+    if let Ok(field) = schema.field_with_name(name) {
+        return Ok((ident(name), field));
+    }
+    let unknown = || filter_error(origin, offset, format!("unknown column {}", name));
+    let mut parts = name.split('.');
+    let first = parts.next().ok_or_else(unknown)?;
+    let mut field = schema.field_with_name(first).map_err(|_| unknown())?;
+    let mut expr = ident(first);
+    for part in parts {
+        let DataType::Struct(children) = field.data_type() else {
+            return Err(unknown());
+        };
+        field = children
+            .iter()
+            .find(|child| child.name() == part)
+            .ok_or_else(unknown)?
+            .as_ref();
+        expr = Expr::GetIndexedField(GetIndexedField::new(
+            Box::new(expr),
+            ScalarValue::Utf8(Some(part.to_string())),
+        ));
+    }
+    Ok((expr, field))
+}
+
+#[derive(PartialEq)]
+enum Quote {
+    None,
+    // "..." with backslash escapes
+    Escaped,
+    // Binary{"..."} of parquet-mr, which is not escaped and ends at "}
+    Binary,
+}
+
+// splits `s` at commas outside of brackets and string literals,
+// returning the trimmed parts with their offsets in `s`, or the position and reason of a syntax error
+fn split_top_level(s: &str) -> std::result::Result<Vec<(usize, &str)>, (usize, &'static str)> {
+    if s.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    let bytes = s.as_bytes();
+    let mut depth: i32 = 0;
+    let mut quote = Quote::None;
+    let mut escaped = false;
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut push_part = |start: usize, end: usize| {
+        let part = &s[start..end];
+        parts.push((start + part.len() - part.trim_start().len(), part.trim()));
+    };
+    for (i, ch) in s.char_indices() {
+        match quote {
+            Quote::Binary => {
+                if ch == '"' && bytes.get(i + 1) == Some(&b'}') {
+                    quote = Quote::None;
+                }
+            }
+            Quote::Escaped => {
+                if escaped {
+                    escaped = false;
+                } else if ch == '\\' {
+                    escaped = true;
+                } else if ch == '"' {
+                    quote = Quote::None;
+                }
             }
+            Quote::None => match ch {
+                '"' if i > 0 && bytes[i - 1] == b'{' => quote = Quote::Binary,
+                '"' => quote = Quote::Escaped,
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => {
+                    depth -= 1;
+                    if depth < 0 {
+                        return Err((i, "unmatched closing bracket"));
+                    }
+                }
+                ',' if depth == 0 => {
+                    push_part(start, i);
+                    start = i + 1;
+                }
+                _ => {}
+            },
+        }
+    }
+    if quote != Quote::None {
+        return Err((s.len(), "unclosed string literal"));
+    }
+    if depth != 0 {
+        return Err((s.len(), "unclosed bracket"));
+    }
+    push_part(start, s.len());
+    Ok(parts)
+}
+
+// `"..."` with backslash escapes, or `Binary{"..."}`
+fn parse_string(value: &str) -> std::result::Result<String, &'static str> {
+    if let Some(binary) = value.strip_prefix("Binary{\"").and_then(|v| v.strip_suffix("\"}")) {
+        return Ok(binary.to_string());
+    }
+    let Some(quoted) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        return Err("expected a double quoted string");
+    };
+    let mut result = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => result.push(chars.next().ok_or("dangling escape")?),
+            _ => result.push(ch),
         }
     }
-    column
+    Ok(result)
+}
+
+// sign extends big endian two's complement bytes to N bytes
+fn sign_extend<const N: usize>(bytes: &[u8]) -> Option<[u8; N]> {
+    if bytes.len() > N {
+        return None;
+    }
+    let mut extended = if bytes.first().map_or(false, |byte| byte & 0x80 != 0) {
+        [0xffu8; N]
+    } else {
+        [0u8; N]
+    };
+    extended[N - bytes.len()..].copy_from_slice(bytes);
+    Some(extended)
 }
 
 #[cfg(test)]
 mod tests {
     use crate::filter::Parser;
+    use arrow::datatypes::i256;
+    use arrow_schema::{DataType, Field, Fields, Schema, TimeUnit};
+    use datafusion::logical_expr::{Expr, GetIndexedField};
+    use datafusion::prelude::{col, isnan, lit, starts_with};
+    use datafusion::scalar::ScalarValue;
+    use std::ops::Not;
     use std::result::Result;
+    use std::sync::Arc;
 
     #[test]
     fn test_filter_parser() -> Result<(), String> {
-        let s = String::from("or(lt(a.b.c, 2.0), gt(a.b.c, 3.0))");
-        let (op, left, right) = Parser::parse_filter_str(s);
+        let s = "or(lt(a.b.c, 2.0), gt(a.b.c, 3.0))";
+        let (op, args) = Parser::parse_filter_str(s, 0, s).map_err(|e| e.to_string())?;
         assert_eq!(op, "or");
-        assert_eq!(left, "lt(a.b.c, 2.0)");
-        assert_eq!(right, "gt(a.b.c, 3.0)");
+        assert_eq!(args, vec![(3, "lt(a.b.c, 2.0)"), (19, "gt(a.b.c, 3.0)")]);
+
+        let s = "in(a, [\"x,)\", Binary{\"y\"}])";
+        let (op, args) = Parser::parse_filter_str(s, 0, s).map_err(|e| e.to_string())?;
+        assert_eq!(op, "in");
+        assert_eq!(args, vec![(3, "a"), (6, "[\"x,)\", Binary{\"y\"}]")]);
+        Ok(())
+    }
+
+    fn test_schema() -> Arc<Schema> {
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+            Field::new(
+                "a",
+                DataType::Struct(Fields::from(vec![Field::new(
+                    "b",
+                    DataType::Struct(Fields::from(vec![Field::new("c", DataType::Int32, true)])),
+                    true,
+                )])),
+                true,
+            ),
+            Field::new(
+                "l",
+                DataType::List(Arc::new(Field::new("item", DataType::Int32, true))),
+                true,
+            ),
+            Field::new("f", DataType::Float64, true),
+            Field::new("u", DataType::UInt32, true),
+            Field::new("ts", DataType::Timestamp(TimeUnit::Millisecond, None), true),
+            Field::new("d", DataType::Decimal256(40, 2), true),
+            Field::new("s", DataType::LargeUtf8, true),
+        ]))
+    }
+
+    #[test]
+    fn test_filter_parser_operators() -> Result<(), String> {
+        let schema = test_schema();
+        let parse = |filter: &str| Parser::parse(filter.to_string(), schema.clone()).map_err(|e| e.to_string());
+
+        assert_eq!(
+            parse("and(gt(id, 1), eq(name, Binary{\"a,b)\"}))")?,
+            col("id").gt(lit(1_i64)).and(col("name").eq(lit("a,b)")))
+        );
+        assert_eq!(parse("not(eq(name, null))")?, col("name").is_null().not());
+        assert_eq!(
+            parse("in(name, [\"a\", \"b\\\"\", null])")?,
+            col("name").in_list(
+                vec![lit("a"), lit("b\""), Expr::Literal(ScalarValue::Utf8(None))],
+                false
+            )
+        );
+        assert_eq!(parse("in(id, [])")?, lit(false));
+        assert_eq!(parse("between(u, 1, 10)")?, col("u").between(lit(1_u32), lit(10_u32)));
+        assert_eq!(parse("startsWith(name, \"ab\")")?, starts_with(col("name"), lit("ab")));
+        assert_eq!(
+            parse("like(s, \"a%\")")?,
+            col("s").like(Expr::Literal(ScalarValue::LargeUtf8(Some("a%".to_string()))))
+        );
+        assert_eq!(parse("isNaN(f)")?, isnan(col("f")));
+        // string prefixes are pushed by spark as user-defined predicates
+        let starts_with_udp = "userdefinedbyinstance(name, \
+            org.apache.spark.sql.execution.datasources.parquet.ParquetFilters$$anon$1@6a2f6f80)";
+        assert_eq!(
+            parse(&format!("and(noteq(name, null), {})", starts_with_udp))?,
+            col("name").is_not_null().and(lit(true))
+        );
+        assert_eq!(
+            parse(&format!("not(or({}, eq(id, 1)))", starts_with_udp))?,
+            lit(false).or(col("id").eq(lit(1_i64))).not()
+        );
+        assert_eq!(
+            parse("userdefinedbyclass(a.b.c, org.apache.parquet.filter2.predicate.UserDefinedPredicate)")?,
+            lit(true)
+        );
+        assert_eq!(
+            parse("gteq(ts, 1000000)")?,
+            col("ts").gt_eq(Expr::Literal(ScalarValue::TimestampMillisecond(Some(1000), None)))
+        );
+        assert_eq!(
+            parse("eq(d, Binary{2 bytes, [-1, 0]})")?,
+            col("d").eq(Expr::Literal(ScalarValue::Decimal256(
+                Some(i256::from_i128(-256)),
+                40,
+                2
+            )))
+        );
+        let get_field = |expr: Expr, name: &str| {
+            Expr::GetIndexedField(GetIndexedField::new(
+                Box::new(expr),
+                ScalarValue::Utf8(Some(name.to_string())),
+            ))
+        };
+        assert_eq!(
+            parse("lt(a.b.c, 3)")?,
+            get_field(get_field(col("a"), "b"), "c").lt(lit(3_i32))
+        );
+        assert_eq!(parse("eq(a.b, null)")?, get_field(col("a"), "b").is_null());
         Ok(())
     }
+
+    #[test]
+    fn test_filter_parser_errors() {
+        let schema = test_schema();
+        let parse = |filter: &str| Parser::parse(filter.to_string(), schema.clone()).map_err(|e| e.to_string());
+
+        for (filter, position, message) in [
+            ("and(gt(id, 1), eq(nam, 1))", 18, "unknown column nam"),
+            ("gt(a.x, 1)", 3, "unknown column a.x"),
+            ("gt(id, x1)", 7, "invalid Int64 literal x1"),
+            ("gt(id, 1", 8, "expected ')'"),
+            ("and(gt(id, 1)", 12, "unclosed bracket"),
+            ("and(gt(id, 1))", 0, "and expects 2 arguments but got 1"),
+            ("gt(id, 1))", 8, "unmatched closing bracket"),
+            ("eq(name, \"abc)", 13, "unclosed string literal"),
+            ("gt(id, null)", 7, "null is not supported by gt"),
+            ("in(id, 1)", 7, "expected a list"),
+            ("in(id, [1, x])", 11, "invalid Int64 literal x"),
+            ("nin(id, [1])", 0, "unsupported filter operator nin"),
+            ("eq(l, 1)", 6, "unsupported literal type"),
+            (
+                "eq(name, abc)",
+                9,
+                "invalid Utf8 literal abc: expected a double quoted string",
+            ),
+        ] {
+            let err = parse(filter).expect_err(filter);
+            assert!(
+                err.contains(&format!("at position {}: {}", position, message)),
+                "{}: {}",
+                filter,
+                err
+            );
+        }
+    }
 }
@@ -0,0 +1,71 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! SQL text filters and computed projections, planned by datafusion against the read schema.
+
+use std::sync::Arc;
+
+use arrow_schema::SchemaRef;
+use datafusion::datasource::empty::EmptyTable;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::expr_rewriter::unnormalize_col;
+use datafusion::logical_expr::{Expr, LogicalPlan};
+use datafusion::prelude::SessionContext;
+
+const SQL_TABLE_NAME: &str = "lakesoul_reader";
+
+/// Plans a single SQL expression on columns of `schema`, e.g. `amount > 100 AND region IN ('eu', 'us')`
+/// or `amount * 2 AS doubled`. Unquoted identifiers are lower cased as in datafusion SQL.
+pub fn parse_sql_expr(sql: &str, schema: SchemaRef) -> Result<Expr> {
+    let ctx = SessionContext::new();
+    ctx.register_table(SQL_TABLE_NAME, Arc::new(EmptyTable::new(schema)))?;
+    // planning only resolves the registered empty table, so the future completes without any io
+    let plan = futures::executor::block_on(
+        ctx.state()
+            .create_logical_plan(&format!("SELECT {} FROM {}", sql, SQL_TABLE_NAME)),
+    )?;
+    match plan {
+        LogicalPlan::Projection(projection)
+            if projection.expr.len() == 1 && matches!(projection.input.as_ref(), LogicalPlan::TableScan(_)) =>
+        {
+            // columns are qualified by the planning table, while they are matched by name in reads
+            Ok(unnormalize_col(projection.expr[0].clone()))
+        }
+        _ => Err(DataFusionError::Plan(format!(
+            "Invalid SQL expression \"{}\": expected a single scalar expression",
+            sql
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_schema::{DataType, Field, Schema};
+    use datafusion::prelude::{col, lit};
+
+    #[test]
+    fn test_parse_sql_expr() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("amount", DataType::Int64, false),
+            Field::new("region", DataType::Utf8, true),
+        ]));
+        assert_eq!(
+            parse_sql_expr("amount > 100 AND region IN ('eu', 'us')", schema.clone())?,
+            col("amount")
+                .gt(lit(100_i64))
+                .and(col("region").in_list(vec![lit("eu"), lit("us")], false))
+        );
+        assert_eq!(
+            parse_sql_expr("amount * 2 AS doubled", schema.clone())?,
+            (col("amount") * lit(2_i64)).alias("doubled")
+        );
+
+        assert!(parse_sql_expr("missing > 1", schema.clone()).is_err());
+        assert!(parse_sql_expr("amount, region", schema.clone()).is_err());
+        assert!(parse_sql_expr("sum(amount)", schema.clone()).is_err());
+        assert!(parse_sql_expr("amount FROM lakesoul_reader WHERE amount > 1", schema).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::{Debug, Formatter};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use arrow::compute::filter_record_batch;
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use arrow_array::cast::as_boolean_array;
+use futures::{Stream, StreamExt};
+
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::utils::conjunction;
+use datafusion::logical_expr::Expr;
+use datafusion::optimizer::simplify_expressions::{ExprSimplifier, SimplifyContext};
+use datafusion::physical_expr::execution_props::ExecutionProps;
+use datafusion::physical_expr::{create_physical_expr, PhysicalExpr};
+use datafusion::physical_plan::{RecordBatchStream, SendableRecordBatchStream};
+use datafusion_common::DFSchema;
+
+/// Evaluates row filters on an already produced stream, e.g. the output of a primary key merge,
+/// where filters cannot be pushed down into the file scans.
+pub(crate) struct FilterStream {
+    schema: SchemaRef,
+
+    predicate: Arc<dyn PhysicalExpr>,
+
+    inner_stream: SendableRecordBatchStream,
+}
+
+impl Debug for FilterStream {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.debug_struct("FilterStream")
+            .field("schema", &self.schema)
+            .field("predicate", &self.predicate)
+            .finish()
+    }
+}
+
+impl FilterStream {
+    /// Wraps `stream` with the conjunction of `filters`. Returns the input stream unchanged when there is no filter.
+    pub(crate) fn try_new(stream: SendableRecordBatchStream, filters: Vec<Expr>) -> Result<SendableRecordBatchStream> {
+        let Some(filter) = conjunction(filters) else {
+            return Ok(stream);
+        };
+        let schema = stream.schema();
+        let df_schema = Arc::new(DFSchema::try_from(schema.as_ref().clone())?);
+        let props = ExecutionProps::new();
+        let simplifier = ExprSimplifier::new(SimplifyContext::new(&props).with_schema(df_schema.clone()));
+        let filter = simplifier.coerce(filter, df_schema.clone())?;
+        let predicate = create_physical_expr(&filter, &df_schema, &schema, &props)?;
+        Ok(Box::pin(FilterStream {
+            schema,
+            predicate,
+            inner_stream: stream,
+        }))
+    }
+
+    fn filter_batch(&self, batch: RecordBatch) -> Result<RecordBatch> {
+        let mask = self.predicate.evaluate(&batch)?.into_array(batch.num_rows());
+        if mask.data_type() != &arrow_schema::DataType::Boolean {
+            return Err(DataFusionError::Internal(format!(
+                "Filter predicate {} is not evaluated to boolean",
+                self.predicate
+            )));
+        }
+        Ok(filter_record_batch(&batch, as_boolean_array(&mask))?)
+    }
+}
+
+impl Stream for FilterStream {
+    type Item = Result<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            return match futures::ready!(self.inner_stream.poll_next_unpin(cx)) {
+                None => Poll::Ready(None),
+                Some(Err(e)) => Poll::Ready(Some(Err(e))),
+                Some(Ok(batch)) => match self.filter_batch(batch) {
+                    // skip batches fully filtered out
+                    Ok(batch) if batch.num_rows() == 0 => continue,
+                    result => Poll::Ready(Some(result)),
+                },
+            };
+        }
+    }
+}
+
+impl RecordBatchStream for FilterStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
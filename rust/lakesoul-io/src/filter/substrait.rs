@@ -0,0 +1,157 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Filters pushed down as serialized substrait `ExtendedExpression`s.
+
+use std::collections::HashMap;
+
+use arrow_schema::SchemaRef;
+use datafusion::common::DFSchema;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::Expr;
+use datafusion_substrait::logical_plan::consumer::from_substrait_rex;
+use datafusion_substrait::substrait::proto::extensions::simple_extension_declaration::MappingType;
+use datafusion_substrait::substrait::proto::extensions::SimpleExtensionDeclaration;
+use datafusion_substrait::substrait::proto::Expression;
+use prost::Message;
+
+/// Wire compatible subset of substrait's `ExtendedExpression` message,
+/// so that decoding doesn't depend on the substrait version bundled with datafusion.
+/// Extension uris, the base schema and aggregate measures are skipped.
+#[derive(Clone, PartialEq, Message)]
+pub struct ExtendedExpression {
+    #[prost(message, repeated, tag = "2")]
+    pub extensions: Vec<SimpleExtensionDeclaration>,
+    #[prost(message, repeated, tag = "3")]
+    pub referred_expr: Vec<ExpressionReference>,
+}
+
+/// Wire compatible subset of substrait's `ExpressionReference`, without the `measure` case of `expr_type`.
+#[derive(Clone, PartialEq, Message)]
+pub struct ExpressionReference {
+    #[prost(message, optional, tag = "1")]
+    pub expression: Option<Expression>,
+    #[prost(string, repeated, tag = "3")]
+    pub output_names: Vec<String>,
+}
+
+/// Decodes a serialized `ExtendedExpression` and converts each of its expressions into a filter.
+/// Field references are resolved by index against `schema`, i.e. the read schema,
+/// so the base schema of the expression should have the same top level fields in the same order.
+pub fn parse_substrait_filters(extended_expression: &[u8], schema: SchemaRef) -> Result<Vec<Expr>> {
+    let extended_expression = ExtendedExpression::decode(extended_expression)
+        .map_err(|e| DataFusionError::Plan(format!("invalid substrait extended expression: {}", e)))?;
+    let functions = extended_expression
+        .extensions
+        .iter()
+        .filter_map(|extension| match &extension.mapping_type {
+            Some(MappingType::ExtensionFunction(function)) => Some((function.function_anchor, &function.name)),
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+    let df_schema = DFSchema::try_from(schema.as_ref().clone())?;
+    extended_expression
+        .referred_expr
+        .iter()
+        .map(|reference| {
+            let expression = reference.expression.as_ref().ok_or_else(|| {
+                DataFusionError::Plan("only expressions of substrait extended expressions are filters".to_string())
+            })?;
+            // the conversion is async but never awaits any io, so it completes on the first poll
+            let expr = futures::executor::block_on(from_substrait_rex(expression, &df_schema, &functions))?;
+            Ok(expr.as_ref().clone())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_schema::{DataType, Field, Schema};
+    use datafusion::prelude::{col, lit};
+    use datafusion_substrait::substrait::proto::expression::field_reference::ReferenceType;
+    use datafusion_substrait::substrait::proto::expression::literal::LiteralType;
+    use datafusion_substrait::substrait::proto::expression::reference_segment::{self, StructField};
+    use datafusion_substrait::substrait::proto::expression::{
+        FieldReference, Literal, ReferenceSegment, RexType, ScalarFunction,
+    };
+    use datafusion_substrait::substrait::proto::extensions::simple_extension_declaration::ExtensionFunction;
+    use datafusion_substrait::substrait::proto::function_argument::ArgType;
+    use datafusion_substrait::substrait::proto::FunctionArgument;
+    use std::sync::Arc;
+
+    fn field(index: i32) -> Expression {
+        Expression {
+            rex_type: Some(RexType::Selection(Box::new(FieldReference {
+                reference_type: Some(ReferenceType::DirectReference(ReferenceSegment {
+                    reference_type: Some(reference_segment::ReferenceType::StructField(Box::new(StructField {
+                        field: index,
+                        child: None,
+                    }))),
+                })),
+                root_type: None,
+            }))),
+        }
+    }
+
+    fn function(anchor: u32, args: Vec<Expression>) -> Expression {
+        Expression {
+            rex_type: Some(RexType::ScalarFunction(ScalarFunction {
+                function_reference: anchor,
+                arguments: args
+                    .into_iter()
+                    .map(|arg| FunctionArgument {
+                        arg_type: Some(ArgType::Value(arg)),
+                    })
+                    .collect(),
+                ..Default::default()
+            })),
+        }
+    }
+
+    #[test]
+    fn test_parse_substrait_filters() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("value", DataType::Int64, true),
+        ]));
+        let extension = |anchor: u32, name: &str| SimpleExtensionDeclaration {
+            mapping_type: Some(MappingType::ExtensionFunction(ExtensionFunction {
+                function_anchor: anchor,
+                name: name.to_string(),
+                ..Default::default()
+            })),
+        };
+        let literal = Expression {
+            rex_type: Some(RexType::Literal(Literal {
+                literal_type: Some(LiteralType::I64(1)),
+                ..Default::default()
+            })),
+        };
+        let extended_expression = ExtendedExpression {
+            extensions: vec![extension(1, "gt"), extension(2, "equal")],
+            referred_expr: vec![
+                ExpressionReference {
+                    expression: Some(function(1, vec![field(0), literal])),
+                    output_names: vec![],
+                },
+                ExpressionReference {
+                    expression: Some(function(2, vec![field(1), field(0)])),
+                    output_names: vec![],
+                },
+            ],
+        };
+
+        let filters = parse_substrait_filters(&extended_expression.encode_to_vec(), schema.clone())?;
+        assert_eq!(filters, vec![col("id").gt(lit(1_i64)), col("value").eq(col("id"))]);
+
+        assert!(parse_substrait_filters(&[0xff, 0xff], schema.clone()).is_err());
+        let missing_expression = ExtendedExpression {
+            extensions: vec![],
+            referred_expr: vec![ExpressionReference::default()],
+        };
+        assert!(parse_substrait_filters(&missing_expression.encode_to_vec(), schema).is_err());
+        Ok(())
+    }
+}
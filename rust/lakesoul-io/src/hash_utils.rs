@@ -0,0 +1,237 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Spark compatible murmur3 hash, the same as `org.apache.spark.sql.catalyst.expressions.Murmur3Hash`,
+//! which LakeSoul uses on the JVM side to assign rows of primary key tables to hash buckets.
+
+use arrow::array::{
+    as_boolean_array, as_largestring_array, as_primitive_array, as_string_array, Array, ArrayRef, BinaryArray,
+    LargeBinaryArray,
+};
+use arrow::datatypes::{
+    DataType, Date32Type, Decimal128Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type,
+    TimeUnit, TimestampMicrosecondType,
+};
+use datafusion::error::{DataFusionError, Result};
+
+/// Seed of Spark's `hash` function and hash partitioning.
+pub const SPARK_MURMUR3_SEED: i32 = 42;
+
+const C1: i32 = 0xcc9e2d51_u32 as i32;
+const C2: i32 = 0x1b873593_u32 as i32;
+
+fn mix_k1(k1: i32) -> i32 {
+    k1.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2)
+}
+
+fn mix_h1(h1: i32, k1: i32) -> i32 {
+    (h1 ^ k1)
+        .rotate_left(13)
+        .wrapping_mul(5)
+        .wrapping_add(0xe6546b64_u32 as i32)
+}
+
+fn fmix(h1: i32, length: i32) -> i32 {
+    let mut h1 = (h1 ^ length) as u32;
+    h1 ^= h1 >> 16;
+    h1 = h1.wrapping_mul(0x85ebca6b);
+    h1 ^= h1 >> 13;
+    h1 = h1.wrapping_mul(0xc2b2ae35);
+    h1 ^= h1 >> 16;
+    h1 as i32
+}
+
+pub fn hash_int(input: i32, seed: i32) -> i32 {
+    fmix(mix_h1(seed, mix_k1(input)), 4)
+}
+
+pub fn hash_long(input: i64, seed: i32) -> i32 {
+    let low = input as i32;
+    let high = ((input as u64) >> 32) as i32;
+    let h1 = mix_h1(seed, mix_k1(low));
+    fmix(mix_h1(h1, mix_k1(high)), 8)
+}
+
+pub fn hash_bytes(bytes: &[u8], seed: i32) -> i32 {
+    let aligned = bytes.len() - bytes.len() % 4;
+    let mut h1 = seed;
+    for chunk in bytes[..aligned].chunks_exact(4) {
+        h1 = mix_h1(h1, mix_k1(i32::from_le_bytes(chunk.try_into().unwrap())));
+    }
+    // unlike the standard murmur3, spark mixes each trailing byte as a sign extended int
+    for byte in &bytes[aligned..] {
+        h1 = mix_h1(h1, mix_k1(*byte as i8 as i32));
+    }
+    fmix(h1, bytes.len() as i32)
+}
+
+fn hash_boolean(input: bool, seed: i32) -> i32 {
+    hash_int(input as i32, seed)
+}
+
+fn hash_byte(input: i8, seed: i32) -> i32 {
+    hash_int(input as i32, seed)
+}
+
+fn hash_short(input: i16, seed: i32) -> i32 {
+    hash_int(input as i32, seed)
+}
+
+fn hash_string(input: &str, seed: i32) -> i32 {
+    hash_bytes(input.as_bytes(), seed)
+}
+
+fn hash_float(input: f32, seed: i32) -> i32 {
+    // -0.0 is hashed as 0, and NaNs as the canonical NaN of java's floatToIntBits
+    if input == 0.0 {
+        hash_int(0, seed)
+    } else if input.is_nan() {
+        hash_int(0x7fc00000, seed)
+    } else {
+        hash_int(input.to_bits() as i32, seed)
+    }
+}
+
+fn hash_double(input: f64, seed: i32) -> i32 {
+    if input == 0.0 {
+        hash_long(0, seed)
+    } else if input.is_nan() {
+        hash_long(0x7ff8000000000000, seed)
+    } else {
+        hash_long(input.to_bits() as i64, seed)
+    }
+}
+
+// decimals which don't fit in a long are hashed by the bytes of java's BigInteger.toByteArray,
+// i.e. the minimal big endian two's complement
+fn hash_decimal(unscaled: i128, precision: u8, seed: i32) -> i32 {
+    if precision <= 18 {
+        return hash_long(unscaled as i64, seed);
+    }
+    let bytes = unscaled.to_be_bytes();
+    let mut start = 0;
+    while start < bytes.len() - 1
+        && ((bytes[start] == 0 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    hash_bytes(&bytes[start..], seed)
+}
+
+macro_rules! hash_values {
+    ($array:expr, $hashes:expr, $hash:expr) => {{
+        let array = $array;
+        for (idx, hash) in $hashes.iter_mut().enumerate() {
+            if array.is_valid(idx) {
+                *hash = $hash(array.value(idx), *hash);
+            }
+        }
+    }};
+}
+
+/// Updates `hashes` of rows with values of a column, chained the same way as Spark hashes multiple columns,
+/// i.e. each hash is used as the seed of the next column. Nulls leave the hashes unchanged.
+pub fn spark_murmur3_hash(array: &ArrayRef, hashes: &mut [i32]) -> Result<()> {
+    if array.len() != hashes.len() {
+        return Err(DataFusionError::Internal(format!(
+            "array length {} doesn't match hashes length {}",
+            array.len(),
+            hashes.len()
+        )));
+    }
+    match array.data_type() {
+        DataType::Null => {}
+        DataType::Boolean => hash_values!(as_boolean_array(array), hashes, hash_boolean),
+        DataType::Int8 => hash_values!(as_primitive_array::<Int8Type>(array), hashes, hash_byte),
+        DataType::Int16 => hash_values!(as_primitive_array::<Int16Type>(array), hashes, hash_short),
+        DataType::Int32 => hash_values!(as_primitive_array::<Int32Type>(array), hashes, hash_int),
+        DataType::Date32 => hash_values!(as_primitive_array::<Date32Type>(array), hashes, hash_int),
+        DataType::Int64 => hash_values!(as_primitive_array::<Int64Type>(array), hashes, hash_long),
+        // spark timestamps are microseconds
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            hash_values!(as_primitive_array::<TimestampMicrosecondType>(array), hashes, hash_long)
+        }
+        DataType::Float32 => hash_values!(as_primitive_array::<Float32Type>(array), hashes, hash_float),
+        DataType::Float64 => hash_values!(as_primitive_array::<Float64Type>(array), hashes, hash_double),
+        DataType::Decimal128(precision, _) => {
+            hash_values!(as_primitive_array::<Decimal128Type>(array), hashes, |value, seed| {
+                hash_decimal(value, *precision, seed)
+            })
+        }
+        DataType::Utf8 => hash_values!(as_string_array(array), hashes, hash_string),
+        DataType::LargeUtf8 => hash_values!(as_largestring_array(array), hashes, hash_string),
+        DataType::Binary => hash_values!(
+            array.as_any().downcast_ref::<BinaryArray>().unwrap(),
+            hashes,
+            hash_bytes
+        ),
+        DataType::LargeBinary => hash_values!(
+            array.as_any().downcast_ref::<LargeBinaryArray>().unwrap(),
+            hashes,
+            hash_bytes
+        ),
+        data_type => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "spark murmur3 hash of {} is not supported",
+                data_type
+            )))
+        }
+    }
+    Ok(())
+}
+
+/// Computes hash bucket ids of rows, as `pmod(hash(columns), bucket_num)` in Spark.
+pub fn spark_hash_bucket_ids(columns: &[ArrayRef], num_rows: usize, bucket_num: usize) -> Result<Vec<usize>> {
+    if bucket_num == 0 {
+        return Err(DataFusionError::Configuration(
+            "hash bucket num should be positive".to_string(),
+        ));
+    }
+    let mut hashes = vec![SPARK_MURMUR3_SEED; num_rows];
+    for column in columns {
+        spark_murmur3_hash(column, &mut hashes)?;
+    }
+    Ok(hashes
+        .into_iter()
+        .map(|hash| hash.rem_euclid(bucket_num as i32) as usize)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, Int64Array, StringArray};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_spark_murmur3_hash() -> Result<()> {
+        // expected values are from spark sql, e.g. `select hash(1)`
+        assert_eq!(hash_int(1, SPARK_MURMUR3_SEED), -559580957);
+        assert_eq!(hash_long(1, SPARK_MURMUR3_SEED), -1712319331);
+        assert_eq!(hash_bytes(b"", SPARK_MURMUR3_SEED), 142593372);
+        assert_eq!(hash_bytes(b"abc", SPARK_MURMUR3_SEED), 1322437556);
+        assert_eq!(hash_float(-0.0, SPARK_MURMUR3_SEED), hash_int(0, SPARK_MURMUR3_SEED));
+
+        let ints = Arc::new(Int32Array::from(vec![Some(1), None, Some(1)])) as ArrayRef;
+        let strings = Arc::new(StringArray::from(vec![Some("abc"), Some("abc"), None])) as ArrayRef;
+        let mut hashes = vec![SPARK_MURMUR3_SEED; 3];
+        spark_murmur3_hash(&ints, &mut hashes)?;
+        spark_murmur3_hash(&strings, &mut hashes)?;
+        assert_eq!(
+            hashes,
+            vec![
+                hash_bytes(b"abc", hash_int(1, SPARK_MURMUR3_SEED)),
+                1322437556,
+                -559580957
+            ]
+        );
+
+        let longs = Arc::new(Int64Array::from(vec![1, 2, 3, 4])) as ArrayRef;
+        let bucket_ids = spark_hash_bucket_ids(&[longs], 4, 4)?;
+        assert!(bucket_ids.iter().all(|bucket_id| *bucket_id < 4));
+        assert_eq!(bucket_ids[0], (-1712319331_i32).rem_euclid(4) as usize);
+        Ok(())
+    }
+}
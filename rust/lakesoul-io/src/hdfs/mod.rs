@@ -11,7 +11,7 @@ use bytes::Bytes;
 use datafusion::error::Result;
 use datafusion_common::DataFusionError;
 use futures::stream::BoxStream;
-use futures::TryStreamExt;
+use futures::{StreamExt, TryStreamExt};
 use hdrs::Client;
 use object_store::path::Path;
 use object_store::Error::Generic;
@@ -22,6 +22,7 @@ use std::io::ErrorKind::NotFound;
 use std::io::{Read, Seek, SeekFrom};
 use std::ops::Range;
 use std::sync::Arc;
+use std::time::SystemTime;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tokio_util::compat::{FuturesAsyncReadCompatExt, FuturesAsyncWriteCompatExt};
 use tokio_util::io::ReaderStream;
@@ -241,13 +242,18 @@ impl ObjectStore for Hdfs {
 
     async fn list(
         &self,
-        _prefix: Option<&Path>,
+        prefix: Option<&Path>,
     ) -> object_store::Result<BoxStream<'_, object_store::Result<ObjectMeta>>> {
-        todo!()
+        let prefix = prefix.cloned();
+        let client = self.client.clone();
+        let objects = maybe_spawn_blocking(move || list_files(client.as_ref(), prefix.as_ref())).await?;
+        Ok(futures::stream::iter(objects.into_iter().map(Ok)).boxed())
     }
 
-    async fn list_with_delimiter(&self, _prefix: Option<&Path>) -> object_store::Result<ListResult> {
-        todo!()
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        let prefix = prefix.cloned();
+        let client = self.client.clone();
+        maybe_spawn_blocking(move || list_dir_with_delimiter(client.as_ref(), prefix.as_ref())).await
     }
 
     async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
@@ -331,8 +337,142 @@ fn add_leading_slash(path: &Path) -> String {
     ["/", path.as_ref().trim_start_matches('/')].join("")
 }
 
+/// A file or directory of a file system being listed.
+struct FileStatus {
+    path: String,
+    size: u64,
+    last_modified: SystemTime,
+    is_dir: bool,
+}
+
+/// The file system calls needed for listing, so that listing can be tested without a hdfs cluster.
+trait FileSystem {
+    fn file_status(&self, path: &str) -> std::io::Result<FileStatus>;
+
+    fn list_dir(&self, path: &str) -> std::io::Result<Vec<FileStatus>>;
+}
+
+impl FileSystem for Client {
+    fn file_status(&self, path: &str) -> std::io::Result<FileStatus> {
+        self.metadata(path).map(|meta| FileStatus {
+            path: meta.path().to_string(),
+            size: meta.len(),
+            last_modified: meta.modified(),
+            is_dir: meta.is_dir(),
+        })
+    }
+
+    fn list_dir(&self, path: &str) -> std::io::Result<Vec<FileStatus>> {
+        Ok(self
+            .read_dir(path)?
+            .map(|meta| FileStatus {
+                path: meta.path().to_string(),
+                size: meta.len(),
+                last_modified: meta.modified(),
+                is_dir: meta.is_dir(),
+            })
+            .collect())
+    }
+}
+
+/// Converts a listed path, which may be a full uri like `hdfs://host:port/a/b`, into a location of the store.
+fn to_location(path: &str) -> object_store::Result<Path> {
+    let path = match path.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("", |idx| &rest[idx..]),
+        None => path,
+    };
+    Path::parse(path).map_err(|e| Generic {
+        store: "hdfs",
+        source: Box::new(e),
+    })
+}
+
+fn to_object_meta(status: &FileStatus) -> object_store::Result<ObjectMeta> {
+    Ok(ObjectMeta {
+        location: to_location(status.path.as_str())?,
+        last_modified: status.last_modified.into(),
+        size: status.size as usize,
+        e_tag: None,
+    })
+}
+
+fn prefix_dir(prefix: Option<&Path>) -> String {
+    prefix.map_or_else(|| "/".to_string(), add_leading_slash)
+}
+
+/// Returns the status of `path`, or `None` if it does not exist.
+fn file_status_if_exists<F: FileSystem>(fs: &F, path: &str) -> object_store::Result<Option<FileStatus>> {
+    match fs.file_status(path) {
+        Ok(status) => Ok(Some(status)),
+        Err(e) if e.kind() == NotFound => Ok(None),
+        Err(e) => Err(Generic {
+            store: "hdfs",
+            source: Box::new(e),
+        }),
+    }
+}
+
+/// Lists all files under `prefix` recursively. As in other object stores, `prefix` matches whole path
+/// segments, so `a/b` lists `a/b/c` but not `a/bc`, and directories themselves are not listed.
+fn list_files<F: FileSystem>(fs: &F, prefix: Option<&Path>) -> object_store::Result<Vec<ObjectMeta>> {
+    let root = prefix_dir(prefix);
+    match file_status_if_exists(fs, root.as_str())? {
+        Some(status) if status.is_dir => {}
+        _ => return Ok(vec![]),
+    }
+    let mut objects = Vec::new();
+    let mut dirs = vec![root];
+    while let Some(dir) = dirs.pop() {
+        let entries = match fs.list_dir(dir.as_str()) {
+            Ok(entries) => entries,
+            // removed while listing
+            Err(e) if e.kind() == NotFound => continue,
+            Err(e) => {
+                return Err(Generic {
+                    store: "hdfs",
+                    source: Box::new(e),
+                })
+            }
+        };
+        for entry in entries {
+            if entry.is_dir {
+                dirs.push(add_leading_slash(&to_location(entry.path.as_str())?));
+            } else {
+                objects.push(to_object_meta(&entry)?);
+            }
+        }
+    }
+    Ok(objects)
+}
+
+/// Lists the direct children of `prefix`, with directories returned as common prefixes.
+fn list_dir_with_delimiter<F: FileSystem>(fs: &F, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+    let mut result = ListResult {
+        common_prefixes: vec![],
+        objects: vec![],
+    };
+    let root = prefix_dir(prefix);
+    match file_status_if_exists(fs, root.as_str())? {
+        Some(status) if status.is_dir => {}
+        _ => return Ok(result),
+    }
+    let entries = fs.list_dir(root.as_str()).map_err(|e| Generic {
+        store: "hdfs",
+        source: Box::new(e),
+    })?;
+    for entry in entries {
+        if entry.is_dir {
+            result.common_prefixes.push(to_location(entry.path.as_str())?);
+        } else {
+            result.objects.push(to_object_meta(&entry)?);
+        }
+    }
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{list_dir_with_delimiter, list_files, FileStatus, FileSystem};
     use crate::lakesoul_io_config::{create_session_context, LakeSoulIOConfigBuilder};
     use bytes::Bytes;
     use datafusion::datasource::object_store::ObjectStoreUrl;
@@ -469,4 +609,100 @@ mod tests {
         let result = bytes_to_string(result);
         assert_eq!(result, string);
     }
+
+    /// A fake hdfs backed by a local directory, which lists paths as full hdfs uris.
+    struct LocalFileSystem {
+        root: std::path::PathBuf,
+    }
+
+    impl LocalFileSystem {
+        fn status_of(&self, local: &std::path::Path) -> std::io::Result<FileStatus> {
+            let meta = std::fs::metadata(local)?;
+            let relative = local.strip_prefix(&self.root).unwrap().to_str().unwrap();
+            Ok(FileStatus {
+                path: format!("hdfs://localhost:9000/{}", relative),
+                size: meta.len(),
+                last_modified: meta.modified()?,
+                is_dir: meta.is_dir(),
+            })
+        }
+    }
+
+    impl FileSystem for LocalFileSystem {
+        fn file_status(&self, path: &str) -> std::io::Result<FileStatus> {
+            self.status_of(&self.root.join(path.trim_start_matches('/')))
+        }
+
+        fn list_dir(&self, path: &str) -> std::io::Result<Vec<FileStatus>> {
+            std::fs::read_dir(self.root.join(path.trim_start_matches('/')))?
+                .map(|entry| self.status_of(&entry?.path()))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_hdfs_list() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let fs = LocalFileSystem {
+            root: temp_dir.path().to_path_buf(),
+        };
+        let files = ["a/b/1.parquet", "a/b/c/2.parquet", "a/bc/3.parquet", "d.txt"];
+        for (i, file) in files.iter().enumerate() {
+            let local = temp_dir.path().join(file);
+            std::fs::create_dir_all(local.parent().unwrap()).unwrap();
+            std::fs::write(local, "x".repeat(i + 1)).unwrap();
+        }
+
+        let list = |prefix: Option<&str>| {
+            let mut objects = list_files(&fs, prefix.map(Path::from).as_ref()).unwrap();
+            objects.sort_by(|a, b| a.location.cmp(&b.location));
+            objects
+        };
+        let all = list(None);
+        assert_eq!(
+            all.iter().map(|meta| meta.location.to_string()).collect::<Vec<_>>(),
+            files
+        );
+        for (i, meta) in all.iter().enumerate() {
+            let local = std::fs::metadata(temp_dir.path().join(files[i])).unwrap();
+            assert_eq!(meta.size, i + 1);
+            assert_eq!(
+                meta.last_modified,
+                chrono::DateTime::<chrono::Utc>::from(local.modified().unwrap())
+            );
+        }
+        let locations = |prefix| {
+            list(Some(prefix))
+                .into_iter()
+                .map(|meta| meta.location)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            locations("a/b"),
+            vec![Path::from("a/b/1.parquet"), Path::from("a/b/c/2.parquet")]
+        );
+        assert_eq!(locations("/a/b/"), locations("a/b"));
+        assert!(locations("a/b/1").is_empty());
+        assert!(locations("missing").is_empty());
+
+        let result = list_dir_with_delimiter(&fs, None).unwrap();
+        assert_eq!(result.common_prefixes, vec![Path::from("a")]);
+        assert_eq!(
+            result.objects.iter().map(|meta| &meta.location).collect::<Vec<_>>(),
+            vec![&Path::from("d.txt")]
+        );
+        let mut result = list_dir_with_delimiter(&fs, Some(&Path::from("a"))).unwrap();
+        result.common_prefixes.sort();
+        assert_eq!(result.common_prefixes, vec![Path::from("a/b"), Path::from("a/bc")]);
+        assert!(result.objects.is_empty());
+        let result = list_dir_with_delimiter(&fs, Some(&Path::from("a/b"))).unwrap();
+        assert_eq!(result.common_prefixes, vec![Path::from("a/b/c")]);
+        assert_eq!(result.objects.len(), 1);
+        assert_eq!(result.objects[0].location, Path::from("a/b/1.parquet"));
+        assert_eq!(result.objects[0].size, 1);
+        assert!(list_dir_with_delimiter(&fs, Some(&Path::from("missing")))
+            .unwrap()
+            .objects
+            .is_empty());
+    }
 }
@@ -13,6 +13,9 @@ use datafusion_common::DataFusionError::ObjectStore;
 use derivative::Derivative;
 use object_store::aws::AmazonS3Builder;
 use object_store::RetryConfig;
+use parquet::basic::{BrotliLevel, Compression, GzipLevel, ZstdLevel};
+use parquet::file::properties::{EnabledStatistics, WriterProperties, WriterVersion};
+use parquet::schema::types::ColumnPath;
 use std::collections::HashMap;
 use std::sync::Arc;
 use url::{ParseError, Url};
@@ -35,6 +38,18 @@ impl Default for IOSchema {
 pub struct LakeSoulIOConfig {
     // files to read or write
     pub(crate) files: Vec<String>,
+    // directory to write rolling files into, instead of the single file in files
+    pub(crate) prefix: String,
+    // start a new file when the bytes flushed to the current one reach this size
+    pub(crate) max_file_size: Option<u64>,
+    // start a new file when the rows written to the current one reach this count
+    pub(crate) max_file_rows: Option<u64>,
+    // number of hash buckets to write rows into, 0 for no bucketing
+    pub(crate) hash_bucket_num: usize,
+    // columns to compute hash bucket ids, primary keys if empty
+    pub(crate) hash_columns: Vec<String>,
+    // range partition columns to split written rows by, which are not stored in files
+    pub(crate) range_partitions: Vec<String>,
     // primary key column names
     pub(crate) primary_keys: Vec<String>,
     // selecting columns
@@ -44,7 +59,13 @@ pub struct LakeSoulIOConfig {
 
     // filtering predicates
     pub(crate) filter_strs: Vec<String>,
+    // serialized substrait ExtendedExpressions
+    pub(crate) substrait_filters: Vec<Vec<u8>>,
+    // sql text filters, e.g. `amount > 100`
+    pub(crate) sql_filters: Vec<String>,
     pub(crate) filters: Vec<Expr>,
+    // sql text computed columns replacing the read columns, e.g. `amount * 2 AS doubled`
+    pub(crate) sql_projections: Vec<String>,
     // read or write batch size
     #[derivative(Default(value = "8192"))]
     pub(crate) batch_size: usize,
@@ -53,6 +74,32 @@ pub struct LakeSoulIOConfig {
     pub(crate) max_row_group_size: usize,
     #[derivative(Default(value = "1"))]
     pub(crate) prefetch_size: usize,
+    // max number of rows to read
+    pub(crate) limit: Option<usize>,
+    // number of primary key ranges merged in parallel
+    #[derivative(Default(value = "1"))]
+    pub(crate) merge_parallelism: usize,
+
+    // parquet writer options, validated when creating writers
+    // compression codec: uncompressed, snappy, gzip, lz4, lz4_raw, zstd or brotli
+    #[derivative(Default(value = "String::from(\"snappy\")"))]
+    pub(crate) compression: String,
+    // compression level of gzip, zstd and brotli, codec default if none
+    pub(crate) compression_level: Option<i32>,
+    #[derivative(Default(value = "true"))]
+    pub(crate) dictionary_enabled: bool,
+    // dictionary encoding of specific columns, overriding dictionary_enabled
+    pub(crate) column_dictionary_enabled: HashMap<String, bool>,
+    // statistics level: none, chunk or page
+    #[derivative(Default(value = "String::from(\"page\")"))]
+    pub(crate) statistics_enabled: String,
+    // best effort max size of data pages in bytes
+    pub(crate) data_page_size_limit: Option<usize>,
+    // columns with bloom filters, with optional false positive probability and number of distinct values
+    pub(crate) bloom_filter_columns: HashMap<String, (Option<f64>, Option<u64>)>,
+    // parquet writer version: 1.0 or 2.0
+    #[derivative(Default(value = "String::from(\"1.0\")"))]
+    pub(crate) writer_version: String,
 
     // arrow schema
     pub(crate) schema: IOSchema,
@@ -63,6 +110,11 @@ pub struct LakeSoulIOConfig {
     // merge operators
     pub(crate) merge_operators: HashMap<String, String>,
 
+    // cdc column of row kinds, keys whose last version is a delete are dropped when merging
+    pub(crate) cdc_column: String,
+    // emit deleted keys instead of dropping them, for incremental reads
+    pub(crate) keep_deletes: bool,
+
     // default column value
     pub(crate) default_column_value: HashMap<String, String>,
 
@@ -74,6 +126,121 @@ pub struct LakeSoulIOConfig {
     pub(crate) default_fs: String,
 }
 
+impl LakeSoulIOConfig {
+    pub fn files(&self) -> &[String] {
+        &self.files
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn primary_keys(&self) -> &[String] {
+        &self.primary_keys
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.0.clone()
+    }
+
+    pub fn default_column_value(&self) -> &HashMap<String, String> {
+        &self.default_column_value
+    }
+
+    pub fn merge_operators(&self) -> &HashMap<String, String> {
+        &self.merge_operators
+    }
+
+    /// Creates parquet writer properties from the writer options of this config.
+    pub fn writer_properties(&self) -> Result<WriterProperties> {
+        let mut builder = WriterProperties::builder()
+            .set_max_row_group_size(self.max_row_group_size)
+            .set_write_batch_size(self.batch_size)
+            .set_compression(parse_compression(&self.compression, self.compression_level)?)
+            .set_dictionary_enabled(self.dictionary_enabled)
+            .set_statistics_enabled(parse_statistics_enabled(&self.statistics_enabled)?)
+            .set_writer_version(parse_writer_version(&self.writer_version)?);
+        if let Some(data_page_size_limit) = self.data_page_size_limit {
+            builder = builder.set_data_page_size_limit(data_page_size_limit);
+        }
+        for (column, enabled) in &self.column_dictionary_enabled {
+            builder = builder.set_column_dictionary_enabled(ColumnPath::from(column.clone()), *enabled);
+        }
+        for (column, (fpp, ndv)) in &self.bloom_filter_columns {
+            builder = builder.set_column_bloom_filter_enabled(ColumnPath::from(column.clone()), true);
+            if let Some(fpp) = fpp {
+                if !(*fpp > 0.0 && *fpp < 1.0) {
+                    return Err(DataFusionError::Configuration(format!(
+                        "bloom filter fpp of column {} should be in (0, 1), but got {}",
+                        column, fpp
+                    )));
+                }
+                builder = builder.set_column_bloom_filter_fpp(ColumnPath::from(column.clone()), *fpp);
+            }
+            if let Some(ndv) = ndv {
+                builder = builder.set_column_bloom_filter_ndv(ColumnPath::from(column.clone()), *ndv);
+            }
+        }
+        Ok(builder.build())
+    }
+}
+
+fn parse_compression(codec: &str, level: Option<i32>) -> Result<Compression> {
+    let invalid_level = |e: parquet::errors::ParquetError| {
+        DataFusionError::Configuration(format!("invalid compression level of {}: {}", codec, e))
+    };
+    let unsigned_level = |level: i32| {
+        u32::try_from(level)
+            .map_err(|_| DataFusionError::Configuration(format!("invalid compression level of {}: {}", codec, level)))
+    };
+    match (codec.to_lowercase().as_str(), level) {
+        ("uncompressed" | "none", None) => Ok(Compression::UNCOMPRESSED),
+        ("snappy", None) => Ok(Compression::SNAPPY),
+        ("lz4", None) => Ok(Compression::LZ4),
+        ("lz4_raw", None) => Ok(Compression::LZ4_RAW),
+        ("gzip", None) => Ok(Compression::GZIP(GzipLevel::default())),
+        ("gzip", Some(level)) => Ok(Compression::GZIP(
+            GzipLevel::try_new(unsigned_level(level)?).map_err(invalid_level)?,
+        )),
+        ("zstd", None) => Ok(Compression::ZSTD(ZstdLevel::default())),
+        ("zstd", Some(level)) => Ok(Compression::ZSTD(ZstdLevel::try_new(level).map_err(invalid_level)?)),
+        ("brotli", None) => Ok(Compression::BROTLI(BrotliLevel::default())),
+        ("brotli", Some(level)) => Ok(Compression::BROTLI(
+            BrotliLevel::try_new(unsigned_level(level)?).map_err(invalid_level)?,
+        )),
+        ("uncompressed" | "none" | "snappy" | "lz4" | "lz4_raw", Some(level)) => Err(DataFusionError::Configuration(
+            format!("compression {} does not support level {}", codec, level),
+        )),
+        _ => Err(DataFusionError::Configuration(format!(
+            "unsupported compression {}",
+            codec
+        ))),
+    }
+}
+
+fn parse_statistics_enabled(statistics_enabled: &str) -> Result<EnabledStatistics> {
+    match statistics_enabled.to_lowercase().as_str() {
+        "none" => Ok(EnabledStatistics::None),
+        "chunk" => Ok(EnabledStatistics::Chunk),
+        "page" => Ok(EnabledStatistics::Page),
+        _ => Err(DataFusionError::Configuration(format!(
+            "unsupported statistics level {}",
+            statistics_enabled
+        ))),
+    }
+}
+
+fn parse_writer_version(writer_version: &str) -> Result<WriterVersion> {
+    match writer_version {
+        "1.0" => Ok(WriterVersion::PARQUET_1_0),
+        "2.0" => Ok(WriterVersion::PARQUET_2_0),
+        _ => Err(DataFusionError::Configuration(format!(
+            "unsupported parquet writer version {}",
+            writer_version
+        ))),
+    }
+}
+
 #[derive(Derivative)]
 #[derivative(Clone, Default)]
 pub struct LakeSoulIOConfigBuilder {
@@ -97,6 +264,51 @@ impl LakeSoulIOConfigBuilder {
         self
     }
 
+    /// Writes rolling files named `part-<index>-<uuid>.parquet` under `prefix` instead of the single file
+    /// given by `with_files`. A new file is started when the current one reaches `max_file_size` or
+    /// `max_file_rows`, only at primary key boundaries if primary keys are set.
+    pub fn with_prefix(mut self, prefix: String) -> Self {
+        self.config.prefix = prefix;
+        self
+    }
+
+    /// Target file size in bytes of rolling files. It is checked each time a row group is flushed,
+    /// so files could exceed it by up to a row group.
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.config.max_file_size = Some(max_file_size);
+        self
+    }
+
+    pub fn with_max_file_rows(mut self, max_file_rows: u64) -> Self {
+        self.config.max_file_rows = Some(max_file_rows);
+        self
+    }
+
+    /// Writes rows into `hash_bucket_num` buckets by the Spark compatible murmur3 hash of the hash columns.
+    /// Requires a prefix, under which rolling files of each bucket are named `part-<index>-<uuid>_<bucket id>.parquet`.
+    pub fn with_hash_bucket_num(mut self, hash_bucket_num: usize) -> Self {
+        self.config.hash_bucket_num = hash_bucket_num;
+        self
+    }
+
+    /// Columns to compute hash bucket ids, defaults to primary keys.
+    pub fn with_hash_columns(mut self, hash_columns: Vec<String>) -> Self {
+        self.config.hash_columns = hash_columns;
+        self
+    }
+
+    /// Splits written rows by values of range partition columns into `col=value` directories under the prefix,
+    /// with partition columns dropped from files.
+    pub fn with_range_partition(mut self, column: String) -> Self {
+        self.config.range_partitions.push(column);
+        self
+    }
+
+    pub fn with_range_partitions(mut self, columns: Vec<String>) -> Self {
+        self.config.range_partitions = columns;
+        self
+    }
+
     pub fn with_primary_key(mut self, pks: String) -> Self {
         self.config.primary_keys.push(pks);
         self
@@ -132,6 +344,60 @@ impl LakeSoulIOConfigBuilder {
         self
     }
 
+    /// Stops reading after `limit` rows, which are counted after merging primary keys and filtering.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.config.limit = Some(limit);
+        self
+    }
+
+    /// Splits the first primary key into at most `merge_parallelism` ranges by parquet statistics,
+    /// which are merged in parallel and concatenated in key order.
+    pub fn with_merge_parallelism(mut self, merge_parallelism: usize) -> Self {
+        self.config.merge_parallelism = merge_parallelism;
+        self
+    }
+
+    /// Sets the compression codec of written files, one of uncompressed, snappy, gzip, lz4, lz4_raw, zstd and brotli.
+    /// `level` is only supported by gzip, zstd and brotli.
+    pub fn with_compression(mut self, codec: String, level: Option<i32>) -> Self {
+        self.config.compression = codec;
+        self.config.compression_level = level;
+        self
+    }
+
+    pub fn with_dictionary_enabled(mut self, enabled: bool) -> Self {
+        self.config.dictionary_enabled = enabled;
+        self
+    }
+
+    pub fn with_column_dictionary_enabled(mut self, column: String, enabled: bool) -> Self {
+        self.config.column_dictionary_enabled.insert(column, enabled);
+        self
+    }
+
+    /// Sets the statistics level of written files, one of none, chunk and page.
+    pub fn with_statistics_enabled(mut self, statistics_enabled: String) -> Self {
+        self.config.statistics_enabled = statistics_enabled;
+        self
+    }
+
+    pub fn with_data_page_size_limit(mut self, data_page_size_limit: usize) -> Self {
+        self.config.data_page_size_limit = Some(data_page_size_limit);
+        self
+    }
+
+    /// Enables bloom filter of a column, with parquet defaults if `fpp` or `ndv` is none.
+    pub fn with_bloom_filter(mut self, column: String, fpp: Option<f64>, ndv: Option<u64>) -> Self {
+        self.config.bloom_filter_columns.insert(column, (fpp, ndv));
+        self
+    }
+
+    /// Sets the parquet writer version, 1.0 or 2.0.
+    pub fn with_writer_version(mut self, writer_version: String) -> Self {
+        self.config.writer_version = writer_version;
+        self
+    }
+
     pub fn with_columns(mut self, cols: Vec<String>) -> Self {
         self.config.columns = cols;
         self
@@ -147,6 +413,27 @@ impl LakeSoulIOConfigBuilder {
         self
     }
 
+    /// Adds filters from a serialized substrait `ExtendedExpression`, whose field references are resolved
+    /// against the read schema when the reader is created.
+    pub fn with_substrait_filter(mut self, extended_expression: Vec<u8>) -> Self {
+        self.config.substrait_filters.push(extended_expression);
+        self
+    }
+
+    /// Adds a filter in SQL, e.g. `amount > 100 AND region IN ('eu', 'us')`,
+    /// which is planned against the read schema when the reader is created.
+    pub fn with_sql_filter(mut self, sql_filter: String) -> Self {
+        self.config.sql_filters.push(sql_filter);
+        self
+    }
+
+    /// Adds an output column computed in SQL, e.g. `amount * 2 AS doubled`.
+    /// When any projection is set, the reader outputs exactly the projections in the order they are added.
+    pub fn with_sql_projection(mut self, sql_projection: String) -> Self {
+        self.config.sql_projections.push(sql_projection);
+        self
+    }
+
     pub fn with_filters(mut self, filters: Vec<Expr>) -> Self {
         self.config.filters = filters;
         self
@@ -157,6 +444,18 @@ impl LakeSoulIOConfigBuilder {
         self
     }
 
+    /// Sets the cdc column of row kinds, e.g. `rowKinds`, by which keys whose last version is a delete are dropped.
+    pub fn with_cdc_column(mut self, cdc_column: String) -> Self {
+        self.config.cdc_column = cdc_column;
+        self
+    }
+
+    /// Keeps keys whose last version is a delete in the merged output, together with the cdc column.
+    pub fn with_keep_deletes(mut self, keep_deletes: bool) -> Self {
+        self.config.keep_deletes = keep_deletes;
+        self
+    }
+
     pub fn with_default_column_value(mut self, field_name: String, value: String) -> Self {
         self.config.default_column_value.insert(field_name, value);
         self
@@ -177,6 +476,12 @@ impl LakeSoulIOConfigBuilder {
     }
 }
 
+impl From<LakeSoulIOConfig> for LakeSoulIOConfigBuilder {
+    fn from(config: LakeSoulIOConfig) -> Self {
+        LakeSoulIOConfigBuilder { config }
+    }
+}
+
 /// First check envs for credentials, region and endpoint.
 /// Second check fs.s3a.xxx, to keep compatible with hadoop s3a.
 /// If no region is provided, default to us-east-1.
@@ -310,9 +615,12 @@ fn register_object_store(path: &str, config: &mut LakeSoulIOConfig, runtime: &Ru
 pub fn create_session_context(config: &mut LakeSoulIOConfig) -> Result<SessionContext> {
     let mut sess_conf = SessionConfig::default()
         .with_batch_size(config.batch_size)
-        .with_parquet_pruning(false)
+        .with_parquet_pruning(true)
         .with_prefetch(config.prefetch_size);
 
+    // skip row groups and pages by column statistics and page index with the filters pushed into parquet scans
+    sess_conf.options_mut().execution.parquet.enable_page_index = true;
+
     sess_conf.options_mut().optimizer.enable_round_robin_repartition = false; // if true, the record_batches poll from stream become unordered
     sess_conf.options_mut().optimizer.prefer_hash_join = false; //if true, panicked at 'range end out of bounds'
 
@@ -346,6 +654,9 @@ pub fn create_session_context(config: &mut LakeSoulIOConfig) -> Result<SessionCo
 #[cfg(test)]
 mod tests {
     use crate::lakesoul_io_config::{create_session_context, LakeSoulIOConfigBuilder};
+    use parquet::basic::{Compression, ZstdLevel};
+    use parquet::file::properties::{EnabledStatistics, WriterVersion};
+    use parquet::schema::types::ColumnPath;
 
     #[test]
     fn test_path_normalize() {
@@ -364,4 +675,46 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_writer_properties() {
+        let props = LakeSoulIOConfigBuilder::new().build().writer_properties().unwrap();
+        assert_eq!(props.compression(&ColumnPath::from("id")), Compression::SNAPPY);
+        assert_eq!(props.writer_version(), WriterVersion::PARQUET_1_0);
+
+        let props = LakeSoulIOConfigBuilder::new()
+            .with_compression("zstd".to_string(), Some(3))
+            .with_column_dictionary_enabled("value".to_string(), false)
+            .with_statistics_enabled("chunk".to_string())
+            .with_data_page_size_limit(4096)
+            .with_bloom_filter("id".to_string(), Some(0.01), None)
+            .with_writer_version("2.0".to_string())
+            .build()
+            .writer_properties()
+            .unwrap();
+        let id = ColumnPath::from("id");
+        let value = ColumnPath::from("value");
+        assert_eq!(
+            props.compression(&id),
+            Compression::ZSTD(ZstdLevel::try_new(3).unwrap())
+        );
+        assert!(props.dictionary_enabled(&id));
+        assert!(!props.dictionary_enabled(&value));
+        assert_eq!(props.statistics_enabled(&id), EnabledStatistics::Chunk);
+        assert_eq!(props.data_page_size_limit(), 4096);
+        assert_eq!(props.bloom_filter_properties(&id).unwrap().fpp, 0.01);
+        assert!(props.bloom_filter_properties(&value).is_none());
+        assert_eq!(props.writer_version(), WriterVersion::PARQUET_2_0);
+
+        for builder in [
+            LakeSoulIOConfigBuilder::new().with_compression("lzo".to_string(), None),
+            LakeSoulIOConfigBuilder::new().with_compression("snappy".to_string(), Some(1)),
+            LakeSoulIOConfigBuilder::new().with_compression("zstd".to_string(), Some(100)),
+            LakeSoulIOConfigBuilder::new().with_statistics_enabled("row".to_string()),
+            LakeSoulIOConfigBuilder::new().with_bloom_filter("id".to_string(), Some(1.5), None),
+            LakeSoulIOConfigBuilder::new().with_writer_version("3.0".to_string()),
+        ] {
+            assert!(builder.build().writer_properties().is_err());
+        }
+    }
 }
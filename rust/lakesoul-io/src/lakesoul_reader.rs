@@ -3,16 +3,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use atomic_refcell::AtomicRefCell;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use arrow::datatypes::Schema;
-use arrow_schema::SchemaRef;
+use arrow_schema::{DataType, Field, SchemaRef};
 
 pub use datafusion::arrow::error::ArrowError;
 pub use datafusion::arrow::error::Result as ArrowResult;
 pub use datafusion::arrow::record_batch::RecordBatch;
 pub use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::Expr;
 use datafusion::physical_plan::expressions::PhysicalSortExpr;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::SendableRecordBatchStream;
 
 use datafusion::prelude::{DataFrame, SessionContext};
@@ -21,45 +24,103 @@ use core::pin::Pin;
 use datafusion::physical_plan::RecordBatchStream;
 use datafusion::prelude::Expr::Column;
 use futures::future::try_join_all;
-use futures::StreamExt;
+use futures::{StreamExt, TryStreamExt};
 
 use tokio::runtime::Runtime;
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
 
 use crate::default_column_stream::empty_schema_stream::EmptySchemaStream;
 use crate::default_column_stream::DefaultColumnStream;
-use crate::filter::Parser as FilterParser;
+use crate::filter::stream::FilterStream;
+use crate::filter::{parse_sql_expr, parse_substrait_filters, Parser as FilterParser};
 use crate::lakesoul_io_config::{create_session_context, LakeSoulIOConfig};
+use crate::projection_stream::ProjectionStream;
 use crate::sorted_merge::merge_operator::MergeOperator;
+use crate::sorted_merge::range_split::{range_filters, row_group_bounds, split_points};
 use crate::sorted_merge::sorted_stream_merger::{SortedStream, SortedStreamMerger};
 
+// number of batches buffered ahead for each primary key range merged in parallel
+const MERGE_RANGE_BUFFER_SIZE: usize = 2;
+
 pub struct LakeSoulReader {
     sess_ctx: SessionContext,
     config: LakeSoulIOConfig,
     stream: Option<Pin<Box<dyn RecordBatchStream + Send>>>,
     pub(crate) schema: Option<SchemaRef>,
+    // computed output columns, applied after merging and filtering
+    projections: Vec<Expr>,
+    // merge operators by field name, UseLast if absent
+    merge_ops: HashMap<String, MergeOperator>,
 }
 
 impl LakeSoulReader {
     pub fn new(mut config: LakeSoulIOConfig) -> Result<Self> {
         let sess_ctx = create_session_context(&mut config)?;
+        // filter strings are parsed up front so that invalid filters are reported when creating the reader
+        let filters = std::mem::take(&mut config.filter_strs)
+            .into_iter()
+            .map(|f| FilterParser::parse(f, config.schema.0.clone()))
+            .collect::<Result<Vec<_>>>()?;
+        config.filters.extend(filters);
+        for extended_expression in std::mem::take(&mut config.substrait_filters) {
+            let filters = parse_substrait_filters(&extended_expression, config.schema.0.clone())?;
+            config.filters.extend(filters);
+        }
+        for sql_filter in std::mem::take(&mut config.sql_filters) {
+            let filter = parse_sql_expr(&sql_filter, config.schema.0.clone())?;
+            config.filters.push(filter);
+        }
+        let projections = std::mem::take(&mut config.sql_projections)
+            .iter()
+            .map(|sql_projection| parse_sql_expr(sql_projection, config.schema.0.clone()))
+            .collect::<Result<Vec<_>>>()?;
+        // merge operators are resolved up front so that unknown names are reported when creating the reader
+        let merge_ops = config
+            .merge_operators
+            .iter()
+            .map(|(field, name)| {
+                let merge_op = MergeOperator::from_name(name)?;
+                if let Ok(schema_field) = config.schema.0.field_with_name(field) {
+                    merge_op.check_data_type(field, schema_field.data_type())?;
+                }
+                Ok((field.clone(), merge_op))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
         Ok(LakeSoulReader {
             sess_ctx,
             config,
             stream: None,
             schema: None,
+            projections,
+            merge_ops,
         })
     }
 
     pub async fn prune_filter_and_execute(
         df: DataFrame,
         request_schema: SchemaRef,
-        filter_str: Vec<String>,
+        filters: Vec<Expr>,
+        default_column_value: Arc<HashMap<String, String>>,
         batch_size: usize,
+        limit: Option<usize>,
     ) -> Result<SendableRecordBatchStream> {
         let file_schema = df.schema().clone();
 
+        // filters are pushed down into the parquet scan to prune row groups and pages.
+        // filters on columns missing in this file, e.g. range partition columns or columns added by
+        // schema evolution, are evaluated once the missing columns are filled with their default values
+        let (pushed_filters, missing_column_filters): (Vec<_>, Vec<_>) =
+            filters.into_iter().partition(|f| match f.to_columns() {
+                Ok(columns) => columns
+                    .iter()
+                    .all(|column| file_schema.field_with_unqualified_name(&column.name).is_ok()),
+                Err(_) => false,
+            });
+        // the limit can be pushed down only if all filters are
+        let scan_limit = limit.filter(|_| missing_column_filters.is_empty());
+
         // find columns requested and prune others
         let cols = request_schema
             .fields()
@@ -71,19 +132,31 @@ impl LakeSoulReader {
                 _ => None,
             })
             .collect::<Vec<_>>();
-        if cols.is_empty() {
-            Ok(Box::pin(EmptySchemaStream::new(batch_size, df.count().await?)))
+        let stream: SendableRecordBatchStream = if cols.is_empty() {
+            // no filter column is in the file either, so all filters are evaluated on default values
+            let count = df.count().await?;
+            Box::pin(EmptySchemaStream::new(
+                batch_size,
+                scan_limit.map_or(count, |limit| count.min(limit)),
+            ))
         } else {
-            // row filtering should go first since filter column may not in the selected cols
-            let arrow_schema = Arc::new(Schema::from(file_schema));
-            let df = filter_str.iter().try_fold(df, |df, f| {
-                let filter = FilterParser::parse(f.clone(), arrow_schema.clone());
-                df.filter(filter)
-            })?;
+            // row filtering should go first since filter column may not in the selected cols.
+            let df = pushed_filters.into_iter().try_fold(df, |df, f| df.filter(f))?;
             // column pruning
             let df = df.select(cols)?;
-            df.execute_stream().await
+            let df = match scan_limit {
+                Some(limit) => df.limit(0, Some(limit))?,
+                None => df,
+            };
+            df.execute_stream().await?
+        };
+        if missing_column_filters.is_empty() {
+            return Ok(stream);
         }
+        let stream =
+            DefaultColumnStream::new_from_streams_with_default(vec![stream], request_schema, default_column_value);
+        let stream = FilterStream::try_new(Box::pin(stream), missing_column_filters)?;
+        Ok(limit_stream(stream, limit))
     }
 
     pub async fn start(&mut self) -> Result<()> {
@@ -91,26 +164,70 @@ impl LakeSoulReader {
         let batch_size = self.config.batch_size;
         if self.config.primary_keys.is_empty() {
             if !self.config.files.is_empty() {
-                let mut stream_init_futs = Vec::with_capacity(self.config.files.len());
-                for i in 0..self.config.files.len() {
-                    let file = self.config.files[i].clone();
+                let limit = self.config.limit;
+                let stream_vec = if limit.is_some() {
+                    // files are opened one after another, so that no more files are opened once the limit is reached
                     let sess_ctx = self.sess_ctx.clone();
-                    let filter_str = self.config.filter_strs.clone();
-                    let schema = schema.clone();
-                    let future = async move {
-                        let df = sess_ctx.read_parquet(file.clone(), Default::default()).await?;
-                        LakeSoulReader::prune_filter_and_execute(df, schema, filter_str, batch_size).await
-                    };
-                    stream_init_futs.push(future);
-                }
-                let stream_vec = try_join_all(stream_init_futs).await?;
+                    let filters = self.config.filters.clone();
+                    let file_schema = schema.clone();
+                    let default_column_value = Arc::new(self.config.default_column_value.clone());
+                    let file_streams = futures::stream::iter(self.config.files.clone()).then(move |file| {
+                        let sess_ctx = sess_ctx.clone();
+                        let filters = filters.clone();
+                        let schema = file_schema.clone();
+                        let default_column_value = default_column_value.clone();
+                        async move {
+                            let df = sess_ctx.read_parquet(file, Default::default()).await?;
+                            LakeSoulReader::prune_filter_and_execute(
+                                df,
+                                schema,
+                                filters,
+                                default_column_value,
+                                batch_size,
+                                limit,
+                            )
+                            .await
+                        }
+                    });
+                    let stream: SendableRecordBatchStream = Box::pin(RecordBatchStreamAdapter::new(
+                        schema.clone(),
+                        file_streams.try_flatten(),
+                    ));
+                    vec![stream]
+                } else {
+                    let mut stream_init_futs = Vec::with_capacity(self.config.files.len());
+                    let default_column_value = Arc::new(self.config.default_column_value.clone());
+                    for i in 0..self.config.files.len() {
+                        let file = self.config.files[i].clone();
+                        let sess_ctx = self.sess_ctx.clone();
+                        let filters = self.config.filters.clone();
+                        let schema = schema.clone();
+                        let default_column_value = default_column_value.clone();
+                        let future = async move {
+                            let df = sess_ctx.read_parquet(file.clone(), Default::default()).await?;
+                            LakeSoulReader::prune_filter_and_execute(
+                                df,
+                                schema,
+                                filters,
+                                default_column_value,
+                                batch_size,
+                                None,
+                            )
+                            .await
+                        };
+                        stream_init_futs.push(future);
+                    }
+                    try_join_all(stream_init_futs).await?
+                };
                 let stream = DefaultColumnStream::new_from_streams_with_default(
                     stream_vec,
                     schema.clone(),
                     Arc::new(self.config.default_column_value.clone()),
                 );
+                let stream = ProjectionStream::try_new(Box::pin(stream), self.projections.clone())?;
+                let stream = limit_stream(stream, limit);
                 self.schema = Some(stream.schema());
-                self.stream = Some(Box::pin(stream));
+                self.stream = Some(stream);
 
                 Ok(())
             } else {
@@ -124,37 +241,27 @@ impl LakeSoulReader {
             ))
         } else {
             let finalize_schema: SchemaRef = self.config.schema.0.clone();
-            let schema: SchemaRef = Arc::new(Schema::new(
-                finalize_schema
-                    .fields
-                    .iter()
-                    .filter_map(|field| {
-                        if self.config.default_column_value.get(field.name()).is_none() {
-                            Some(field.clone())
-                        } else {
-                            None
-                        }
-                    })
-                    .collect::<Vec<_>>(),
-            )); //merge_schema
-
-            let mut stream_init_futs = Vec::with_capacity(self.config.files.len());
-            for i in 0..self.config.files.len() {
-                let file = self.config.files[i].clone();
-                let sess_ctx = self.sess_ctx.clone();
-                let schema = schema.clone();
-                let future = async move {
-                    let df = sess_ctx.read_parquet(file.as_str(), Default::default()).await?;
-                    LakeSoulReader::prune_filter_and_execute(df, schema, vec![], batch_size).await
-                };
-                stream_init_futs.push(future);
+            let mut merge_fields = finalize_schema
+                .fields
+                .iter()
+                .filter_map(|field| {
+                    if self.config.default_column_value.get(field.name()).is_none() {
+                        Some(field.clone())
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            // the cdc column is merged even if not read, and dropped again by the finalized stream
+            let cdc_column = &self.config.cdc_column;
+            if !cdc_column.is_empty() && finalize_schema.field_with_name(cdc_column).is_err() {
+                merge_fields.push(Arc::new(Field::new(cdc_column, DataType::Utf8, true)));
             }
+            let schema: SchemaRef = Arc::new(Schema::new(merge_fields)); //merge_schema
 
-            let stream_res = try_join_all(stream_init_futs).await?;
-            let streams = stream_res
-                .into_iter()
-                .map(|s| SortedStream::new(Box::pin(DefaultColumnStream::new_from_stream(s, schema.clone()))))
-                .collect();
+            // only filters on primary keys can be pushed down into file scans and used for row group and page pruning,
+            // others must be applied after merging so that merge operators see every version of a row
+            let (pk_filters, merged_filters) = self.split_pk_filters();
 
             let mut sort_exprs = Vec::with_capacity(self.config.primary_keys.len());
             for i in 0..self.config.primary_keys.len() {
@@ -164,41 +271,140 @@ impl LakeSoulReader {
                 });
             }
 
-            let merge_ops = self
-                .config
-                .schema
-                .0
+            // the row kind of a key is the one of its last version
+            let merge_ops = schema
                 .fields()
                 .iter()
-                .map(|field| {
-                    MergeOperator::from_name(
-                        self.config
-                            .merge_operators
-                            .get(field.name())
-                            .unwrap_or(&String::from("UseLast")),
-                    )
+                .map(|field| match self.merge_ops.get(field.name()) {
+                    Some(merge_op) if field.name() != cdc_column => merge_op.clone(),
+                    _ => MergeOperator::default(),
                 })
                 .collect::<Vec<_>>();
-
-            let merge_stream = SortedStreamMerger::new_from_streams(
-                streams,
-                schema.clone(),
-                self.config.primary_keys.clone(),
-                self.config.batch_size,
-                merge_ops,
-            )
-            .unwrap();
+            // the merge can stop early only if no filter is evaluated on the merged rows
+            let merge_limit = self.config.limit.filter(|_| merged_filters.is_empty());
+
+            let pk_ranges = self.split_pk_ranges(&schema).await?;
+            let merge_stream: SendableRecordBatchStream = if pk_ranges.len() > 1 {
+                // rows of a primary key all fall into the same range, so ranges are merged independently
+                // and the outputs are concatenated in key order
+                let mergers = try_join_all(pk_ranges.into_iter().map(|range_filter| {
+                    let mut filters = pk_filters.clone();
+                    filters.push(range_filter);
+                    self.open_merger(schema.clone(), filters, merge_ops.clone(), merge_limit)
+                }))
+                .await?;
+                let receivers = mergers.into_iter().map(|mut merger| {
+                    let (tx, rx) = tokio::sync::mpsc::channel(MERGE_RANGE_BUFFER_SIZE);
+                    tokio::spawn(async move {
+                        while let Some(batch) = merger.next().await {
+                            if tx.send(batch).await.is_err() {
+                                break;
+                            }
+                        }
+                    });
+                    ReceiverStream::new(rx)
+                });
+                Box::pin(RecordBatchStreamAdapter::new(
+                    schema.clone(),
+                    futures::stream::iter(receivers.collect::<Vec<_>>()).flatten(),
+                ))
+            } else {
+                Box::pin(
+                    self.open_merger(schema.clone(), pk_filters, merge_ops, merge_limit)
+                        .await?,
+                )
+            };
             let finalized_stream = DefaultColumnStream::new_from_streams_with_default(
-                vec![Box::pin(merge_stream)],
+                vec![merge_stream],
                 finalize_schema.clone(),
                 Arc::new(self.config.default_column_value.clone()),
             );
-            self.schema = Some(finalized_stream.schema());
-            self.stream = Some(Box::pin(finalized_stream));
+            let filtered_stream = FilterStream::try_new(Box::pin(finalized_stream), merged_filters)?;
+            let projected_stream = ProjectionStream::try_new(filtered_stream, self.projections.clone())?;
+            let limited_stream = limit_stream(projected_stream, self.config.limit);
+            self.schema = Some(limited_stream.schema());
+            self.stream = Some(limited_stream);
             Ok(())
         }
     }
 
+    // opens all files with `filters` pushed down into the scans and merges them by primary keys
+    async fn open_merger(
+        &self,
+        schema: SchemaRef,
+        filters: Vec<Expr>,
+        merge_ops: Vec<MergeOperator>,
+        limit: Option<usize>,
+    ) -> Result<SortedStreamMerger> {
+        let batch_size = self.config.batch_size;
+        let default_column_value = Arc::new(self.config.default_column_value.clone());
+        let mut stream_init_futs = Vec::with_capacity(self.config.files.len());
+        for i in 0..self.config.files.len() {
+            let file = self.config.files[i].clone();
+            let sess_ctx = self.sess_ctx.clone();
+            let schema = schema.clone();
+            let filters = filters.clone();
+            let default_column_value = default_column_value.clone();
+            let future = async move {
+                let df = sess_ctx.read_parquet(file.as_str(), Default::default()).await?;
+                LakeSoulReader::prune_filter_and_execute(df, schema, filters, default_column_value, batch_size, None)
+                    .await
+            };
+            stream_init_futs.push(future);
+        }
+
+        let stream_res = try_join_all(stream_init_futs).await?;
+        let streams = stream_res
+            .into_iter()
+            .map(|s| SortedStream::new(Box::pin(DefaultColumnStream::new_from_stream(s, schema.clone()))))
+            .collect();
+
+        Ok(SortedStreamMerger::new_from_streams(
+            streams,
+            schema,
+            self.config.primary_keys.clone(),
+            self.config.batch_size,
+            merge_ops,
+        )?
+        .with_limit(limit)
+        .with_cdc_column(
+            Some(self.config.cdc_column.as_str()).filter(|cdc_column| !cdc_column.is_empty()),
+            self.config.keep_deletes,
+        )?)
+    }
+
+    // filters splitting the first primary key into at most merge_parallelism ranges by parquet statistics,
+    // a single range if the statistics are missing
+    async fn split_pk_ranges(&self, schema: &SchemaRef) -> Result<Vec<Expr>> {
+        if self.config.merge_parallelism <= 1 {
+            return Ok(vec![]);
+        }
+        let pk = schema.field_with_name(&self.config.primary_keys[0])?;
+        let points = match row_group_bounds(&self.sess_ctx, &self.config.files, pk).await? {
+            Some(bounds) => split_points(bounds, self.config.merge_parallelism),
+            None => vec![],
+        };
+        Ok(range_filters(pk.name(), &points))
+    }
+
+    fn is_pk_only_filter(&self, filter: &Expr) -> bool {
+        match filter.to_columns() {
+            Ok(columns) => columns
+                .iter()
+                .all(|column| self.config.primary_keys.contains(&column.name)),
+            Err(_) => false,
+        }
+    }
+
+    // split filter exprs into those referencing only primary keys and the others
+    fn split_pk_filters(&self) -> (Vec<Expr>, Vec<Expr>) {
+        self.config
+            .filters
+            .iter()
+            .cloned()
+            .partition(|f| self.is_pk_only_filter(f))
+    }
+
     pub async fn next_rb(&mut self) -> Option<Result<RecordBatch>> {
         if let Some(stream) = &mut self.stream {
             stream.next().await
@@ -208,6 +414,28 @@ impl LakeSoulReader {
     }
 }
 
+// truncates `stream` to `limit` rows, without polling the input any more once the limit is reached
+fn limit_stream(stream: SendableRecordBatchStream, limit: Option<usize>) -> SendableRecordBatchStream {
+    let Some(limit) = limit else {
+        return stream;
+    };
+    let schema = stream.schema();
+    let limited = futures::stream::unfold((stream, limit), |(mut stream, remaining)| async move {
+        if remaining == 0 {
+            return None;
+        }
+        match stream.next().await? {
+            Ok(batch) => {
+                let batch = batch.slice(0, batch.num_rows().min(remaining));
+                let remaining = remaining - batch.num_rows();
+                Some((Ok(batch), (stream, remaining)))
+            }
+            Err(e) => Some((Err(e), (stream, 0))),
+        }
+    });
+    Box::pin(RecordBatchStreamAdapter::new(schema, limited))
+}
+
 // Reader will be used in async closure sent to tokio
 // while accessing its mutable methods.
 pub struct SyncSendableMutableLakeSoulReader {
@@ -296,6 +524,23 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_reader_invalid_filter() {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, false)]));
+        let reader_conf = LakeSoulIOConfigBuilder::new()
+            .with_schema(schema)
+            .with_filter_str("and(gt(id, 1), lt(missing, 2))".to_string())
+            .build();
+        let err = LakeSoulReader::new(reader_conf)
+            .err()
+            .expect("invalid filter should be reported");
+        assert!(
+            err.to_string().contains("at position 18: unknown column missing"),
+            "{}",
+            err
+        );
+    }
+
     #[test]
     fn test_reader_local_blocked() -> Result<()> {
         let project_dir = std::env::current_dir()?;
@@ -695,4 +940,486 @@ mod tests {
 
         Ok(())
     }
+
+    fn write_pk_file(path: &std::path::Path, ids: Vec<i64>, values: Vec<i64>) -> Result<String> {
+        use arrow_array::{ArrayRef, Int64Array};
+        use parquet::arrow::ArrowWriter;
+
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+            ("value", Arc::new(Int64Array::from(values)) as ArrayRef),
+        ])?;
+        let file = std::fs::File::create(path)?;
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(path.to_str().unwrap().to_string())
+    }
+
+    async fn read_pk_files_with_filter(files: Vec<String>, filter_str: String) -> Result<Vec<RecordBatch>> {
+        let reader_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(files)
+            .with_primary_keys(vec!["id".to_string()])
+            .with_schema(Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new("value", DataType::Int64, true),
+            ])))
+            .with_filter_str(filter_str)
+            .build();
+        let mut reader = LakeSoulReader::new(reader_conf)?;
+        reader.start().await?;
+        let mut batches = vec![];
+        while let Some(rb) = reader.next_rb().await {
+            batches.push(rb?);
+        }
+        Ok(batches)
+    }
+
+    #[test]
+    fn test_reader_invalid_merge_op() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let reader_conf = LakeSoulIOConfigBuilder::new()
+            .with_schema(schema)
+            .with_primary_keys(vec!["id".to_string()])
+            .with_merge_op("name".to_string(), "Count".to_string())
+            .build();
+        assert!(matches!(
+            LakeSoulReader::new(reader_conf),
+            Err(DataFusionError::Configuration(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_pk_reader_with_filter() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let files = vec![
+            write_pk_file(&temp_dir.path().join("base.parquet"), vec![1, 2, 3], vec![10, 20, 30])?,
+            write_pk_file(&temp_dir.path().join("delta.parquet"), vec![2, 3], vec![5, 35])?,
+        ];
+
+        // non primary key filter must be evaluated on merged rows
+        let batches = read_pk_files_with_filter(files.clone(), "gt(value, 8)".to_string()).await?;
+        datafusion::assert_batches_eq!(
+            vec!["+----+-------+", "| id | value |", "+----+-------+", "| 1  | 10    |", "| 3  | 35    |", "+----+-------+",],
+            &batches
+        );
+
+        // primary key filter is pushed down into file scans
+        let batches = read_pk_files_with_filter(files, "lt(id, 3)".to_string()).await?;
+        datafusion::assert_batches_eq!(
+            vec!["+----+-------+", "| id | value |", "+----+-------+", "| 1  | 10    |", "| 2  | 5     |", "+----+-------+",],
+            &batches
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pk_reader_with_sql() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let files = vec![
+            write_pk_file(&temp_dir.path().join("base.parquet"), vec![1, 2, 3], vec![10, 20, 30])?,
+            write_pk_file(&temp_dir.path().join("delta.parquet"), vec![2, 3], vec![5, 35])?,
+        ];
+        let reader_conf = LakeSoulIOConfigBuilder::new()
+            .with_files(files)
+            .with_primary_keys(vec!["id".to_string()])
+            .with_schema(Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new("value", DataType::Int64, true),
+            ])))
+            .with_sql_filter("value > 8 AND id IN (1, 2, 3)".to_string())
+            .with_sql_projection("id".to_string())
+            .with_sql_projection("value * 2 AS doubled".to_string())
+            .build();
+        let mut reader = LakeSoulReader::new(reader_conf)?;
+        reader.start().await?;
+        let mut batches = vec![];
+        while let Some(rb) = reader.next_rb().await {
+            batches.push(rb?);
+        }
+        datafusion::assert_batches_eq!(
+            vec![
+                "+----+---------+",
+                "| id | doubled |",
+                "+----+---------+",
+                "| 1  | 20      |",
+                "| 3  | 70      |",
+                "+----+---------+",
+            ],
+            &batches
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_with_limit() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let files = vec![
+            write_pk_file(&temp_dir.path().join("base.parquet"), vec![1, 2, 3], vec![10, 20, 30])?,
+            write_pk_file(&temp_dir.path().join("delta.parquet"), vec![2, 3], vec![5, 35])?,
+        ];
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("value", DataType::Int64, true),
+        ]));
+        let read = |builder: LakeSoulIOConfigBuilder| async move {
+            let mut reader = LakeSoulReader::new(builder.build())?;
+            reader.start().await?;
+            let mut num_rows = 0;
+            while let Some(rb) = reader.next_rb().await {
+                num_rows += rb?.num_rows();
+            }
+            Result::Ok(num_rows)
+        };
+        let builder = LakeSoulIOConfigBuilder::new()
+            .with_files(files)
+            .with_schema(schema)
+            .with_limit(4);
+
+        // without primary keys, the first file is read fully and the second partially
+        assert_eq!(read(builder.clone()).await?, 4);
+        // merged rows are limited
+        assert_eq!(
+            read(builder.clone().with_primary_keys(vec!["id".to_string()])).await?,
+            3
+        );
+        // the limit applies after filters on merged rows
+        assert_eq!(
+            read(
+                builder
+                    .with_primary_keys(vec!["id".to_string()])
+                    .with_limit(1)
+                    .with_filter_str("gt(value, 8)".to_string())
+            )
+            .await?,
+            1
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reader_with_filter_on_missing_column() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let files = vec![write_pk_file(
+            &temp_dir.path().join("base.parquet"),
+            vec![1, 2, 3],
+            vec![10, 20, 30],
+        )?];
+        // date is a range partition column and extra is added by schema evolution, neither is in the file
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("value", DataType::Int64, true),
+            Field::new("date", DataType::Utf8, true),
+            Field::new("extra", DataType::Int64, true),
+        ]));
+        let read = |sql_filter: &str, limit: Option<usize>| {
+            let mut builder = LakeSoulIOConfigBuilder::new()
+                .with_files(files.clone())
+                .with_schema(schema.clone())
+                .with_default_column_value("date".to_string(), "20230101".to_string())
+                .with_sql_filter(sql_filter.to_string());
+            if let Some(limit) = limit {
+                builder = builder.with_limit(limit);
+            }
+            async move {
+                let mut reader = LakeSoulReader::new(builder.build())?;
+                reader.start().await?;
+                let mut num_rows = 0;
+                while let Some(rb) = reader.next_rb().await {
+                    num_rows += rb?.num_rows();
+                }
+                Result::Ok(num_rows)
+            }
+        };
+        assert_eq!(read("date = '20230102'", None).await?, 0);
+        assert_eq!(read("date = '20230101' AND value > 10", None).await?, 2);
+        assert_eq!(read("date = '20230101'", Some(2)).await?, 2);
+        assert_eq!(read("extra > 1", None).await?, 0);
+        assert_eq!(read("extra IS NULL", Some(1)).await?, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pk_reader_with_merge_parallelism() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let files = vec![
+            write_pk_file(
+                &temp_dir.path().join("base.parquet"),
+                (0..100).collect(),
+                (0..100).collect(),
+            )?,
+            write_pk_file(
+                &temp_dir.path().join("delta.parquet"),
+                (0..100).step_by(3).collect(),
+                (0..100).step_by(3).map(|i| i * 10).collect(),
+            )?,
+            write_pk_file(&temp_dir.path().join("tail.parquet"), vec![99, 150], vec![-1, -2])?,
+        ];
+        let read = |merge_parallelism: usize| {
+            let files = files.clone();
+            async move {
+                let reader_conf = LakeSoulIOConfigBuilder::new()
+                    .with_files(files)
+                    .with_primary_keys(vec!["id".to_string()])
+                    .with_schema(Arc::new(Schema::new(vec![
+                        Field::new("id", DataType::Int64, false),
+                        Field::new("value", DataType::Int64, true),
+                    ])))
+                    .with_merge_parallelism(merge_parallelism)
+                    .build();
+                let mut reader = LakeSoulReader::new(reader_conf)?;
+                reader.start().await?;
+                let mut batches = vec![];
+                while let Some(rb) = reader.next_rb().await {
+                    batches.push(rb?);
+                }
+                Result::Ok(arrow::compute::concat_batches(&batches[0].schema(), &batches)?)
+            }
+        };
+
+        let expected = read(1).await?;
+        assert_eq!(expected.num_rows(), 101);
+        assert_eq!(read(4).await?, expected);
+        assert_eq!(read(200).await?, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pk_reader_with_registered_merge_op() -> Result<()> {
+        use crate::sorted_merge::merge_operator::{register_merge_function, MergeFunction, MergeResult};
+        use crate::sorted_merge::sort_key_range::SortKeyArrayRange;
+        use arrow::array::{as_primitive_array, ArrayBuilder, Int64Builder};
+        use arrow_array::types::Int64Type;
+        use smallvec::SmallVec;
+
+        #[derive(Debug)]
+        struct Max;
+
+        impl MergeFunction for Max {
+            fn merge(
+                &self,
+                _data_type: DataType,
+                ranges: &SmallVec<[SortKeyArrayRange; 4]>,
+                append_array_data_builder: &mut Box<dyn ArrayBuilder>,
+            ) -> ArrowResult<MergeResult> {
+                let max = ranges
+                    .iter()
+                    .flat_map(|range| {
+                        let array = range.array();
+                        as_primitive_array::<Int64Type>(array.as_ref())
+                            .slice(range.begin_row(), range.end_row() - range.begin_row())
+                            .iter()
+                            .flatten()
+                            .collect::<Vec<_>>()
+                    })
+                    .max();
+                match max {
+                    Some(max) => {
+                        let builder = append_array_data_builder
+                            .as_any_mut()
+                            .downcast_mut::<Int64Builder>()
+                            .unwrap();
+                        builder.append_value(max);
+                        Ok(MergeResult::AppendValue(builder.len() - 1))
+                    }
+                    None => Ok(MergeResult::AppendNull),
+                }
+            }
+        }
+
+        register_merge_function("TestMax", Arc::new(Max))?;
+        let temp_dir = tempfile::tempdir()?;
+        let files = vec![
+            write_pk_file(&temp_dir.path().join("base.parquet"), vec![1, 2, 3], vec![10, 20, 30])?,
+            write_pk_file(&temp_dir.path().join("delta.parquet"), vec![2, 3], vec![5, 35])?,
+        ];
+        let builder = LakeSoulIOConfigBuilder::new()
+            .with_files(files)
+            .with_primary_keys(vec!["id".to_string()])
+            .with_schema(Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new("value", DataType::Int64, true),
+            ])));
+
+        let reader_conf = builder
+            .clone()
+            .with_merge_op("value".to_string(), "TestMax".to_string())
+            .build();
+        let mut reader = LakeSoulReader::new(reader_conf)?;
+        reader.start().await?;
+        let mut batches = vec![];
+        while let Some(rb) = reader.next_rb().await {
+            batches.push(rb?);
+        }
+        datafusion::assert_batches_eq!(
+            vec![
+                "+----+-------+",
+                "| id | value |",
+                "+----+-------+",
+                "| 1  | 10    |",
+                "| 2  | 20    |",
+                "| 3  | 35    |",
+                "+----+-------+",
+            ],
+            &batches
+        );
+
+        let reader_conf = builder
+            .with_merge_op("value".to_string(), "Unknown".to_string())
+            .build();
+        assert!(matches!(
+            LakeSoulReader::new(reader_conf),
+            Err(DataFusionError::Configuration(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pk_reader_with_cdc_column() -> Result<()> {
+        use arrow_array::{ArrayRef, Int64Array, StringArray};
+        use parquet::arrow::ArrowWriter;
+
+        fn write_cdc_file(path: &std::path::Path, ids: Vec<i64>, values: Vec<i64>, kinds: Vec<&str>) -> Result<String> {
+            let batch = RecordBatch::try_from_iter(vec![
+                ("id", Arc::new(Int64Array::from(ids)) as ArrayRef),
+                ("value", Arc::new(Int64Array::from(values)) as ArrayRef),
+                ("rowKinds", Arc::new(StringArray::from(kinds)) as ArrayRef),
+            ])?;
+            let file = std::fs::File::create(path)?;
+            let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+            writer.write(&batch)?;
+            writer.close()?;
+            Ok(path.to_str().unwrap().to_string())
+        }
+
+        async fn read(builder: LakeSoulIOConfigBuilder) -> Result<Vec<RecordBatch>> {
+            let mut reader = LakeSoulReader::new(builder.build())?;
+            reader.start().await?;
+            let mut batches = vec![];
+            while let Some(rb) = reader.next_rb().await {
+                batches.push(rb?);
+            }
+            Ok(batches)
+        }
+
+        let temp_dir = tempfile::tempdir()?;
+        let files = vec![
+            write_cdc_file(
+                &temp_dir.path().join("base.parquet"),
+                vec![1, 2, 3],
+                vec![10, 20, 30],
+                vec!["insert", "insert", "insert"],
+            )?,
+            write_cdc_file(
+                &temp_dir.path().join("delta1.parquet"),
+                vec![1, 2],
+                vec![11, 20],
+                vec!["update", "delete"],
+            )?,
+            write_cdc_file(
+                &temp_dir.path().join("delta2.parquet"),
+                vec![2, 3],
+                vec![22, 30],
+                vec!["insert", "delete"],
+            )?,
+        ];
+        let builder = LakeSoulIOConfigBuilder::new()
+            .with_files(files)
+            .with_primary_keys(vec!["id".to_string()])
+            .with_cdc_column("rowKinds".to_string());
+
+        // the cdc column is merged even if it is not read, a key deleted and inserted again is kept
+        let batches = read(builder.clone().with_schema(Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("value", DataType::Int64, true),
+        ]))))
+        .await?;
+        datafusion::assert_batches_eq!(
+            vec![
+                "+----+-------+",
+                "| id | value |",
+                "+----+-------+",
+                "| 1  | 11    |",
+                "| 2  | 22    |",
+                "+----+-------+",
+            ],
+            &batches
+        );
+
+        let batches = read(
+            builder
+                .with_schema(Arc::new(Schema::new(vec![
+                    Field::new("id", DataType::Int64, false),
+                    Field::new("value", DataType::Int64, true),
+                    Field::new("rowKinds", DataType::Utf8, true),
+                ])))
+                .with_merge_op("rowKinds".to_string(), "UseFirst".to_string())
+                .with_keep_deletes(true),
+        )
+        .await?;
+        datafusion::assert_batches_eq!(
+            vec![
+                "+----+-------+----------+",
+                "| id | value | rowKinds |",
+                "+----+-------+----------+",
+                "| 1  | 11    | update   |",
+                "| 2  | 22    | insert   |",
+                "| 3  | 30    | delete   |",
+                "+----+-------+----------+",
+            ],
+            &batches
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_row_group_and_page_pruning() -> Result<()> {
+        use arrow_array::{ArrayRef, Int64Array};
+        use datafusion::physical_plan::{collect, ExecutionPlan};
+        use parquet::arrow::ArrowWriter;
+        use parquet::file::properties::WriterProperties;
+
+        fn sum_metric(plan: &Arc<dyn ExecutionPlan>, name: &str) -> usize {
+            plan.metrics()
+                .and_then(|metrics| metrics.sum_by_name(name))
+                .map(|value| value.as_usize())
+                .unwrap_or(0)
+                + plan
+                    .children()
+                    .iter()
+                    .map(|child| sum_metric(child, name))
+                    .sum::<usize>()
+        }
+
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path().join("pruning.parquet");
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from_iter_values(0..400)) as ArrayRef),
+            ("value", Arc::new(Int64Array::from_iter_values((0..400).map(|i| i * 10))) as ArrayRef),
+        ])?;
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(100)
+            .set_data_page_row_count_limit(10)
+            .set_write_batch_size(10)
+            .build();
+        let mut writer = ArrowWriter::try_new(std::fs::File::create(&path)?, batch.schema(), Some(props))?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        let mut conf = LakeSoulIOConfigBuilder::new()
+            .with_files(vec![path.to_str().unwrap().to_string()])
+            .build();
+        let sess_ctx = create_session_context(&mut conf)?;
+        let df = sess_ctx.read_parquet(conf.files[0].clone(), Default::default()).await?;
+        let filter = FilterParser::parse("gt(id, 345)".to_string(), Arc::new(Schema::from(df.schema().clone())))?;
+        let plan = df.filter(filter)?.create_physical_plan().await?;
+        let batches = collect(plan.clone(), sess_ctx.task_ctx()).await?;
+        assert_eq!(batches.iter().map(|batch| batch.num_rows()).sum::<usize>(), 54);
+        assert_eq!(sum_metric(&plan, "row_groups_pruned"), 3);
+        assert!(sum_metric(&plan, "page_index_rows_filtered") > 0);
+        Ok(())
+    }
 }
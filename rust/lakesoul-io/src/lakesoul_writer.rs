@@ -2,12 +2,20 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::constant::{
+    LAKESOUL_EMPTY_STRING, LAKESOUL_NON_PARTITION_TABLE_PART_DESC, LAKESOUL_NULL_STRING,
+    LAKESOUL_PARTITION_DESC_KV_DELIM, LAKESOUL_RANGE_PARTITION_SPLITTER,
+};
+use crate::hash_utils::spark_hash_bucket_ids;
 use crate::lakesoul_io_config::{create_session_context, IOSchema, LakeSoulIOConfig};
+use crate::statistics::{column_statistics_from_metadata, ColumnStatistics};
 use crate::transform::{uniform_record_batch, uniform_schema};
 
-use arrow::compute::SortOptions;
+use arrow::array::{as_string_array, Array, UInt32Array};
+use arrow::compute::{cast, take, SortOptions};
 use arrow::record_batch::RecordBatch;
-use arrow_schema::SchemaRef;
+use arrow::row::{OwnedRow, RowConverter, SortField};
+use arrow_schema::{DataType, SchemaRef};
 use async_trait::async_trait;
 use atomic_refcell::AtomicRefCell;
 use datafusion::datasource::object_store::ObjectStoreUrl;
@@ -21,15 +29,13 @@ use datafusion::physical_plan::stream::{RecordBatchReceiverStream, RecordBatchRe
 use datafusion::physical_plan::{ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics};
 use datafusion::prelude::SessionContext;
 use datafusion_common::DataFusionError;
-use datafusion_common::DataFusionError::Internal;
+use datafusion_common::DataFusionError::{Configuration, Internal};
 use object_store::path::Path;
 use object_store::{MultipartId, ObjectStore};
 use parquet::arrow::ArrowWriter;
-use parquet::basic::Compression;
-use parquet::file::properties::WriterProperties;
 use std::any::Any;
 use std::borrow::Borrow;
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 use std::fmt::{Debug, Formatter};
 use std::io::ErrorKind::ResourceBusy;
 use std::io::Write;
@@ -43,11 +49,26 @@ use tokio::task::JoinHandle;
 use tokio_stream::StreamExt;
 use url::Url;
 
+/// Metadata of a written file, needed to commit it as a `DataFileOp`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlushResult {
+    pub path: String,
+    /// file size in bytes
+    pub size: u64,
+    pub num_rows: u64,
+    /// comma separated names of columns in the file
+    pub file_exist_cols: String,
+    pub column_statistics: Vec<ColumnStatistics>,
+    /// range partition of the file like `date=20230101,region=cn`, or `-5` if the table is not partitioned
+    pub partition_desc: String,
+}
+
 #[async_trait]
 pub trait AsyncBatchWriter {
     async fn write_record_batch(&mut self, batch: RecordBatch) -> Result<()>;
 
-    async fn flush_and_close(self: Box<Self>) -> Result<()>;
+    /// Returns metadata of the written files, in the order they were written.
+    async fn flush_and_close(self: Box<Self>) -> Result<Vec<FlushResult>>;
 
     async fn abort_and_close(self: Box<Self>) -> Result<()>;
 }
@@ -66,17 +87,73 @@ pub struct MultiPartAsyncWriter {
     writer: Box<dyn AsyncWrite + Unpin + Send>,
     multi_part_id: MultipartId,
     arrow_writer: ArrowWriter<InMemBuf>,
-    _config: LakeSoulIOConfig,
+    config: LakeSoulIOConfig,
     object_store: Arc<dyn ObjectStore>,
     path: Path,
+    // bytes uploaded so far
+    size: u64,
+}
+
+/// An async writer rolling over `MultiPartAsyncWriter`s of files under `config.prefix`.
+/// A new file is started once the current one reaches `config.max_file_size` or `config.max_file_rows`.
+/// With primary keys, the incoming batches are expected to be sorted, and a new file is only started
+/// at the first row with a different key from the last written row, so that rows of a key are never
+/// split across files.
+pub struct RollingAsyncWriter {
+    config: LakeSoulIOConfig,
+    // unique id in file names of this writer, followed by the bucket id of bucketed writers
+    file_id: String,
+    writer: MultiPartAsyncWriter,
+    // rows written to the current file
+    num_rows: u64,
+    flushed: Vec<FlushResult>,
+    flushed_paths: Vec<Path>,
+    // primary key columns and their row converter, empty and none without primary keys
+    key_indices: Vec<usize>,
+    key_converter: Option<RowConverter>,
+    last_key: Option<OwnedRow>,
+}
+
+/// An async writer routing rows into `config.hash_bucket_num` buckets by the Spark compatible murmur3 hash
+/// of `config.hash_columns`, the same as hash partitioning of LakeSoul tables on the JVM side.
+/// Rows of each bucket are written by a `RollingAsyncWriter`, sorted by a `SortAsyncWriter` if primary keys exist,
+/// into files named with the bucket id, e.g. `part-00000-<uuid>_00003.parquet`.
+pub struct HashBucketAsyncWriter {
+    // config of input batches, which may contain aux sort cols
+    config: LakeSoulIOConfig,
+    // config of written files
+    writer_config: LakeSoulIOConfig,
+    runtime: Arc<Runtime>,
+    // shared by file names of all buckets
+    write_id: String,
+    hash_indices: Vec<usize>,
+    // writers of buckets which have received rows, ordered by bucket id
+    writers: BTreeMap<usize, SendableWriter>,
 }
 
-/// Wrap the above async writer with a SortExec to
+/// An async writer splitting rows by values of `config.range_partitions` into files under `col=value`
+/// directories of `config.prefix`, with range partition columns dropped from files. Rows of each partition
+/// are written by a rolling writer, which is bucketed and sorted if required.
+pub struct PartitioningAsyncWriter {
+    // config of input batches
+    config: LakeSoulIOConfig,
+    // config of written files
+    writer_config: LakeSoulIOConfig,
+    runtime: Arc<Runtime>,
+    partition_indices: Vec<usize>,
+    // indices of columns written into files
+    file_column_indices: Vec<usize>,
+    // writers of partitions which have received rows, keyed by partition desc
+    writers: BTreeMap<String, SendableWriter>,
+}
+
+/// Wrap the above async writers with a SortExec to
 /// sort the batches before write to async writer
 pub struct SortAsyncWriter {
     sorter_sender: Sender<Result<RecordBatch>>,
     _sort_exec: Arc<dyn ExecutionPlan>,
-    join_handle: JoinHandle<Result<()>>,
+    // None if aborted
+    join_handle: JoinHandle<Result<Option<Vec<FlushResult>>>>,
 }
 
 /// A VecDeque which is both std::io::Write and bytes::Buf
@@ -170,6 +247,8 @@ impl MultiPartAsyncWriter {
         if config.files.len() != 1 {
             return Err(Internal("wrong number of file names provided for writer".to_string()));
         }
+        // validate writer options before starting the upload
+        let writer_properties = config.writer_properties()?;
         let sess_ctx = create_session_context(&mut config)?;
         let file_name = &config.files[0];
 
@@ -194,13 +273,7 @@ impl MultiPartAsyncWriter {
         let arrow_writer = ArrowWriter::try_new(
             in_mem_buf.clone(),
             uniform_schema(schema.clone()),
-            Some(
-                WriterProperties::builder()
-                    .set_max_row_group_size(config.max_row_group_size)
-                    .set_write_batch_size(config.batch_size)
-                    .set_compression(Compression::SNAPPY)
-                    .build(),
-            ),
+            Some(writer_properties),
         )?;
 
         Ok(MultiPartAsyncWriter {
@@ -210,9 +283,10 @@ impl MultiPartAsyncWriter {
             writer: async_writer,
             multi_part_id: multipart_id,
             arrow_writer,
-            _config: config,
+            config,
             object_store,
             path,
+            size: 0,
         })
     }
 
@@ -221,6 +295,7 @@ impl MultiPartAsyncWriter {
         arrow_writer: &mut ArrowWriter<InMemBuf>,
         in_mem_buf: &mut InMemBuf,
         writer: &mut Box<dyn AsyncWrite + Unpin + Send>,
+        size: &mut u64,
     ) -> Result<()> {
         arrow_writer.write(&batch)?;
         let mut v = in_mem_buf
@@ -228,6 +303,7 @@ impl MultiPartAsyncWriter {
             .try_borrow_mut()
             .map_err(|e| Internal(format!("{:?}", e)))?;
         if v.len() > 0 {
+            *size += v.len() as u64;
             MultiPartAsyncWriter::write_part(writer, &mut v).await
         } else {
             Ok(())
@@ -241,40 +317,491 @@ impl MultiPartAsyncWriter {
         writer.write_all_buf(in_mem_buf).await?;
         Ok(())
     }
-}
-
-#[async_trait]
-impl AsyncBatchWriter for MultiPartAsyncWriter {
-    async fn write_record_batch(&mut self, batch: RecordBatch) -> Result<()> {
-        let batch = uniform_record_batch(batch)?;
-        MultiPartAsyncWriter::write_batch(batch, &mut self.arrow_writer, &mut self.in_mem_buf, &mut self.writer).await
-    }
 
-    async fn flush_and_close(self: Box<Self>) -> Result<()> {
+    async fn close_file(mut self) -> Result<FlushResult> {
         // close arrow writer to flush remaining rows
-        let mut this = *self;
-        let arrow_writer = this.arrow_writer;
-        arrow_writer.close()?;
-        let mut v = this
+        let metadata = self.arrow_writer.close()?;
+        let mut v = self
             .in_mem_buf
             .0
             .try_borrow_mut()
             .map_err(|e| Internal(format!("{:?}", e)))?;
         if v.len() > 0 {
-            MultiPartAsyncWriter::write_part(&mut this.writer, &mut v).await?;
+            self.size += v.len() as u64;
+            MultiPartAsyncWriter::write_part(&mut self.writer, &mut v).await?;
         }
+        drop(v);
         // shutdown multi part async writer to complete the upload
-        this.writer.flush().await?;
-        this.writer.shutdown().await?;
+        self.writer.flush().await?;
+        self.writer.shutdown().await?;
+        Ok(FlushResult {
+            path: self.config.files[0].clone(),
+            size: self.size,
+            num_rows: metadata.num_rows as u64,
+            file_exist_cols: self
+                .schema
+                .fields()
+                .iter()
+                .map(|field| field.name().as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+            column_statistics: column_statistics_from_metadata(self.schema.clone(), &metadata)?,
+            partition_desc: LAKESOUL_NON_PARTITION_TABLE_PART_DESC.to_string(),
+        })
+    }
+
+    async fn abort_file(self) -> Result<()> {
+        self.object_store
+            .abort_multipart(&self.path, &self.multi_part_id)
+            .await
+            .map_err(DataFusionError::ObjectStore)
+    }
+}
+
+#[async_trait]
+impl AsyncBatchWriter for MultiPartAsyncWriter {
+    async fn write_record_batch(&mut self, batch: RecordBatch) -> Result<()> {
+        let batch = uniform_record_batch(batch)?;
+        MultiPartAsyncWriter::write_batch(
+            batch,
+            &mut self.arrow_writer,
+            &mut self.in_mem_buf,
+            &mut self.writer,
+            &mut self.size,
+        )
+        .await
+    }
+
+    async fn flush_and_close(self: Box<Self>) -> Result<Vec<FlushResult>> {
+        Ok(vec![self.close_file().await?])
+    }
+
+    async fn abort_and_close(self: Box<Self>) -> Result<()> {
+        self.abort_file().await
+    }
+}
+
+impl RollingAsyncWriter {
+    pub async fn try_new(config: LakeSoulIOConfig) -> Result<Self> {
+        RollingAsyncWriter::try_new_with_file_id(config, uuid::Uuid::new_v4().to_string()).await
+    }
+
+    async fn try_new_with_file_id(config: LakeSoulIOConfig, file_id: String) -> Result<Self> {
+        if config.prefix.is_empty() || !config.files.is_empty() {
+            return Err(Configuration(
+                "rolling writer requires a prefix and no file names".to_string(),
+            ));
+        }
+        if config.max_file_size == Some(0) || config.max_file_rows == Some(0) {
+            return Err(Configuration("max file size and rows should be positive".to_string()));
+        }
+        let writer = MultiPartAsyncWriter::try_new(RollingAsyncWriter::file_config(&config, &file_id, 0)).await?;
+        let key_indices = config
+            .primary_keys
+            .iter()
+            .map(|pk| writer.schema.index_of(pk))
+            .collect::<std::result::Result<Vec<usize>, _>>()?;
+        let key_converter = if key_indices.is_empty() {
+            None
+        } else {
+            Some(RowConverter::new(
+                key_indices
+                    .iter()
+                    .map(|idx| SortField::new(writer.schema.field(*idx).data_type().clone()))
+                    .collect(),
+            )?)
+        };
+        Ok(RollingAsyncWriter {
+            config,
+            file_id,
+            writer,
+            num_rows: 0,
+            flushed: vec![],
+            flushed_paths: vec![],
+            key_indices,
+            key_converter,
+            last_key: None,
+        })
+    }
+
+    fn file_config(config: &LakeSoulIOConfig, file_id: &str, index: usize) -> LakeSoulIOConfig {
+        let mut file_config = config.clone();
+        file_config.files = vec![format!(
+            "{}/part-{:05}-{}.parquet",
+            config.prefix.trim_end_matches('/'),
+            index,
+            file_id
+        )];
+        file_config
+    }
+
+    fn reached_limit(&self) -> bool {
+        self.num_rows > 0
+            && (matches!(self.config.max_file_rows, Some(max_file_rows) if self.num_rows >= max_file_rows)
+                || matches!(self.config.max_file_size, Some(max_file_size) if self.writer.size >= max_file_size))
+    }
+
+    // number of rows that could be written to the current file before reaching max_file_rows
+    fn rows_before_limit(&self, num_rows: usize) -> usize {
+        match self.config.max_file_rows {
+            Some(max_file_rows) => num_rows.min((max_file_rows - self.num_rows) as usize),
+            None => num_rows,
+        }
+    }
+
+    // number of leading rows of the batch with the same key as the last written row
+    fn last_key_rows(&mut self, batch: &RecordBatch) -> Result<usize> {
+        let (Some(converter), Some(last_key)) = (self.key_converter.as_mut(), self.last_key.as_ref()) else {
+            return Ok(0);
+        };
+        let keys = self
+            .key_indices
+            .iter()
+            .map(|idx| batch.column(*idx).clone())
+            .collect::<Vec<_>>();
+        let rows = converter.convert_columns(&keys)?;
+        Ok((0..rows.num_rows())
+            .take_while(|idx| rows.row(*idx) == last_key.row())
+            .count())
+    }
+
+    async fn write_rows(&mut self, batch: RecordBatch) -> Result<()> {
+        if let Some(converter) = self.key_converter.as_mut() {
+            let last_row = batch.slice(batch.num_rows() - 1, 1);
+            let keys = self
+                .key_indices
+                .iter()
+                .map(|idx| last_row.column(*idx).clone())
+                .collect::<Vec<_>>();
+            self.last_key = Some(converter.convert_columns(&keys)?.row(0).owned());
+        }
+        self.num_rows += batch.num_rows() as u64;
+        self.writer.write_record_batch(batch).await
+    }
+
+    async fn roll(&mut self) -> Result<()> {
+        let config = RollingAsyncWriter::file_config(&self.config, &self.file_id, self.flushed.len() + 1);
+        let writer = MultiPartAsyncWriter::try_new(config).await?;
+        let writer = std::mem::replace(&mut self.writer, writer);
+        self.flushed_paths.push(writer.path.clone());
+        self.flushed.push(writer.close_file().await?);
+        self.num_rows = 0;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncBatchWriter for RollingAsyncWriter {
+    async fn write_record_batch(&mut self, batch: RecordBatch) -> Result<()> {
+        // keys are compared in the uniformed types of the written schema
+        let mut batch = uniform_record_batch(batch)?;
+        while batch.num_rows() > 0 {
+            let len = if self.reached_limit() {
+                // rows of the last written key stay in the current file
+                match self.last_key_rows(&batch)? {
+                    0 => {
+                        self.roll().await?;
+                        self.rows_before_limit(batch.num_rows())
+                    }
+                    len => len,
+                }
+            } else {
+                self.rows_before_limit(batch.num_rows())
+            };
+            self.write_rows(batch.slice(0, len)).await?;
+            batch = batch.slice(len, batch.num_rows() - len);
+        }
         Ok(())
     }
 
+    async fn flush_and_close(self: Box<Self>) -> Result<Vec<FlushResult>> {
+        let this = *self;
+        let mut flushed = this.flushed;
+        flushed.push(this.writer.close_file().await?);
+        Ok(flushed)
+    }
+
     async fn abort_and_close(self: Box<Self>) -> Result<()> {
         let this = *self;
-        this.object_store
-            .abort_multipart(&this.path, &this.multi_part_id)
-            .await
-            .map_err(DataFusionError::ObjectStore)
+        let object_store = this.writer.object_store.clone();
+        this.writer.abort_file().await?;
+        // remove files already completed
+        for path in this.flushed_paths {
+            object_store.delete(&path).await?;
+        }
+        Ok(())
+    }
+}
+
+impl HashBucketAsyncWriter {
+    pub fn try_new(config: LakeSoulIOConfig, writer_config: LakeSoulIOConfig, runtime: Arc<Runtime>) -> Result<Self> {
+        if config.hash_bucket_num == 0 {
+            return Err(Configuration("hash bucket num should be positive".to_string()));
+        }
+        let hash_columns = if config.hash_columns.is_empty() {
+            &config.primary_keys
+        } else {
+            &config.hash_columns
+        };
+        if hash_columns.is_empty() {
+            return Err(Configuration(
+                "no hash columns provided for hash bucket writer".to_string(),
+            ));
+        }
+        if writer_config.prefix.is_empty() {
+            return Err(Configuration("hash bucket writer requires a prefix".to_string()));
+        }
+        let hash_indices = hash_columns
+            .iter()
+            .map(|column| config.schema.0.index_of(column))
+            .collect::<std::result::Result<Vec<usize>, _>>()?;
+        Ok(HashBucketAsyncWriter {
+            config,
+            writer_config,
+            runtime,
+            write_id: uuid::Uuid::new_v4().to_string(),
+            hash_indices,
+            writers: BTreeMap::new(),
+        })
+    }
+
+    async fn bucket_writer(&mut self, bucket_id: usize) -> Result<&mut SendableWriter> {
+        if !self.writers.contains_key(&bucket_id) {
+            let file_id = format!("{}_{:05}", self.write_id, bucket_id);
+            let writer = RollingAsyncWriter::try_new_with_file_id(self.writer_config.clone(), file_id).await?;
+            let writer: SendableWriter = if self.config.primary_keys.is_empty() {
+                Box::new(writer)
+            } else {
+                let task_ctx = writer.writer.sess_ctx.task_ctx();
+                Box::new(SortAsyncWriter::try_new_with_writer(
+                    Box::new(writer),
+                    task_ctx,
+                    self.config.clone(),
+                    self.runtime.clone(),
+                )?)
+            };
+            self.writers.insert(bucket_id, writer);
+        }
+        Ok(self.writers.get_mut(&bucket_id).unwrap())
+    }
+}
+
+#[async_trait]
+impl AsyncBatchWriter for HashBucketAsyncWriter {
+    async fn write_record_batch(&mut self, batch: RecordBatch) -> Result<()> {
+        let hash_columns = self
+            .hash_indices
+            .iter()
+            .map(|idx| batch.column(*idx).clone())
+            .collect::<Vec<_>>();
+        let bucket_ids = spark_hash_bucket_ids(&hash_columns, batch.num_rows(), self.config.hash_bucket_num)?;
+        let mut bucket_rows: BTreeMap<usize, Vec<u32>> = BTreeMap::new();
+        for (row, bucket_id) in bucket_ids.into_iter().enumerate() {
+            bucket_rows.entry(bucket_id).or_default().push(row as u32);
+        }
+        for (bucket_id, rows) in bucket_rows {
+            let rows = UInt32Array::from(rows);
+            let columns = batch
+                .columns()
+                .iter()
+                .map(|column| take(column, &rows, None))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let bucket_batch = RecordBatch::try_new(batch.schema(), columns)?;
+            self.bucket_writer(bucket_id)
+                .await?
+                .write_record_batch(bucket_batch)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn flush_and_close(self: Box<Self>) -> Result<Vec<FlushResult>> {
+        let mut results = vec![];
+        for (_, writer) in self.writers {
+            results.extend(writer.flush_and_close().await?);
+        }
+        Ok(results)
+    }
+
+    async fn abort_and_close(self: Box<Self>) -> Result<()> {
+        for (_, writer) in self.writers {
+            writer.abort_and_close().await?;
+        }
+        Ok(())
+    }
+}
+
+impl PartitioningAsyncWriter {
+    pub fn try_new(config: LakeSoulIOConfig, writer_config: LakeSoulIOConfig, runtime: Arc<Runtime>) -> Result<Self> {
+        if config.prefix.is_empty() {
+            return Err(Configuration("partitioning writer requires a prefix".to_string()));
+        }
+        let partition_indices = config
+            .range_partitions
+            .iter()
+            .map(|column| config.schema.0.index_of(column))
+            .collect::<std::result::Result<Vec<usize>, _>>()?;
+        let file_column_indices = (0..config.schema.0.fields().len())
+            .filter(|idx| !partition_indices.contains(idx))
+            .collect();
+        Ok(PartitioningAsyncWriter {
+            config,
+            writer_config,
+            runtime,
+            partition_indices,
+            file_column_indices,
+            writers: BTreeMap::new(),
+        })
+    }
+
+    // configs of the input and files of a partition, which exclude range partition columns
+    fn partition_configs(&self, partition_dir: &str) -> Result<(LakeSoulIOConfig, LakeSoulIOConfig)> {
+        let exclude_partitions = |config: &LakeSoulIOConfig| -> Result<LakeSoulIOConfig> {
+            let mut partition_config = config.clone();
+            let schema = &config.schema.0;
+            let indices = (0..schema.fields().len())
+                .filter(|idx| !self.config.range_partitions.contains(schema.field(*idx).name()))
+                .collect::<Vec<_>>();
+            partition_config.schema = IOSchema(Arc::new(schema.project(&indices)?));
+            partition_config.prefix = format!("{}/{}", config.prefix.trim_end_matches('/'), partition_dir);
+            partition_config.range_partitions = vec![];
+            Ok(partition_config)
+        };
+        Ok((
+            exclude_partitions(&self.config)?,
+            exclude_partitions(&self.writer_config)?,
+        ))
+    }
+
+    async fn partition_writer(&mut self, partition_desc: &str, partition_dir: &str) -> Result<&mut SendableWriter> {
+        if !self.writers.contains_key(partition_desc) {
+            let (config, writer_config) = self.partition_configs(partition_dir)?;
+            let writer = create_prefix_writer(config, writer_config, self.runtime.clone()).await?;
+            self.writers.insert(partition_desc.to_string(), writer);
+        }
+        Ok(self.writers.get_mut(partition_desc).unwrap())
+    }
+}
+
+// escapes characters of partition values which are not allowed in paths, the same as spark
+fn escape_partition_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\u{01}'..='\u{1f}'
+            | '"'
+            | '#'
+            | '%'
+            | '\''
+            | '*'
+            | '/'
+            | ':'
+            | '='
+            | '?'
+            | '\\'
+            | '\u{7f}'
+            | '{'
+            | '['
+            | ']'
+            | '^' => escaped.push_str(&format!("%{:02X}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[async_trait]
+impl AsyncBatchWriter for PartitioningAsyncWriter {
+    async fn write_record_batch(&mut self, batch: RecordBatch) -> Result<()> {
+        let partition_values = self
+            .partition_indices
+            .iter()
+            .map(|idx| cast(batch.column(*idx), &DataType::Utf8))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        // partition desc of rows, with partition dir and row indices
+        let mut partition_rows: BTreeMap<String, (String, Vec<u32>)> = BTreeMap::new();
+        for row in 0..batch.num_rows() {
+            let mut desc = vec![];
+            let mut dir = vec![];
+            for (column, values) in self.config.range_partitions.iter().zip(partition_values.iter()) {
+                let values = as_string_array(values);
+                let value = if values.is_null(row) {
+                    LAKESOUL_NULL_STRING
+                } else if values.value(row).is_empty() {
+                    LAKESOUL_EMPTY_STRING
+                } else {
+                    values.value(row)
+                };
+                desc.push([column.as_str(), value].join(LAKESOUL_PARTITION_DESC_KV_DELIM));
+                dir.push([column.clone(), escape_partition_value(value)].join(LAKESOUL_PARTITION_DESC_KV_DELIM));
+            }
+            partition_rows
+                .entry(desc.join(LAKESOUL_RANGE_PARTITION_SPLITTER))
+                .or_insert_with(|| (dir.join("/"), vec![]))
+                .1
+                .push(row as u32);
+        }
+
+        let file_batch = batch.project(&self.file_column_indices)?;
+        for (desc, (dir, rows)) in partition_rows {
+            let rows = UInt32Array::from(rows);
+            let columns = file_batch
+                .columns()
+                .iter()
+                .map(|column| take(column, &rows, None))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let partition_batch = RecordBatch::try_new(file_batch.schema(), columns)?;
+            self.partition_writer(&desc, &dir)
+                .await?
+                .write_record_batch(partition_batch)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn flush_and_close(self: Box<Self>) -> Result<Vec<FlushResult>> {
+        let mut results = vec![];
+        for (desc, writer) in self.writers {
+            for mut result in writer.flush_and_close().await? {
+                result.partition_desc = desc.clone();
+                results.push(result);
+            }
+        }
+        Ok(results)
+    }
+
+    async fn abort_and_close(self: Box<Self>) -> Result<()> {
+        for (_, writer) in self.writers {
+            writer.abort_and_close().await?;
+        }
+        Ok(())
+    }
+}
+
+// creates a writer of rolling files under the prefix of `writer_config`, which is bucketed and sorted if required
+async fn create_prefix_writer(
+    config: LakeSoulIOConfig,
+    writer_config: LakeSoulIOConfig,
+    runtime: Arc<Runtime>,
+) -> Result<SendableWriter> {
+    if config.hash_bucket_num > 0 {
+        return Ok(Box::new(HashBucketAsyncWriter::try_new(
+            config,
+            writer_config,
+            runtime,
+        )?));
+    }
+    let writer = RollingAsyncWriter::try_new(writer_config).await?;
+    if config.primary_keys.is_empty() {
+        Ok(Box::new(writer))
+    } else {
+        let task_ctx = writer.writer.sess_ctx.task_ctx();
+        Ok(Box::new(SortAsyncWriter::try_new_with_writer(
+            Box::new(writer),
+            task_ctx,
+            config,
+            runtime,
+        )?))
     }
 }
 
@@ -283,6 +810,18 @@ impl SortAsyncWriter {
         async_writer: MultiPartAsyncWriter,
         config: LakeSoulIOConfig,
         runtime: Arc<Runtime>,
+    ) -> Result<Self> {
+        let task_ctx = async_writer.sess_ctx.task_ctx();
+        SortAsyncWriter::try_new_with_writer(Box::new(async_writer), task_ctx, config, runtime)
+    }
+
+    /// Sorts batches before writing them into `async_writer`, e.g. a [`RollingAsyncWriter`].
+    /// `task_ctx` is the context to execute the sort in.
+    pub fn try_new_with_writer(
+        mut async_writer: Box<dyn AsyncBatchWriter + Send>,
+        task_ctx: Arc<TaskContext>,
+        config: LakeSoulIOConfig,
+        runtime: Arc<Runtime>,
     ) -> Result<Self> {
         let _ = runtime.enter();
         let schema = config.schema.0.clone();
@@ -326,9 +865,8 @@ impl SortAsyncWriter {
             Arc::new(ProjectionExec::try_new(proj_expr, sort_exec)?)
         };
 
-        let mut sorted_stream = exec_plan.execute(0, async_writer.sess_ctx.task_ctx())?;
+        let mut sorted_stream = exec_plan.execute(0, task_ctx)?;
 
-        let mut async_writer = Box::new(async_writer);
         let join_handle = tokio::task::spawn(async move {
             while let Some(batch) = sorted_stream.next().await {
                 match batch {
@@ -336,11 +874,10 @@ impl SortAsyncWriter {
                         async_writer.write_record_batch(batch).await?;
                     }
                     // received abort singal
-                    Err(_) => return async_writer.abort_and_close().await,
+                    Err(_) => return async_writer.abort_and_close().await.map(|_| None),
                 }
             }
-            async_writer.flush_and_close().await?;
-            Ok(())
+            async_writer.flush_and_close().await.map(Some)
         });
 
         Ok(SortAsyncWriter {
@@ -360,12 +897,13 @@ impl AsyncBatchWriter for SortAsyncWriter {
             .map_err(|e| DataFusionError::External(Box::new(e)))
     }
 
-    async fn flush_and_close(self: Box<Self>) -> Result<()> {
+    async fn flush_and_close(self: Box<Self>) -> Result<Vec<FlushResult>> {
         let sender = self.sorter_sender;
         drop(sender);
         self.join_handle
             .await
-            .map_err(|e| DataFusionError::External(Box::new(e)))?
+            .map_err(|e| DataFusionError::External(Box::new(e)))??
+            .ok_or_else(|| Internal("writer has been aborted".to_string()))
     }
 
     async fn abort_and_close(self: Box<Self>) -> Result<()> {
@@ -379,6 +917,7 @@ impl AsyncBatchWriter for SortAsyncWriter {
         self.join_handle
             .await
             .map_err(|e| DataFusionError::External(Box::new(e)))?
+            .map(|_| ())
     }
 }
 
@@ -411,13 +950,46 @@ impl SyncSendableMutableLakeSoulWriter {
 
             let mut writer_config = config.clone();
             writer_config.schema = IOSchema(uniform_schema(writer_schema));
-            let writer = MultiPartAsyncWriter::try_new(writer_config).await?;
 
-            let schema = writer.schema.clone();
-            let writer: Box<dyn AsyncBatchWriter + Send> = if !config.primary_keys.is_empty() {
-                Box::new(SortAsyncWriter::try_new(writer, config, runtime.clone())?)
+            // partitioning and bucket writers sort rows of each partition or bucket themselves
+            if !config.range_partitions.is_empty() || config.hash_bucket_num > 0 {
+                let schema = writer_config.schema.0.clone();
+                let writer: SendableWriter = if config.range_partitions.is_empty() {
+                    Box::new(HashBucketAsyncWriter::try_new(config, writer_config, runtime.clone())?)
+                } else {
+                    Box::new(PartitioningAsyncWriter::try_new(
+                        config,
+                        writer_config,
+                        runtime.clone(),
+                    )?)
+                };
+                return Ok(SyncSendableMutableLakeSoulWriter {
+                    inner: Arc::new(Mutex::new(writer)),
+                    runtime,
+                    schema,
+                });
+            }
+
+            // write rolling files under the prefix if exists
+            let (writer, task_ctx, schema): (SendableWriter, _, _) = if config.prefix.is_empty() {
+                let writer = MultiPartAsyncWriter::try_new(writer_config).await?;
+                let (task_ctx, schema) = (writer.sess_ctx.task_ctx(), writer.schema.clone());
+                (Box::new(writer), task_ctx, schema)
             } else {
-                Box::new(writer)
+                let writer = RollingAsyncWriter::try_new(writer_config).await?;
+                let (task_ctx, schema) = (writer.writer.sess_ctx.task_ctx(), writer.writer.schema.clone());
+                (Box::new(writer), task_ctx, schema)
+            };
+
+            let writer: SendableWriter = if !config.primary_keys.is_empty() {
+                Box::new(SortAsyncWriter::try_new_with_writer(
+                    writer,
+                    task_ctx,
+                    config,
+                    runtime.clone(),
+                )?)
+            } else {
+                writer
             };
 
             Ok(SyncSendableMutableLakeSoulWriter {
@@ -441,7 +1013,7 @@ impl SyncSendableMutableLakeSoulWriter {
         })
     }
 
-    pub fn flush_and_close(self) -> Result<()> {
+    pub fn flush_and_close(self) -> Result<Vec<FlushResult>> {
         let inner_writer = match Arc::try_unwrap(self.inner) {
             Ok(inner) => inner,
             Err(_) => return Err(Internal("Cannot get ownership of inner writer".to_string())),
@@ -472,18 +1044,22 @@ impl SyncSendableMutableLakeSoulWriter {
 
 #[cfg(test)]
 mod tests {
+    use crate::constant::LAKESOUL_NULL_STRING;
+    use crate::hash_utils::spark_hash_bucket_ids;
     use crate::lakesoul_io_config::LakeSoulIOConfigBuilder;
     use crate::lakesoul_reader::LakeSoulReader;
     use crate::lakesoul_writer::{
-        AsyncBatchWriter, MultiPartAsyncWriter, SortAsyncWriter, SyncSendableMutableLakeSoulWriter,
+        AsyncBatchWriter, FlushResult, MultiPartAsyncWriter, RollingAsyncWriter, SortAsyncWriter,
+        SyncSendableMutableLakeSoulWriter,
     };
-    use arrow::array::{ArrayRef, Int64Array};
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
     use arrow::record_batch::RecordBatch;
+    use arrow_array::Array;
     use datafusion::error::Result;
+    use datafusion::scalar::ScalarValue;
     use parquet::arrow::arrow_reader::ParquetRecordBatchReader;
     use std::fs::File;
     use std::sync::Arc;
-    use arrow_array::Array;
     use tokio::runtime::Builder;
 
     #[test]
@@ -508,7 +1084,16 @@ mod tests {
                 .build();
             let mut async_writer = MultiPartAsyncWriter::try_new(writer_conf).await?;
             async_writer.write_record_batch(to_write.clone()).await?;
-            Box::new(async_writer).flush_and_close().await?;
+            let results = Box::new(async_writer).flush_and_close().await?;
+            assert_eq!(results.len(), 1);
+            let result = &results[0];
+            assert_eq!(result.path, format!("file://{}", path));
+            assert_eq!(result.size, std::fs::metadata(&path)?.len());
+            assert_eq!(result.num_rows, 3);
+            assert_eq!(result.file_exist_cols, "col");
+            assert_eq!(result.column_statistics[0].min, Some(ScalarValue::Int64(Some(1))));
+            assert_eq!(result.column_statistics[0].max, Some(ScalarValue::Int64(Some(3))));
+            assert_eq!(result.column_statistics[0].null_count, Some(0));
 
             let file = File::open(path.clone())?;
             let mut record_batch_reader = ParquetRecordBatchReader::try_new(file, 1024).unwrap();
@@ -616,6 +1201,143 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_rolling_write() -> Result<()> {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let temp_dir = tempfile::tempdir()?;
+        let prefix = temp_dir.path().to_str().unwrap().to_string();
+        let col = Arc::new(Int64Array::from_iter_values([4, 1, 3, 1, 2, 3, 1, 3, 3, 2])) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("col", col)])?;
+
+        let read_files = |results: &[FlushResult]| -> Result<Vec<Vec<i64>>> {
+            results
+                .iter()
+                .map(|result| {
+                    let file = File::open(result.path.trim_start_matches("file://"))?;
+                    let mut values = vec![];
+                    for batch in ParquetRecordBatchReader::try_new(file, 1024)? {
+                        let batch = batch?;
+                        let col = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                        values.extend(col.values().iter());
+                    }
+                    assert_eq!(result.num_rows, values.len() as u64);
+                    Ok(values)
+                })
+                .collect()
+        };
+
+        // without primary keys, files are split exactly at the row count
+        let async_writer_conf = LakeSoulIOConfigBuilder::new()
+            .with_prefix(format!("{}/unsorted", prefix))
+            .with_max_file_rows(4)
+            .with_schema(to_write.schema())
+            .build();
+        let results = runtime.block_on(async {
+            let mut async_writer = RollingAsyncWriter::try_new(async_writer_conf).await?;
+            async_writer.write_record_batch(to_write.slice(0, 3)).await?;
+            async_writer.write_record_batch(to_write.slice(3, 7)).await?;
+            Box::new(async_writer).flush_and_close().await
+        })?;
+        assert_eq!(
+            read_files(&results)?,
+            vec![vec![4, 1, 3, 1], vec![2, 3, 1, 3], vec![3, 2]]
+        );
+
+        // with primary keys, files are only split at key boundaries of the sorted rows
+        let writer_conf = LakeSoulIOConfigBuilder::new()
+            .with_prefix(format!("{}/sorted", prefix))
+            .with_max_file_rows(2)
+            .with_schema(to_write.schema())
+            .with_primary_keys(vec!["col".to_string()])
+            .build();
+        let writer = SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime)?;
+        writer.write_batch(to_write)?;
+        let results = writer.flush_and_close()?;
+        assert_eq!(
+            read_files(&results)?,
+            vec![vec![1, 1, 1], vec![2, 2], vec![3, 3, 3, 3], vec![4]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_bucket_write() -> Result<()> {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let temp_dir = tempfile::tempdir()?;
+        let id = Arc::new(Int64Array::from_iter_values((0..20).rev())) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("id", id)])?;
+        let writer_conf = LakeSoulIOConfigBuilder::new()
+            .with_prefix(temp_dir.path().to_str().unwrap().to_string())
+            .with_schema(to_write.schema())
+            .with_primary_keys(vec!["id".to_string()])
+            .with_hash_bucket_num(4)
+            .build();
+        let writer = SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime)?;
+        writer.write_batch(to_write)?;
+        let results = writer.flush_and_close()?;
+
+        let mut num_rows = 0;
+        for result in results {
+            let file = File::open(result.path.trim_start_matches("file://"))?;
+            for batch in ParquetRecordBatchReader::try_new(file, 1024)? {
+                let ids = batch?.column(0).clone();
+                let values = ids.as_any().downcast_ref::<Int64Array>().unwrap().values().to_vec();
+                assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+                for bucket_id in spark_hash_bucket_ids(&[ids], values.len(), 4)? {
+                    assert!(result.path.ends_with(&format!("_{:05}.parquet", bucket_id)));
+                }
+                num_rows += values.len();
+            }
+        }
+        assert_eq!(num_rows, 20);
+        Ok(())
+    }
+
+    #[test]
+    fn test_partitioning_write() -> Result<()> {
+        let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+        let temp_dir = tempfile::tempdir()?;
+        let date = Arc::new(StringArray::from(vec![
+            Some("2023-01-02"),
+            Some("2023-01-01"),
+            None,
+            Some("2023-01-02"),
+        ])) as ArrayRef;
+        let id = Arc::new(Int64Array::from_iter_values([4, 3, 2, 1])) as ArrayRef;
+        let to_write = RecordBatch::try_from_iter([("date", date), ("id", id)])?;
+        let writer_conf = LakeSoulIOConfigBuilder::new()
+            .with_prefix(temp_dir.path().to_str().unwrap().to_string())
+            .with_schema(to_write.schema())
+            .with_primary_keys(vec!["id".to_string()])
+            .with_range_partitions(vec!["date".to_string()])
+            .build();
+        let writer = SyncSendableMutableLakeSoulWriter::try_new(writer_conf, runtime)?;
+        writer.write_batch(to_write)?;
+        let results = writer.flush_and_close()?;
+
+        let partitions = results
+            .iter()
+            .map(|result| {
+                let file = File::open(result.path.trim_start_matches("file://"))?;
+                let batch = ParquetRecordBatchReader::try_new(file, 1024)?.next().unwrap()?;
+                assert_eq!(batch.schema().fields().len(), 1);
+                assert_eq!(result.file_exist_cols, "id");
+                let ids = batch.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                Ok((result.partition_desc.clone(), ids.values().to_vec()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            partitions,
+            vec![
+                ("date=2023-01-01".to_string(), vec![3]),
+                ("date=2023-01-02".to_string(), vec![1, 4]),
+                (format!("date={}", LAKESOUL_NULL_STRING), vec![2]),
+            ]
+        );
+        assert!(results[0].path.contains("/date=2023-01-01/part-"));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_s3_read_write() -> Result<()> {
         let common_conf_builder = LakeSoulIOConfigBuilder::new()
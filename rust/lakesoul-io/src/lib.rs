@@ -19,4 +19,7 @@ mod hdfs;
 
 pub mod default_column_stream;
 pub mod constant;
+pub mod hash_utils;
+mod projection_stream;
+pub mod statistics;
 pub mod transform;
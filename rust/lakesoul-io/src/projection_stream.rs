@@ -0,0 +1,92 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::{Debug, Formatter};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use futures::{Stream, StreamExt};
+
+use datafusion::error::Result;
+use datafusion::logical_expr::{Expr, ExprSchemable};
+use datafusion::optimizer::simplify_expressions::{ExprSimplifier, SimplifyContext};
+use datafusion::physical_expr::execution_props::ExecutionProps;
+use datafusion::physical_expr::{create_physical_expr, PhysicalExpr};
+use datafusion::physical_plan::{RecordBatchStream, SendableRecordBatchStream};
+use datafusion_common::DFSchema;
+
+/// Evaluates computed projections on the final stream of a reader, after merging and filtering.
+pub(crate) struct ProjectionStream {
+    schema: SchemaRef,
+
+    exprs: Vec<Arc<dyn PhysicalExpr>>,
+
+    inner_stream: SendableRecordBatchStream,
+}
+
+impl Debug for ProjectionStream {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.debug_struct("ProjectionStream")
+            .field("schema", &self.schema)
+            .field("exprs", &self.exprs)
+            .finish()
+    }
+}
+
+impl ProjectionStream {
+    /// Wraps `stream` to output exactly `projections`. Returns the input stream unchanged when there is no projection.
+    pub(crate) fn try_new(
+        stream: SendableRecordBatchStream,
+        projections: Vec<Expr>,
+    ) -> Result<SendableRecordBatchStream> {
+        if projections.is_empty() {
+            return Ok(stream);
+        }
+        let input_schema = stream.schema();
+        let df_schema = Arc::new(DFSchema::try_from(input_schema.as_ref().clone())?);
+        let props = ExecutionProps::new();
+        let simplifier = ExprSimplifier::new(SimplifyContext::new(&props).with_schema(df_schema.clone()));
+        let mut fields = Vec::with_capacity(projections.len());
+        let mut exprs = Vec::with_capacity(projections.len());
+        for projection in projections {
+            let projection = simplifier.coerce(projection, df_schema.clone())?;
+            fields.push(projection.to_field(&df_schema)?.field().clone());
+            exprs.push(create_physical_expr(&projection, &df_schema, &input_schema, &props)?);
+        }
+        Ok(Box::pin(ProjectionStream {
+            schema: Arc::new(Schema::new(fields)),
+            exprs,
+            inner_stream: stream,
+        }))
+    }
+
+    fn project_batch(&self, batch: RecordBatch) -> Result<RecordBatch> {
+        let columns = self
+            .exprs
+            .iter()
+            .map(|expr| expr.evaluate(&batch).map(|value| value.into_array(batch.num_rows())))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(RecordBatch::try_new(self.schema.clone(), columns)?)
+    }
+}
+
+impl Stream for ProjectionStream {
+    type Item = Result<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match futures::ready!(self.inner_stream.poll_next_unpin(cx)) {
+            None => Poll::Ready(None),
+            Some(result) => Poll::Ready(Some(result.and_then(|batch| self.project_batch(batch)))),
+        }
+    }
+}
+
+impl RecordBatchStream for ProjectionStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
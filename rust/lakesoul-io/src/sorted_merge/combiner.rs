@@ -8,15 +8,17 @@ use std::fmt::Debug;
 use std::sync::Arc;
 
 use crate::constant::{ConstEmptyArray, ConstNullArray};
-use crate::sorted_merge::merge_operator::{MergeOperator, MergeResult};
+use crate::sorted_merge::merge_operator::{ArrayValueBuilder, MergeOperator, MergeResult};
 use crate::sorted_merge::sort_key_range::{
     SortKeyArrayRange, SortKeyBatchRange, SortKeyBatchRanges, SortKeyBatchRangesRef,
 };
 
 use arrow::compute::interleave;
 use arrow::{
-    array::{make_array as make_arrow_array, Array, ArrayBuilder, ArrayRef, PrimitiveBuilder, StringBuilder},
-    datatypes::{DataType, Field, SchemaRef},
+    array::{
+        make_array as make_arrow_array, make_builder, Array, ArrayBuilder, ArrayRef, PrimitiveBuilder, StringBuilder,
+    },
+    datatypes::{DataType, Field, IntervalUnit, SchemaRef, TimeUnit},
     error::ArrowError,
     error::Result as ArrowResult,
     record_batch::RecordBatch,
@@ -116,7 +118,7 @@ impl MinHeapSortKeyBatchRangeCombiner {
 
     pub fn poll_result(&mut self) -> RangeCombinerResult {
         if self.in_progress.len() == self.target_batch_size {
-            RangeCombinerResult::RecordBatch(self.build_record_batch())
+            self.poll_record_batch()
         } else {
             match self.heap.pop() {
                 Some(Reverse(range)) => {
@@ -137,13 +139,21 @@ impl MinHeapSortKeyBatchRangeCombiner {
                             self.in_progress.push(self.current_sort_key_range.clone());
                             self.get_mut_current_sort_key_range().set_batch_range(None);
                         }
-                        RangeCombinerResult::RecordBatch(self.build_record_batch())
+                        self.poll_record_batch()
                     }
                 }
             }
         }
     }
 
+    // errors of merge operators are surfaced as RangeCombinerResult::Err
+    fn poll_record_batch(&mut self) -> RangeCombinerResult {
+        match self.build_record_batch() {
+            Ok(batch) => RangeCombinerResult::RecordBatch(Ok(batch)),
+            Err(e) => RangeCombinerResult::Err(e),
+        }
+    }
+
     fn build_record_batch(&mut self) -> ArrowResult<RecordBatch> {
         let columns = self
             .schema
@@ -188,11 +198,11 @@ impl MinHeapSortKeyBatchRangeCombiner {
                     self.const_empty_array.get(field.data_type()),
                 )
             })
-            .collect();
+            .collect::<ArrowResult<Vec<_>>>();
 
         self.in_progress.clear();
 
-        RecordBatch::try_new(self.schema.clone(), columns)
+        RecordBatch::try_new(self.schema.clone(), columns?)
     }
 
     fn init_current_sort_key_range(&mut self) {
@@ -212,7 +222,7 @@ fn merge_sort_key_array_ranges(
     batch_idx_to_flatten_array_idx: &HashMap<usize, usize>,
     merge_operator: &MergeOperator,
     empty_array: ArrayRef,
-) -> ArrayRef {
+) -> ArrowResult<ArrayRef> {
     assert_eq!(ranges.len(), capacity);
     let data_type = (*field.data_type()).clone();
     let mut append_array_data_builder: Box<dyn ArrayBuilder> = match data_type {
@@ -226,14 +236,47 @@ fn merge_sort_key_array_ranges(
         DataType::Int64 => Box::new(PrimitiveBuilder::<Int64Type>::with_capacity(capacity)),
         DataType::Float32 => Box::new(PrimitiveBuilder::<Float32Type>::with_capacity(capacity)),
         DataType::Float64 => Box::new(PrimitiveBuilder::<Float64Type>::with_capacity(capacity)),
+        DataType::Decimal128(_, _) => {
+            Box::new(PrimitiveBuilder::<Decimal128Type>::with_capacity(capacity).with_data_type(data_type.clone()))
+        }
+        DataType::Decimal256(_, _) => {
+            Box::new(PrimitiveBuilder::<Decimal256Type>::with_capacity(capacity).with_data_type(data_type.clone()))
+        }
+        DataType::Duration(TimeUnit::Second) => {
+            Box::new(PrimitiveBuilder::<DurationSecondType>::with_capacity(capacity))
+        }
+        DataType::Duration(TimeUnit::Millisecond) => {
+            Box::new(PrimitiveBuilder::<DurationMillisecondType>::with_capacity(capacity))
+        }
+        DataType::Duration(TimeUnit::Microsecond) => {
+            Box::new(PrimitiveBuilder::<DurationMicrosecondType>::with_capacity(capacity))
+        }
+        DataType::Duration(TimeUnit::Nanosecond) => {
+            Box::new(PrimitiveBuilder::<DurationNanosecondType>::with_capacity(capacity))
+        }
+        DataType::Interval(IntervalUnit::YearMonth) => {
+            Box::new(PrimitiveBuilder::<IntervalYearMonthType>::with_capacity(capacity))
+        }
+        DataType::Interval(IntervalUnit::DayTime) => {
+            Box::new(PrimitiveBuilder::<IntervalDayTimeType>::with_capacity(capacity))
+        }
+        DataType::Interval(IntervalUnit::MonthDayNano) => {
+            Box::new(PrimitiveBuilder::<IntervalMonthDayNanoType>::with_capacity(capacity))
+        }
         DataType::Utf8 => Box::new(StringBuilder::with_capacity(capacity, 256)),
-        _ => {
-            if *merge_operator == MergeOperator::UseLast {
-                Box::new(PrimitiveBuilder::<Int32Type>::with_capacity(capacity))
-            } else {
-                unimplemented!()
+        DataType::List(_) | DataType::Map(_, _) => Box::new(ArrayValueBuilder::new(data_type.clone())),
+        _ => match merge_operator {
+            // the builder is never appended to
+            op if op.extends_only() => Box::new(PrimitiveBuilder::<Int32Type>::with_capacity(capacity)),
+            // user-defined operators may append values of other types
+            MergeOperator::Custom(_) => make_builder(&data_type, capacity),
+            _ => {
+                return Err(ArrowError::NotYetImplemented(format!(
+                    "{} doesn't support MergeOperator {:?}",
+                    data_type, merge_operator
+                )))
             }
-        }
+        },
     };
     let append_idx = flatten_dedup_arrays.len();
     let null_idx = append_idx - 1;
@@ -243,7 +286,8 @@ fn merge_sort_key_array_ranges(
     let extend_list: Vec<(usize, usize)> = ranges
         .iter()
         .map(|ranges_per_row| {
-            match merge_operator.merge(data_type.clone(), ranges_per_row, &mut append_array_data_builder) {
+            let merged = merge_operator.merge(data_type.clone(), ranges_per_row, &mut append_array_data_builder)?;
+            Ok(match merged {
                 MergeResult::AppendValue(row_idx) => (append_idx, row_idx),
                 MergeResult::AppendNull => {
                     if !field.is_nullable() {
@@ -253,9 +297,9 @@ fn merge_sort_key_array_ranges(
                     (null_idx, 0)
                 }
                 MergeResult::Extend(batch_idx, row_idx) => (batch_idx_to_flatten_array_idx[&batch_idx], row_idx),
-            }
+            })
         })
-        .collect();
+        .collect::<ArrowResult<_>>()?;
 
     let append_array = match append_array_data_builder.len() {
         0 => empty_array,
@@ -271,5 +315,4 @@ fn merge_sort_key_array_ranges(
             .as_slice(),
         extend_list.as_slice(),
     )
-    .unwrap()
 }
@@ -2,25 +2,113 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use std::any::Any;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
+use std::sync::{Arc, RwLock};
 
-use arrow::array::{as_primitive_array, as_string_array, ArrayBuilder, UInt8Builder};
+use arrow::array::{
+    as_list_array, as_map_array, as_primitive_array, as_string_array, as_struct_array, build_compare, make_array,
+    ArrayBuilder, ArrayData, ArrayRef, UInt32Array, UInt8Builder,
+};
+use arrow::compute::{concat, take};
+use arrow::error::{ArrowError, Result as ArrowResult};
+use arrow::row::{RowConverter, SortField};
 use arrow_array::{builder::*, types::*, Array, ArrowPrimitiveType};
-use arrow_schema::DataType;
+use arrow_buffer::{i256, Buffer};
+use arrow_schema::{DataType, IntervalUnit, TimeUnit};
+use datafusion::error::{DataFusionError, Result};
+use lazy_static::lazy_static;
 use smallvec::SmallVec;
 
 use crate::sorted_merge::sort_key_range::SortKeyArrayRange;
-use crate::sum_with_primitive_type_and_append_value;
 
-#[derive(Default, Debug, Clone, PartialEq, Eq)]
+lazy_static! {
+    static ref MERGE_FUNCTIONS: RwLock<HashMap<String, Arc<dyn MergeFunction>>> = RwLock::new(HashMap::new());
+}
+
+/// A user-defined merge operator, see [`register_merge_function`].
+pub trait MergeFunction: Debug + Send + Sync {
+    /// Merges the values of one primary key in a column of type `data_type`.
+    /// `ranges` is never empty and is ordered from the oldest to the latest rows.
+    /// A computed value must be appended to `append_array_data_builder`, which is a builder of `data_type`,
+    /// or an [`ArrayValueBuilder`] for lists and maps, and returned as [`MergeResult::AppendValue`]
+    /// with its index in the builder. Errors fail the read.
+    fn merge(
+        &self,
+        data_type: DataType,
+        ranges: &SmallVec<[SortKeyArrayRange; 4]>,
+        append_array_data_builder: &mut Box<dyn ArrayBuilder>,
+    ) -> ArrowResult<MergeResult>;
+}
+
+/// Registers a merge operator under `name`, which can then be used as a merge operator name in
+/// [`crate::lakesoul_io_config::LakeSoulIOConfigBuilder::with_merge_op`]. Registering a name again replaces the
+/// previous operator, while built-in names can't be registered.
+pub fn register_merge_function(name: &str, function: Arc<dyn MergeFunction>) -> Result<()> {
+    if MergeOperator::from_builtin_name(name).is_some() {
+        return Err(DataFusionError::Configuration(format!(
+            "Can't register built-in MergeOperator {}",
+            name
+        )));
+    }
+    MERGE_FUNCTIONS
+        .write()
+        .map_err(|e| DataFusionError::Execution(e.to_string()))?
+        .insert(name.to_string(), function);
+    Ok(())
+}
+
+#[derive(Default, Debug, Clone)]
 pub enum MergeOperator {
     #[default]
     UseLast,
     UseLastNotNull,
+    UseFirst,
+    UseFirstNotNull,
+    // fails on integer overflows
     Sum,
-    Concat,
+    // saturates at the bounds of the type on integer overflows
+    SaturatingSum,
+    Max,
+    Min,
+    // number of non-null values. A count isn't merged again by its value, so a merged count
+    // must not be merged with later rows, e.g. by compaction
+    Count,
+    // joins non-null strings with the delimiter, skipping repeated strings if distinct
+    Concat {
+        delimiter: String,
+        distinct: bool,
+    },
+    // concatenates non-null lists
+    ListAppend,
+    // unions non-null maps, where later values of a key override earlier ones
+    MapMerge,
+    Custom(Arc<dyn MergeFunction>),
+}
+
+impl PartialEq for MergeOperator {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (MergeOperator::Custom(a), MergeOperator::Custom(b)) => {
+                Arc::as_ptr(a) as *const () == Arc::as_ptr(b) as *const ()
+            }
+            (
+                MergeOperator::Concat { delimiter, distinct },
+                MergeOperator::Concat {
+                    delimiter: other_delimiter,
+                    distinct: other_distinct,
+                },
+            ) => delimiter == other_delimiter && distinct == other_distinct,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
 }
 
+impl Eq for MergeOperator {}
+
+#[derive(Debug, PartialEq, Eq)]
 pub enum MergeResult {
     AppendNull,
     AppendValue(usize),
@@ -28,13 +116,49 @@ pub enum MergeResult {
 }
 
 impl MergeOperator {
-    pub fn from_name(name: &str) -> Self {
+    /// Resolves a built-in or registered merge operator by name.
+    /// Concat and DistinctConcat join with "," unless a delimiter is given in parentheses, e.g. `Concat(|)`.
+    pub fn from_name(name: &str) -> Result<Self> {
+        match MergeOperator::from_builtin_name(name) {
+            Some(merge_operator) => Ok(merge_operator),
+            None => MERGE_FUNCTIONS
+                .read()
+                .map_err(|e| DataFusionError::Execution(e.to_string()))?
+                .get(name)
+                .map(|function| MergeOperator::Custom(function.clone()))
+                .ok_or_else(|| DataFusionError::Configuration(format!("Invalid MergeOperator name {}", name))),
+        }
+    }
+
+    fn from_builtin_name(name: &str) -> Option<Self> {
+        let concat = |name: &str, distinct: bool| {
+            let delimiter = match name.strip_prefix('(') {
+                Some(delimiter) => delimiter.strip_suffix(')')?,
+                None if name.is_empty() => ",",
+                None => return None,
+            };
+            Some(MergeOperator::Concat {
+                delimiter: delimiter.to_string(),
+                distinct,
+            })
+        };
         match name {
-            "UseLast" => MergeOperator::UseLast,
-            "UseLastNotNull" => MergeOperator::UseLastNotNull,
-            "Sum" => MergeOperator::Sum,
-            "Concat" => MergeOperator::Concat,
-            _ => panic!("Invalid MergeOperator name"),
+            "UseLast" => Some(MergeOperator::UseLast),
+            "UseLastNotNull" => Some(MergeOperator::UseLastNotNull),
+            "UseFirst" => Some(MergeOperator::UseFirst),
+            "UseFirstNotNull" => Some(MergeOperator::UseFirstNotNull),
+            "Sum" => Some(MergeOperator::Sum),
+            "SaturatingSum" => Some(MergeOperator::SaturatingSum),
+            "Max" => Some(MergeOperator::Max),
+            "Min" => Some(MergeOperator::Min),
+            "Count" => Some(MergeOperator::Count),
+            "ListAppend" => Some(MergeOperator::ListAppend),
+            "MapMerge" => Some(MergeOperator::MapMerge),
+            _ => match (name.strip_prefix("DistinctConcat"), name.strip_prefix("Concat")) {
+                (Some(rest), _) => concat(rest, true),
+                (_, Some(rest)) => concat(rest, false),
+                _ => None,
+            },
         }
     }
 
@@ -43,30 +167,158 @@ impl MergeOperator {
         data_type: DataType,
         ranges: &SmallVec<[SortKeyArrayRange; 4]>,
         append_array_data_builder: &mut Box<dyn ArrayBuilder>,
-    ) -> MergeResult {
-        match &ranges.len() {
-            0 => MergeResult::AppendNull,
-            1 => match self {
-                MergeOperator::UseLast => MergeResult::Extend(ranges[0].batch_idx, ranges[0].end_row - 1),
-                MergeOperator::UseLastNotNull => last_non_null(ranges),
-                MergeOperator::Sum => match ranges[0].end_row - ranges[0].begin_row {
-                    1 => MergeResult::Extend(ranges[0].batch_idx, ranges[0].end_row - 1),
-                    _ => sum_with_primitive_type(data_type, ranges, append_array_data_builder),
-                },
-                MergeOperator::Concat => match ranges[0].end_row - ranges[0].begin_row {
-                    1 => MergeResult::Extend(ranges[0].batch_idx, ranges[0].end_row - 1),
-                    _ => concat_with_string_type(ranges, append_array_data_builder),
-                },
-            },
-            _ => match self {
-                MergeOperator::UseLast => {
-                    MergeResult::Extend(ranges.last().unwrap().batch_idx, ranges.last().unwrap().end_row - 1)
-                }
-                MergeOperator::UseLastNotNull => last_non_null(ranges),
-                MergeOperator::Sum => sum_with_primitive_type(data_type, ranges, append_array_data_builder),
-                MergeOperator::Concat => concat_with_string_type(ranges, append_array_data_builder),
-            },
+    ) -> ArrowResult<MergeResult> {
+        if ranges.is_empty() {
+            return Ok(MergeResult::AppendNull);
         }
+        // a single row is already the result of aggregating operators
+        let single_row = ranges.len() == 1 && ranges[0].end_row - ranges[0].begin_row == 1;
+        let result = match self {
+            MergeOperator::UseLast => {
+                MergeResult::Extend(ranges.last().unwrap().batch_idx, ranges.last().unwrap().end_row - 1)
+            }
+            MergeOperator::UseLastNotNull => last_non_null(ranges),
+            MergeOperator::UseFirst => MergeResult::Extend(ranges[0].batch_idx, ranges[0].begin_row),
+            MergeOperator::UseFirstNotNull => first_non_null(ranges),
+            MergeOperator::Sum
+            | MergeOperator::SaturatingSum
+            | MergeOperator::Concat { .. }
+            | MergeOperator::ListAppend
+            | MergeOperator::MapMerge
+                if single_row =>
+            {
+                MergeResult::Extend(ranges[0].batch_idx, ranges[0].begin_row)
+            }
+            MergeOperator::Sum => sum_with_primitive_type(data_type, ranges, append_array_data_builder, false)?,
+            MergeOperator::SaturatingSum => {
+                sum_with_primitive_type(data_type, ranges, append_array_data_builder, true)?
+            }
+            MergeOperator::Max => extreme_non_null(data_type, ranges, Ordering::Greater)?,
+            MergeOperator::Min => extreme_non_null(data_type, ranges, Ordering::Less)?,
+            MergeOperator::Count => count_non_null(data_type, ranges, append_array_data_builder)?,
+            MergeOperator::Concat { delimiter, distinct } => {
+                concat_with_string_type(ranges, append_array_data_builder, delimiter, *distinct)
+            }
+            MergeOperator::ListAppend => list_append(ranges, append_array_data_builder),
+            MergeOperator::MapMerge => map_merge(ranges, append_array_data_builder),
+            MergeOperator::Custom(function) => function.merge(data_type, ranges, append_array_data_builder)?,
+        };
+        Ok(result)
+    }
+
+    /// Checks that the operator can merge values of `data_type`, so that unsupported combinations are
+    /// reported as configuration errors before reading.
+    pub fn check_data_type(&self, field_name: &str, data_type: &DataType) -> Result<()> {
+        let supported = match self {
+            MergeOperator::UseLast
+            | MergeOperator::UseLastNotNull
+            | MergeOperator::UseFirst
+            | MergeOperator::UseFirstNotNull
+            | MergeOperator::Custom(_) => true,
+            MergeOperator::Sum | MergeOperator::SaturatingSum => matches!(
+                data_type,
+                DataType::UInt8
+                    | DataType::UInt16
+                    | DataType::UInt32
+                    | DataType::UInt64
+                    | DataType::Int8
+                    | DataType::Int16
+                    | DataType::Int32
+                    | DataType::Int64
+                    | DataType::Float32
+                    | DataType::Float64
+                    | DataType::Decimal128(_, _)
+                    | DataType::Decimal256(_, _)
+                    | DataType::Duration(_)
+                    | DataType::Interval(_)
+            ),
+            MergeOperator::Max | MergeOperator::Min => {
+                let empty = arrow_array::new_empty_array(data_type);
+                build_compare(empty.as_ref(), empty.as_ref()).is_ok()
+            }
+            MergeOperator::Count => matches!(
+                data_type,
+                DataType::Int32 | DataType::Int64 | DataType::UInt32 | DataType::UInt64
+            ),
+            MergeOperator::Concat { .. } => data_type == &DataType::Utf8,
+            MergeOperator::ListAppend => matches!(data_type, DataType::List(_)),
+            MergeOperator::MapMerge => matches!(data_type, DataType::Map(_, _)),
+        };
+        match supported {
+            true => Ok(()),
+            false => Err(DataFusionError::Configuration(format!(
+                "MergeOperator {:?} doesn't support field {} of type {}",
+                self, field_name, data_type
+            ))),
+        }
+    }
+
+    // whether the operator only picks rows of the inputs, so that it never appends to the builder
+    pub(crate) fn extends_only(&self) -> bool {
+        matches!(
+            self,
+            MergeOperator::UseLast
+                | MergeOperator::UseLastNotNull
+                | MergeOperator::UseFirst
+                | MergeOperator::UseFirstNotNull
+                | MergeOperator::Max
+                | MergeOperator::Min
+        )
+    }
+}
+
+/// Builder of arrays of any type from arrays of one value, used for values of nested types.
+#[derive(Debug)]
+pub struct ArrayValueBuilder {
+    data_type: DataType,
+    values: Vec<ArrayRef>,
+}
+
+impl ArrayValueBuilder {
+    pub fn new(data_type: DataType) -> Self {
+        ArrayValueBuilder {
+            data_type,
+            values: vec![],
+        }
+    }
+
+    pub fn append_value(&mut self, value: ArrayRef) {
+        self.values.push(value)
+    }
+}
+
+impl ArrayBuilder for ArrayValueBuilder {
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        let array = self.finish_cloned();
+        self.values.clear();
+        array
+    }
+
+    fn finish_cloned(&self) -> ArrayRef {
+        match self.values.len() {
+            0 => arrow_array::new_empty_array(&self.data_type),
+            _ => concat(&self.values.iter().map(|value| value.as_ref()).collect::<Vec<_>>()).unwrap(),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn Any> {
+        self
     }
 }
 
@@ -97,67 +349,289 @@ fn last_non_null(ranges: &SmallVec<[SortKeyArrayRange; 4]>) -> MergeResult {
     }
 }
 
+fn first_non_null(ranges: &SmallVec<[SortKeyArrayRange; 4]>) -> MergeResult {
+    for range in ranges.iter() {
+        let array = range.array();
+        if let Some(row_idx) = (range.begin_row..range.end_row).find(|row_idx| array.is_valid(*row_idx)) {
+            return MergeResult::Extend(range.batch_idx, row_idx);
+        }
+    }
+    MergeResult::AppendNull
+}
+
+// picks the greatest or least non-null value by `ordering`, the earliest one among equal values
+fn extreme_non_null(
+    dt: DataType,
+    ranges: &SmallVec<[SortKeyArrayRange; 4]>,
+    ordering: Ordering,
+) -> ArrowResult<MergeResult> {
+    let unsupported = |_| {
+        ArrowError::NotYetImplemented(format!(
+            "{} doesn't support MergeOperator::Max and MergeOperator::Min",
+            dt
+        ))
+    };
+    // range index and row index of the current extreme value
+    let mut extreme: Option<(usize, usize)> = None;
+    for (range_idx, range) in ranges.iter().enumerate() {
+        let array = range.array();
+        let cmp = build_compare(array.as_ref(), array.as_ref()).map_err(unsupported)?;
+        let range_extreme = (range.begin_row..range.end_row)
+            .filter(|row_idx| array.is_valid(*row_idx))
+            .reduce(|a, b| if cmp(b, a) == ordering { b } else { a });
+        extreme = match (extreme, range_extreme) {
+            (Some((extreme_range_idx, extreme_row_idx)), Some(row_idx)) => {
+                let extreme_array = ranges[extreme_range_idx].array();
+                let cmp = build_compare(array.as_ref(), extreme_array.as_ref()).map_err(unsupported)?;
+                if cmp(row_idx, extreme_row_idx) == ordering {
+                    Some((range_idx, row_idx))
+                } else {
+                    extreme
+                }
+            }
+            (None, Some(row_idx)) => Some((range_idx, row_idx)),
+            (extreme, None) => extreme,
+        };
+    }
+    Ok(match extreme {
+        Some((range_idx, row_idx)) => MergeResult::Extend(ranges[range_idx].batch_idx, row_idx),
+        None => MergeResult::AppendNull,
+    })
+}
+
+// the count is never null, it's 0 if all values are null
+fn count_non_null(
+    dt: DataType,
+    ranges: &SmallVec<[SortKeyArrayRange; 4]>,
+    append_array_data_builder: &mut Box<dyn ArrayBuilder>,
+) -> ArrowResult<MergeResult> {
+    let count = ranges
+        .iter()
+        .map(|range| {
+            let array = range.array();
+            (range.begin_row..range.end_row)
+                .filter(|row_idx| array.is_valid(*row_idx))
+                .count()
+        })
+        .sum::<usize>();
+    macro_rules! append_count {
+        ($builder_type:ty, $native_ty:ty) => {{
+            let builder = append_array_data_builder
+                .as_any_mut()
+                .downcast_mut::<$builder_type>()
+                .unwrap();
+            builder.append_value(count as $native_ty);
+            Ok(MergeResult::AppendValue(builder.len() - 1))
+        }};
+    }
+    match dt {
+        DataType::Int32 => append_count!(Int32Builder, i32),
+        DataType::Int64 => append_count!(Int64Builder, i64),
+        DataType::UInt32 => append_count!(UInt32Builder, u32),
+        DataType::UInt64 => append_count!(UInt64Builder, u64),
+        _ => Err(ArrowError::NotYetImplemented(format!(
+            "{} doesn't support MergeOperator::Count",
+            dt
+        ))),
+    }
+}
+
+// sums non-null values, the sum is null only if all values are null.
+// integer overflows are errors or saturate at the bounds of the type,
+// and decimal sums must fit the precision of the column like in spark.
 fn sum_with_primitive_type(
     dt: DataType,
     ranges: &SmallVec<[SortKeyArrayRange; 4]>,
     append_array_data_builder: &mut Box<dyn ArrayBuilder>,
-) -> MergeResult {
+    saturating: bool,
+) -> ArrowResult<MergeResult> {
+    macro_rules! sum {
+        ($primitive_type_name:ty) => {
+            sum_with_add::<$primitive_type_name>(
+                &dt,
+                ranges,
+                append_array_data_builder,
+                |a, b| a.overflowing_sum(b, saturating),
+                Some,
+            )
+        };
+    }
+    macro_rules! sum_float {
+        ($primitive_type_name:ty) => {
+            sum_with_add::<$primitive_type_name>(&dt, ranges, append_array_data_builder, |a, b| Some(a + b), Some)
+        };
+    }
     match dt {
-        DataType::UInt8 => {
-            sum_with_primitive_type_and_append_value!(UInt8Type, u8, UInt8Builder, append_array_data_builder, ranges)
-        }
-        DataType::UInt16 => {
-            sum_with_primitive_type_and_append_value!(UInt16Type, u16, UInt16Builder, append_array_data_builder, ranges)
-        }
-        DataType::UInt32 => {
-            sum_with_primitive_type_and_append_value!(UInt32Type, u32, UInt32Builder, append_array_data_builder, ranges)
+        DataType::UInt8 => sum!(UInt8Type),
+        DataType::UInt16 => sum!(UInt16Type),
+        DataType::UInt32 => sum!(UInt32Type),
+        DataType::UInt64 => sum!(UInt64Type),
+        DataType::Int8 => sum!(Int8Type),
+        DataType::Int16 => sum!(Int16Type),
+        DataType::Int32 => sum!(Int32Type),
+        DataType::Int64 => sum!(Int64Type),
+        DataType::Float32 => sum_float!(Float32Type),
+        DataType::Float64 => sum_float!(Float64Type),
+        DataType::Decimal128(precision, _) => {
+            let max = 10_i128.pow(precision as u32) - 1;
+            sum_with_add::<Decimal128Type>(
+                &dt,
+                ranges,
+                append_array_data_builder,
+                |a, b| a.overflowing_sum(b, saturating),
+                |sum| bound_decimal(sum, max.wrapping_neg(), max, saturating),
+            )
         }
-        DataType::UInt64 => {
-            sum_with_primitive_type_and_append_value!(UInt64Type, u64, UInt64Builder, append_array_data_builder, ranges)
+        DataType::Decimal256(precision, _) => {
+            let max = i256::from_i128(10)
+                .wrapping_pow(precision as u32)
+                .wrapping_sub(i256::ONE);
+            sum_with_add::<Decimal256Type>(
+                &dt,
+                ranges,
+                append_array_data_builder,
+                |a, b| a.overflowing_sum(b, saturating),
+                |sum| bound_decimal(sum, max.wrapping_neg(), max, saturating),
+            )
         }
-        DataType::Int8 => {
-            sum_with_primitive_type_and_append_value!(Int8Type, i8, Int8Builder, append_array_data_builder, ranges)
-        }
-        DataType::Int16 => {
-            sum_with_primitive_type_and_append_value!(Int16Type, i16, Int16Builder, append_array_data_builder, ranges)
-        }
-        DataType::Int32 => {
-            sum_with_primitive_type_and_append_value!(Int32Type, i32, Int32Builder, append_array_data_builder, ranges)
-        }
-        DataType::Int64 => {
-            sum_with_primitive_type_and_append_value!(Int64Type, i64, Int64Builder, append_array_data_builder, ranges)
-        }
-        DataType::Float32 => sum_with_primitive_type_and_append_value!(
-            Float32Type,
-            f32,
-            Float32Builder,
+        DataType::Duration(TimeUnit::Second) => sum!(DurationSecondType),
+        DataType::Duration(TimeUnit::Millisecond) => sum!(DurationMillisecondType),
+        DataType::Duration(TimeUnit::Microsecond) => sum!(DurationMicrosecondType),
+        DataType::Duration(TimeUnit::Nanosecond) => sum!(DurationNanosecondType),
+        DataType::Interval(IntervalUnit::YearMonth) => sum!(IntervalYearMonthType),
+        // fields of intervals are summed separately
+        DataType::Interval(IntervalUnit::DayTime) => sum_with_add::<IntervalDayTimeType>(
+            &dt,
+            ranges,
             append_array_data_builder,
-            ranges
+            |a, b| {
+                let (a_days, a_millis) = IntervalDayTimeType::to_parts(a);
+                let (b_days, b_millis) = IntervalDayTimeType::to_parts(b);
+                Some(IntervalDayTimeType::make_value(
+                    a_days.overflowing_sum(b_days, saturating)?,
+                    a_millis.overflowing_sum(b_millis, saturating)?,
+                ))
+            },
+            Some,
         ),
-        DataType::Float64 => sum_with_primitive_type_and_append_value!(
-            Float64Type,
-            f64,
-            Float64Builder,
+        DataType::Interval(IntervalUnit::MonthDayNano) => sum_with_add::<IntervalMonthDayNanoType>(
+            &dt,
+            ranges,
             append_array_data_builder,
-            ranges
+            |a, b| {
+                let (a_months, a_days, a_nanos) = IntervalMonthDayNanoType::to_parts(a);
+                let (b_months, b_days, b_nanos) = IntervalMonthDayNanoType::to_parts(b);
+                Some(IntervalMonthDayNanoType::make_value(
+                    a_months.overflowing_sum(b_months, saturating)?,
+                    a_days.overflowing_sum(b_days, saturating)?,
+                    a_nanos.overflowing_sum(b_nanos, saturating)?,
+                ))
+            },
+            Some,
         ),
-        _ => panic!("{} doesn't support MergeOperator::Sum", dt),
+        _ => Err(ArrowError::NotYetImplemented(format!(
+            "{} doesn't support MergeOperator::Sum",
+            dt
+        ))),
+    }
+}
+
+// folds non-null values with `add` and checks the sum with `finish`, both return None on overflow
+fn sum_with_add<T: ArrowPrimitiveType>(
+    dt: &DataType,
+    ranges: &SmallVec<[SortKeyArrayRange; 4]>,
+    append_array_data_builder: &mut Box<dyn ArrayBuilder>,
+    add: impl Fn(T::Native, T::Native) -> Option<T::Native>,
+    finish: impl Fn(T::Native) -> Option<T::Native>,
+) -> ArrowResult<MergeResult> {
+    let overflow = || ArrowError::ComputeError(format!("Overflow in MergeOperator::Sum of {}", dt));
+    let mut sum = None;
+    for range in ranges.iter() {
+        let array = range.array();
+        let arr = as_primitive_array::<T>(array.as_ref());
+        for row_idx in range.begin_row..range.end_row {
+            if arr.is_valid(row_idx) {
+                let value = arr.value(row_idx);
+                sum = match sum {
+                    Some(sum) => Some(add(sum, value).ok_or_else(overflow)?),
+                    None => Some(value),
+                };
+            }
+        }
+    }
+    match sum {
+        // only when all ranges are null values, the result is also null
+        None => Ok(MergeResult::AppendNull),
+        Some(sum) => {
+            let sum = finish(sum).ok_or_else(overflow)?;
+            let builder = append_array_data_builder
+                .as_any_mut()
+                .downcast_mut::<PrimitiveBuilder<T>>()
+                .unwrap();
+            builder.append_value(sum);
+            Ok(MergeResult::AppendValue(builder.len() - 1))
+        }
+    }
+}
+
+fn bound_decimal<N: PartialOrd>(sum: N, min: N, max: N, saturating: bool) -> Option<N> {
+    match (sum < min, sum > max) {
+        (false, false) => Some(sum),
+        _ if !saturating => None,
+        (true, _) => Some(min),
+        (_, true) => Some(max),
+    }
+}
+
+// integer addition, which returns None on overflow or saturates at the bounds of the type
+trait OverflowingSum: Sized {
+    fn overflowing_sum(self, rhs: Self, saturating: bool) -> Option<Self>;
+}
+
+macro_rules! impl_overflowing_sum {
+    ($($native_ty:ty),*) => {
+        $(
+            impl OverflowingSum for $native_ty {
+                fn overflowing_sum(self, rhs: Self, saturating: bool) -> Option<Self> {
+                    match saturating {
+                        true => Some(self.saturating_add(rhs)),
+                        false => self.checked_add(rhs),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_overflowing_sum!(u8, u16, u32, u64, i8, i16, i32, i64, i128);
+
+impl OverflowingSum for i256 {
+    fn overflowing_sum(self, rhs: Self, saturating: bool) -> Option<Self> {
+        match (self.checked_add(rhs), saturating) {
+            (Some(sum), _) => Some(sum),
+            (None, false) => None,
+            (None, true) if rhs < i256::ZERO => Some(i256::MIN),
+            (None, true) => Some(i256::MAX),
+        }
     }
 }
 
 fn concat_with_string_type(
     ranges: &SmallVec<[SortKeyArrayRange; 4]>,
     append_array_data_builder: &mut Box<dyn ArrayBuilder>,
+    delimiter: &str,
+    distinct: bool,
 ) -> MergeResult {
     let mut is_none = true;
     let mut res = String::new();
-    for range in ranges.iter() {
-        let array = range.array();
+    let arrays = ranges.iter().map(|range| range.array()).collect::<Vec<_>>();
+    let mut seen = HashSet::new();
+    for (range, array) in ranges.iter().zip(arrays.iter()) {
         let arr = as_string_array(array.as_ref());
         for i in range.begin_row..range.end_row {
-            if !arr.is_null(i) {
+            if !arr.is_null(i) && (!distinct || seen.insert(arr.value(i))) {
                 if !is_none {
-                    res.push(',');
+                    res.push_str(delimiter);
                 }
                 is_none = false;
                 res.push_str(arr.value(i));
@@ -177,51 +651,486 @@ fn concat_with_string_type(
     }
 }
 
-#[macro_export]
-macro_rules! sum_with_primitive_type_and_append_value {
-    ($primitive_type_name:ty, $native_ty:ty, $primitive_builder_type:ty, $builder:ident, $ranges:ident) => {{
-        let mut is_none = true;
-        let mut res = <$primitive_type_name>::default_value();
-        for range in $ranges.iter() {
-            let array = range.array();
-            let arr = as_primitive_array::<$primitive_type_name>(array.as_ref());
-            let values = arr.values();
-            if is_none {
-                let null_buffer = arr.nulls();
-                match null_buffer {
-                    Some(buffer) => {
-                        let offset = arr.offset();
-                        let null_buf_range = buffer.slice(offset + range.begin_row, range.end_row - range.begin_row);
-                        // the entire range is null
-                        is_none &=
-                            null_buf_range.null_count() == (range.end_row - range.begin_row);
-                    }
-                    None => is_none = false,
-                }
+// concatenates the elements of non-null lists into one list
+fn list_append(
+    ranges: &SmallVec<[SortKeyArrayRange; 4]>,
+    append_array_data_builder: &mut Box<dyn ArrayBuilder>,
+) -> MergeResult {
+    let mut values = vec![];
+    for range in ranges.iter() {
+        let array = range.array();
+        let list = as_list_array(array.as_ref());
+        for i in range.begin_row..range.end_row {
+            if list.is_valid(i) {
+                values.push(list.value(i));
             }
-            if !is_none {
-                res += values[range.begin_row..range.end_row].iter().sum::<$native_ty>();
+        }
+    }
+    if values.is_empty() {
+        return MergeResult::AppendNull;
+    }
+    let values = concat(&values.iter().map(|value| value.as_ref()).collect::<Vec<_>>()).unwrap();
+    let array = ranges[0].array();
+    append_single_nested_value(array.data_type(), values, append_array_data_builder)
+}
+
+// unions the entries of non-null maps into one map, keeping the position of the first and the value of the last
+// entry of each key
+fn map_merge(
+    ranges: &SmallVec<[SortKeyArrayRange; 4]>,
+    append_array_data_builder: &mut Box<dyn ArrayBuilder>,
+) -> MergeResult {
+    let mut entries = vec![];
+    for range in ranges.iter() {
+        let array = range.array();
+        let map = as_map_array(array.as_ref());
+        let map_entries = map.to_data().child_data()[0].clone();
+        let offsets = map.value_offsets();
+        for i in range.begin_row..range.end_row {
+            if map.is_valid(i) {
+                let (start, end) = (offsets[i] as usize, offsets[i + 1] as usize);
+                entries.push(make_array(map_entries.slice(start, end - start)));
             }
         }
-        match is_none {
-            // only when all ranges are null values, the result is also null
-            true => MergeResult::AppendNull,
-            false => {
-                $builder
-                    .as_any_mut()
-                    .downcast_mut::<$primitive_builder_type>()
-                    .unwrap()
-                    .append_value(res);
-                MergeResult::AppendValue($builder.len() - 1)
+    }
+    if entries.is_empty() {
+        return MergeResult::AppendNull;
+    }
+    let entries = concat(&entries.iter().map(|entry| entry.as_ref()).collect::<Vec<_>>()).unwrap();
+    let keys = as_struct_array(entries.as_ref()).column(0).clone();
+    let converter = RowConverter::new(vec![SortField::new(keys.data_type().clone())]).unwrap();
+    let rows = converter.convert_columns(&[keys]).unwrap();
+    let mut key_positions = HashMap::new();
+    let mut indices: Vec<u32> = vec![];
+    for (i, row) in rows.iter().enumerate() {
+        match key_positions.get(&row) {
+            Some(&position) => indices[position] = i as u32,
+            None => {
+                key_positions.insert(row, indices.len());
+                indices.push(i as u32);
             }
         }
-    }};
+    }
+    let entries = take(entries.as_ref(), &UInt32Array::from(indices), None).unwrap();
+    let array = ranges[0].array();
+    append_single_nested_value(array.data_type(), entries, append_array_data_builder)
+}
+
+// appends a list or map of `values` to the builder, which must be an `ArrayValueBuilder`
+fn append_single_nested_value(
+    data_type: &DataType,
+    values: ArrayRef,
+    append_array_data_builder: &mut Box<dyn ArrayBuilder>,
+) -> MergeResult {
+    let data = ArrayData::builder(data_type.clone())
+        .len(1)
+        .add_buffer(Buffer::from_slice_ref([0_i32, values.len() as i32]))
+        .add_child_data(values.to_data())
+        .build()
+        .unwrap();
+    let builder = append_array_data_builder
+        .as_any_mut()
+        .downcast_mut::<ArrayValueBuilder>()
+        .unwrap();
+    builder.append_value(make_array(data));
+    MergeResult::AppendValue(builder.len() - 1)
 }
 
 #[cfg(test)]
 mod tests {
-    use arrow::array::{PrimitiveArray, TimestampMillisecondArray};
+    use super::*;
+    use arrow::array::{
+        Date32Array, Decimal128Array, Decimal256Array, DurationSecondArray, Int64Array, IntervalDayTimeArray,
+        IntervalMonthDayNanoArray, ListArray, MapArray, PrimitiveArray, StringArray, TimestampMillisecondArray,
+    };
     use arrow::datatypes::TimestampMillisecondType;
+
+    #[derive(Debug)]
+    struct TakeFirst;
+
+    impl MergeFunction for TakeFirst {
+        fn merge(
+            &self,
+            _data_type: DataType,
+            ranges: &SmallVec<[SortKeyArrayRange; 4]>,
+            _append_array_data_builder: &mut Box<dyn ArrayBuilder>,
+        ) -> ArrowResult<MergeResult> {
+            Ok(MergeResult::Extend(ranges[0].batch_idx, ranges[0].begin_row))
+        }
+    }
+
+    // one range of all rows of each array, in batch order
+    fn ranges(arrays: Vec<ArrayRef>) -> SmallVec<[SortKeyArrayRange; 4]> {
+        arrays
+            .into_iter()
+            .enumerate()
+            .map(|(batch_idx, array)| SortKeyArrayRange {
+                begin_row: 0,
+                end_row: array.len(),
+                stream_idx: batch_idx,
+                batch_idx,
+                array,
+            })
+            .collect()
+    }
+
+    fn try_merge(
+        op: &str,
+        arrays: Vec<ArrayRef>,
+        append_array_data_builder: &mut Box<dyn ArrayBuilder>,
+    ) -> ArrowResult<MergeResult> {
+        let data_type = arrays[0].data_type().clone();
+        MergeOperator::from_name(op)
+            .unwrap()
+            .merge(data_type, &ranges(arrays), append_array_data_builder)
+    }
+
+    fn merge(op: &str, arrays: Vec<ArrayRef>, append_array_data_builder: &mut Box<dyn ArrayBuilder>) -> MergeResult {
+        try_merge(op, arrays, append_array_data_builder).unwrap()
+    }
+
+    #[test]
+    fn test_merge_operator_from_name() -> Result<()> {
+        assert_eq!(
+            MergeOperator::from_name("UseLastNotNull")?,
+            MergeOperator::UseLastNotNull
+        );
+        assert_eq!(
+            MergeOperator::from_name("DistinctConcat(;)")?,
+            MergeOperator::Concat {
+                delimiter: ";".to_string(),
+                distinct: true
+            }
+        );
+        assert_eq!(
+            MergeOperator::from_name("Concat")?,
+            MergeOperator::Concat {
+                delimiter: ",".to_string(),
+                distinct: false
+            }
+        );
+        assert!(MergeOperator::from_name("Concat;").is_err());
+        assert!(matches!(
+            MergeOperator::from_name("TakeFirst"),
+            Err(DataFusionError::Configuration(_))
+        ));
+        assert!(register_merge_function("Sum", Arc::new(TakeFirst)).is_err());
+        assert!(register_merge_function("Concat(|)", Arc::new(TakeFirst)).is_err());
+
+        let function: Arc<dyn MergeFunction> = Arc::new(TakeFirst);
+        register_merge_function("TakeFirst", function.clone())?;
+        assert_eq!(MergeOperator::from_name("TakeFirst")?, MergeOperator::Custom(function));
+        Ok(())
+    }
+
+    #[test]
+    fn test_pick_row_merge_operators() {
+        let mut builder: Box<dyn ArrayBuilder> = Box::new(Int64Builder::new());
+        let arrays: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![None, Some(3)])),
+            Arc::new(Int64Array::from(vec![Some(7), Some(1), None])),
+        ];
+        assert_eq!(
+            merge("UseFirst", arrays.clone(), &mut builder),
+            MergeResult::Extend(0, 0)
+        );
+        assert_eq!(
+            merge("UseFirstNotNull", arrays.clone(), &mut builder),
+            MergeResult::Extend(0, 1)
+        );
+        assert_eq!(
+            merge("UseLastNotNull", arrays.clone(), &mut builder),
+            MergeResult::Extend(1, 1)
+        );
+        assert_eq!(merge("Max", arrays.clone(), &mut builder), MergeResult::Extend(1, 0));
+        assert_eq!(merge("Min", arrays, &mut builder), MergeResult::Extend(1, 1));
+
+        let dates: Vec<ArrayRef> = vec![
+            Arc::new(Date32Array::from(vec![Some(19000), None])),
+            Arc::new(Date32Array::from(vec![Some(18000)])),
+        ];
+        assert_eq!(merge("Max", dates.clone(), &mut builder), MergeResult::Extend(0, 0));
+        assert_eq!(merge("Min", dates, &mut builder), MergeResult::Extend(1, 0));
+        let timestamps: Vec<ArrayRef> = vec![
+            Arc::new(TimestampMillisecondArray::from(vec![1, 5])),
+            Arc::new(TimestampMillisecondArray::from(vec![3])),
+        ];
+        assert_eq!(merge("Max", timestamps, &mut builder), MergeResult::Extend(0, 1));
+
+        let nulls: Vec<ArrayRef> = vec![Arc::new(Int64Array::from(vec![None, None]))];
+        for op in ["UseFirstNotNull", "Max", "Min"] {
+            assert_eq!(merge(op, nulls.clone(), &mut builder), MergeResult::AppendNull);
+        }
+        assert_eq!(builder.len(), 0);
+    }
+
+    #[test]
+    fn test_sum_merge_operator() {
+        let mut builder: Box<dyn ArrayBuilder> = Box::new(Int64Builder::new());
+        let arrays: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![None, Some(3)])),
+            Arc::new(Int64Array::from(vec![Some(7), None])),
+        ];
+        assert_eq!(merge("Sum", arrays, &mut builder), MergeResult::AppendValue(0));
+        let overflow: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![i64::MAX - 1])),
+            Arc::new(Int64Array::from(vec![Some(2), None])),
+        ];
+        assert!(matches!(
+            try_merge("Sum", overflow.clone(), &mut builder),
+            Err(ArrowError::ComputeError(_))
+        ));
+        assert_eq!(
+            merge("SaturatingSum", overflow, &mut builder),
+            MergeResult::AppendValue(1)
+        );
+        let nulls: Vec<ArrayRef> = vec![Arc::new(Int64Array::from(vec![None, None]))];
+        assert_eq!(merge("Sum", nulls, &mut builder), MergeResult::AppendNull);
+        assert_eq!(
+            as_primitive_array::<Int64Type>(builder.finish().as_ref()),
+            &Int64Array::from(vec![10, i64::MAX])
+        );
+    }
+
+    #[test]
+    fn test_sum_merge_operator_with_decimal() {
+        let decimals = |values: Vec<i128>| -> ArrayRef {
+            Arc::new(Decimal128Array::from(values).with_precision_and_scale(5, 2).unwrap())
+        };
+        let data_type = DataType::Decimal128(5, 2);
+        let mut builder: Box<dyn ArrayBuilder> = Box::new(Decimal128Builder::new().with_data_type(data_type.clone()));
+        assert_eq!(
+            merge(
+                "Sum",
+                vec![decimals(vec![12345, 50000]), decimals(vec![-100])],
+                &mut builder
+            ),
+            MergeResult::AppendValue(0)
+        );
+        // the sum exceeds the precision of the column
+        let overflow = vec![decimals(vec![99999]), decimals(vec![1])];
+        assert!(try_merge("Sum", overflow.clone(), &mut builder).is_err());
+        assert_eq!(
+            merge("SaturatingSum", overflow, &mut builder),
+            MergeResult::AppendValue(1)
+        );
+        assert_eq!(
+            merge("SaturatingSum", vec![decimals(vec![-99999, -99999])], &mut builder),
+            MergeResult::AppendValue(2)
+        );
+        assert_eq!(
+            builder.finish().to_data(),
+            decimals(vec![62245, 99999, -99999]).to_data()
+        );
+
+        let decimals = |values: Vec<i128>| -> ArrayRef {
+            Arc::new(
+                Decimal256Array::from(values.into_iter().map(i256::from_i128).collect::<Vec<_>>())
+                    .with_precision_and_scale(40, 0)
+                    .unwrap(),
+            )
+        };
+        let mut builder: Box<dyn ArrayBuilder> =
+            Box::new(Decimal256Builder::new().with_data_type(DataType::Decimal256(40, 0)));
+        assert_eq!(
+            merge(
+                "Sum",
+                vec![decimals(vec![i128::MAX]), decimals(vec![i128::MAX])],
+                &mut builder
+            ),
+            MergeResult::AppendValue(0)
+        );
+        assert_eq!(
+            builder.finish().to_data(),
+            Decimal256Array::from(vec![i256::from_i128(i128::MAX).wrapping_mul(i256::from_i128(2))])
+                .with_precision_and_scale(40, 0)
+                .unwrap()
+                .to_data()
+        );
+    }
+
+    #[test]
+    fn test_sum_merge_operator_with_duration_and_interval() {
+        let mut builder: Box<dyn ArrayBuilder> = Box::new(DurationSecondBuilder::new());
+        let durations: Vec<ArrayRef> = vec![
+            Arc::new(DurationSecondArray::from(vec![Some(60), None])),
+            Arc::new(DurationSecondArray::from(vec![Some(3)])),
+        ];
+        assert_eq!(merge("Sum", durations, &mut builder), MergeResult::AppendValue(0));
+        assert_eq!(
+            builder.finish().to_data(),
+            DurationSecondArray::from(vec![63]).to_data()
+        );
+
+        let mut builder: Box<dyn ArrayBuilder> = Box::new(IntervalDayTimeBuilder::new());
+        let intervals: Vec<ArrayRef> = vec![
+            Arc::new(IntervalDayTimeArray::from(vec![
+                IntervalDayTimeType::make_value(1, 500),
+                IntervalDayTimeType::make_value(2, -100),
+            ])),
+            Arc::new(IntervalDayTimeArray::from(vec![IntervalDayTimeType::make_value(
+                -1, 20,
+            )])),
+        ];
+        assert_eq!(merge("Sum", intervals, &mut builder), MergeResult::AppendValue(0));
+        let overflow: Vec<ArrayRef> = vec![Arc::new(IntervalDayTimeArray::from(vec![
+            IntervalDayTimeType::make_value(i32::MAX, 0),
+            IntervalDayTimeType::make_value(1, 0),
+        ]))];
+        assert!(try_merge("Sum", overflow.clone(), &mut builder).is_err());
+        assert_eq!(
+            merge("SaturatingSum", overflow, &mut builder),
+            MergeResult::AppendValue(1)
+        );
+        assert_eq!(
+            builder.finish().to_data(),
+            IntervalDayTimeArray::from(vec![
+                IntervalDayTimeType::make_value(2, 420),
+                IntervalDayTimeType::make_value(i32::MAX, 0),
+            ])
+            .to_data()
+        );
+
+        let mut builder: Box<dyn ArrayBuilder> = Box::new(IntervalMonthDayNanoBuilder::new());
+        let intervals: Vec<ArrayRef> = vec![Arc::new(IntervalMonthDayNanoArray::from(vec![
+            IntervalMonthDayNanoType::make_value(1, 2, 3),
+            IntervalMonthDayNanoType::make_value(10, 20, 30),
+        ]))];
+        assert_eq!(merge("Sum", intervals, &mut builder), MergeResult::AppendValue(0));
+        assert_eq!(
+            builder.finish().to_data(),
+            IntervalMonthDayNanoArray::from(vec![IntervalMonthDayNanoType::make_value(11, 22, 33)]).to_data()
+        );
+    }
+
+    #[test]
+    fn test_count_merge_operator() {
+        let mut builder: Box<dyn ArrayBuilder> = Box::new(Int64Builder::new());
+        let arrays: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![None, Some(3)])),
+            Arc::new(Int64Array::from(vec![Some(7)])),
+        ];
+        assert_eq!(merge("Count", arrays, &mut builder), MergeResult::AppendValue(0));
+        let nulls: Vec<ArrayRef> = vec![Arc::new(Int64Array::from(vec![None]))];
+        assert_eq!(merge("Count", nulls, &mut builder), MergeResult::AppendValue(1));
+        assert_eq!(
+            as_primitive_array::<Int64Type>(builder.finish().as_ref()),
+            &Int64Array::from(vec![2, 0])
+        );
+        let strings: Vec<ArrayRef> = vec![Arc::new(StringArray::from(vec!["a", "b"]))];
+        assert!(matches!(
+            try_merge("Count", strings, &mut builder),
+            Err(ArrowError::NotYetImplemented(_))
+        ));
+    }
+
+    #[test]
+    fn test_merge_operator_check_data_type() -> Result<()> {
+        let check = |op: &str, data_type: DataType| MergeOperator::from_name(op)?.check_data_type("c", &data_type);
+        check("UseLast", DataType::Binary)?;
+        check("Sum", DataType::Decimal128(10, 2))?;
+        check("Max", DataType::Utf8)?;
+        check("Count", DataType::Int64)?;
+        check("Concat", DataType::Utf8)?;
+        assert!(check("Sum", DataType::Date32).is_err());
+        assert!(check("Count", DataType::Int16).is_err());
+        assert!(check("Concat", DataType::LargeUtf8).is_err());
+        assert!(check("ListAppend", DataType::Int32).is_err());
+        assert!(matches!(
+            check(
+                "Max",
+                DataType::Struct(vec![arrow_schema::Field::new("a", DataType::Int32, true)].into())
+            ),
+            Err(DataFusionError::Configuration(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_concat_merge_operator() {
+        let mut builder: Box<dyn ArrayBuilder> = Box::new(StringBuilder::new());
+        let arrays: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec![Some("a"), None, Some("b")])),
+            Arc::new(StringArray::from(vec![Some("a"), Some("c")])),
+        ];
+        assert_eq!(
+            merge("Concat", arrays.clone(), &mut builder),
+            MergeResult::AppendValue(0)
+        );
+        assert_eq!(
+            merge("Concat(|)", arrays.clone(), &mut builder),
+            MergeResult::AppendValue(1)
+        );
+        assert_eq!(
+            merge("DistinctConcat(|)", arrays, &mut builder),
+            MergeResult::AppendValue(2)
+        );
+        let nulls: Vec<ArrayRef> = vec![Arc::new(StringArray::from(vec![None, None::<&str>]))];
+        assert_eq!(merge("DistinctConcat", nulls, &mut builder), MergeResult::AppendNull);
+        assert_eq!(
+            as_string_array(builder.finish().as_ref()),
+            &StringArray::from(vec!["a,b,a,c", "a|b|a|c", "a|b|c"])
+        );
+    }
+
+    #[test]
+    fn test_list_append_merge_operator() {
+        let arrays: Vec<ArrayRef> = vec![
+            Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+                Some(vec![Some(1), Some(2)]),
+                None,
+            ])),
+            Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(vec![Some(vec![
+                Some(3),
+                None,
+            ])])),
+        ];
+        let mut builder: Box<dyn ArrayBuilder> = Box::new(ArrayValueBuilder::new(arrays[0].data_type().clone()));
+        assert_eq!(merge("ListAppend", arrays, &mut builder), MergeResult::AppendValue(0));
+        let nulls: Vec<ArrayRef> = vec![Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+            None::<Vec<Option<i64>>>,
+            None,
+        ]))];
+        assert_eq!(merge("ListAppend", nulls, &mut builder), MergeResult::AppendNull);
+        assert_eq!(
+            as_list_array(builder.finish().as_ref()),
+            &ListArray::from_iter_primitive::<Int64Type, _, _>(vec![Some(vec![Some(1), Some(2), Some(3), None])])
+        );
+    }
+
+    #[test]
+    fn test_map_merge_operator() {
+        fn map_array(maps: Vec<Option<Vec<(&str, i64)>>>) -> MapArray {
+            let mut builder = MapBuilder::new(None, StringBuilder::new(), Int64Builder::new());
+            for map in maps {
+                match map {
+                    Some(entries) => {
+                        for (key, value) in entries {
+                            builder.keys().append_value(key);
+                            builder.values().append_value(value);
+                        }
+                        builder.append(true).unwrap();
+                    }
+                    None => builder.append(false).unwrap(),
+                }
+            }
+            builder.finish()
+        }
+
+        let arrays: Vec<ArrayRef> = vec![
+            Arc::new(map_array(vec![Some(vec![("a", 1), ("b", 2)]), None])),
+            Arc::new(map_array(vec![Some(vec![("c", 3), ("a", 4)])])),
+        ];
+        let mut builder: Box<dyn ArrayBuilder> = Box::new(ArrayValueBuilder::new(arrays[0].data_type().clone()));
+        assert_eq!(merge("MapMerge", arrays, &mut builder), MergeResult::AppendValue(0));
+        let nulls: Vec<ArrayRef> = vec![Arc::new(map_array(vec![None]))];
+        assert_eq!(merge("MapMerge", nulls, &mut builder), MergeResult::AppendNull);
+        assert_eq!(
+            builder.finish().to_data(),
+            map_array(vec![Some(vec![("a", 4), ("b", 2), ("c", 3)])]).to_data()
+        );
+    }
+
     #[test]
     fn test_timestamp_with_fixed_offset_tz_fmt_debug() {
         let arr: PrimitiveArray<TimestampMillisecondType> =
@@ -5,4 +5,5 @@
 pub mod sorted_stream_merger;
 pub mod combiner;
 pub mod sort_key_range;
-pub mod merge_operator;
\ No newline at end of file
+pub mod merge_operator;
+pub mod range_split;
@@ -0,0 +1,120 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Splits the primary key space of files into sub-ranges which can be merged independently.
+
+use std::cmp::Ordering;
+
+use arrow_schema::Field;
+use datafusion::datasource::listing::ListingTableUrl;
+use datafusion::error::Result;
+use datafusion::logical_expr::{lit, Expr};
+use datafusion::prelude::SessionContext;
+use datafusion::scalar::ScalarValue;
+use parquet::arrow::async_reader::{AsyncFileReader, ParquetObjectReader};
+
+use crate::statistics::{max_to_scalar, min_to_scalar};
+
+/// Collects min and max values of `field` in all row groups of `files` from parquet footers.
+/// Returns None if any row group has no statistics of the field.
+pub(crate) async fn row_group_bounds(
+    sess_ctx: &SessionContext,
+    files: &[String],
+    field: &Field,
+) -> Result<Option<Vec<ScalarValue>>> {
+    let mut bounds = vec![];
+    for file in files {
+        let url = ListingTableUrl::parse(file)?;
+        let store = sess_ctx.runtime_env().object_store(url.object_store())?;
+        let meta = store.head(url.prefix()).await?;
+        let mut reader = ParquetObjectReader::new(store, meta);
+        let metadata = reader.get_metadata().await?;
+        let Some(idx) = metadata
+            .file_metadata()
+            .schema_descr()
+            .columns()
+            .iter()
+            .position(|column| column.path().parts() == [field.name().clone()])
+        else {
+            return Ok(None);
+        };
+        for row_group in metadata.row_groups() {
+            let Some(stats) = row_group.column(idx).statistics() else {
+                return Ok(None);
+            };
+            if row_group.num_rows() == stats.null_count() as i64 {
+                continue;
+            }
+            match (
+                min_to_scalar(stats, field.data_type()),
+                max_to_scalar(stats, field.data_type()),
+            ) {
+                (Some(min), Some(max)) => bounds.extend([min, max]),
+                _ => return Ok(None),
+            }
+        }
+    }
+    Ok(Some(bounds))
+}
+
+/// Picks at most `num_ranges - 1` distinct split points at even quantiles of `bounds`.
+pub(crate) fn split_points(mut bounds: Vec<ScalarValue>, num_ranges: usize) -> Vec<ScalarValue> {
+    bounds.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    bounds.dedup();
+    if bounds.is_empty() {
+        return vec![];
+    }
+    let mut points = (1..num_ranges)
+        .map(|i| bounds[bounds.len() * i / num_ranges].clone())
+        // splitting at the smallest value would leave only nulls in the first range
+        .filter(|point| point != &bounds[0])
+        .collect::<Vec<_>>();
+    points.dedup();
+    points
+}
+
+/// Filters of the sub-ranges of `column` divided by ascending `split_points`, in key order.
+/// Nulls sort first as in the merge, so they belong to the first range.
+pub(crate) fn range_filters(column: &str, split_points: &[ScalarValue]) -> Vec<Expr> {
+    let column = Expr::Column(datafusion::common::Column::new_unqualified(column));
+    (0..=split_points.len())
+        .map(|i| {
+            let lower = (i > 0).then(|| column.clone().gt_eq(lit(split_points[i - 1].clone())));
+            let upper = split_points.get(i).map(|point| column.clone().lt(lit(point.clone())));
+            match (lower, upper) {
+                (Some(lower), Some(upper)) => lower.and(upper),
+                (Some(lower), None) => lower,
+                (None, Some(upper)) => column.clone().is_null().or(upper),
+                (None, None) => lit(true),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::prelude::col;
+
+    #[test]
+    fn test_split_points() {
+        let bounds = [5, 1, 3, 9, 3, 7, 1].map(|v| ScalarValue::Int64(Some(v))).to_vec();
+        assert_eq!(
+            split_points(bounds.clone(), 3),
+            vec![ScalarValue::Int64(Some(3)), ScalarValue::Int64(Some(7))]
+        );
+        assert!(split_points(bounds.clone(), 1).is_empty());
+        assert!(split_points(vec![ScalarValue::Int64(Some(1)); 4], 4).is_empty());
+        assert!(split_points(vec![], 4).is_empty());
+
+        assert_eq!(
+            range_filters("id", &split_points(bounds, 2)),
+            vec![
+                col("id").is_null().or(col("id").lt(lit(5_i64))),
+                col("id").gt_eq(lit(5_i64)),
+            ]
+        );
+        assert_eq!(range_filters("id", &[]), vec![lit(true)]);
+    }
+}
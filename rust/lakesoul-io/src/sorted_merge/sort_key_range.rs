@@ -173,6 +173,18 @@ impl SortKeyArrayRange {
     pub fn array(&self) -> ArrayRef {
         self.array.clone()
     }
+
+    pub fn begin_row(&self) -> usize {
+        self.begin_row
+    }
+
+    pub fn end_row(&self) -> usize {
+        self.end_row
+    }
+
+    pub fn batch_idx(&self) -> usize {
+        self.batch_idx
+    }
 }
 
 impl Clone for SortKeyArrayRange {
@@ -8,14 +8,18 @@ use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
+use crate::constant::LAKESOUL_CDC_DELETE;
 use crate::sorted_merge::combiner::{RangeCombiner, RangeCombinerResult};
 use crate::sorted_merge::merge_operator::MergeOperator;
 use crate::sorted_merge::sort_key_range::SortKeyBatchRange;
 
+use arrow::array::{as_string_array, BooleanArray};
+use arrow::compute::filter_record_batch;
 use arrow::record_batch::RecordBatch;
 use arrow::row::{RowConverter, SortField};
+use arrow_schema::DataType;
 use datafusion::arrow::datatypes::SchemaRef;
-use datafusion::error::Result;
+use datafusion::error::{DataFusionError, Result};
 use datafusion::physical_expr::PhysicalExpr;
 use datafusion::physical_plan::{expressions::col, RecordBatchStream, SendableRecordBatchStream};
 use datafusion_common::DataFusionError::ArrowError;
@@ -95,6 +99,14 @@ pub(crate) struct SortedStreamMerger {
     batch_idx_counter: usize,
 
     initialized: Vec<bool>,
+
+    /// Max number of merged rows to emit
+    limit: Option<usize>,
+
+    num_output_rows: usize,
+
+    /// Index of the cdc column, by which keys whose last version is a delete are dropped
+    cdc_column_idx: Option<usize>,
 }
 
 impl SortedStreamMerger {
@@ -166,9 +178,68 @@ impl SortedStreamMerger {
             row_converters,
             batch_idx_counter: 0,
             initialized: vec![false; streams_num],
+            limit: None,
+            num_output_rows: 0,
+            cdc_column_idx: None,
         })
     }
 
+    /// Stops merging after emitting `limit` rows, without polling the input streams any more.
+    pub(crate) fn with_limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Drops keys whose last version is a delete by the cdc column, unless `keep_deletes` is set.
+    /// The cdc column must be merged with UseLast.
+    pub(crate) fn with_cdc_column(mut self, cdc_column: Option<&str>, keep_deletes: bool) -> Result<Self> {
+        self.cdc_column_idx = match cdc_column {
+            Some(cdc_column) if !keep_deletes => {
+                let field = self.schema.field_with_name(cdc_column)?;
+                if field.data_type() != &DataType::Utf8 {
+                    return Err(DataFusionError::Configuration(format!(
+                        "cdc column {} must be of type Utf8, found {}",
+                        cdc_column,
+                        field.data_type()
+                    )));
+                }
+                Some(self.schema.index_of(cdc_column)?)
+            }
+            _ => None,
+        };
+        Ok(self)
+    }
+
+    fn drop_deletes(&self, batch: RecordBatch) -> arrow::error::Result<RecordBatch> {
+        match self.cdc_column_idx {
+            Some(idx) => {
+                let row_kinds = as_string_array(batch.column(idx).as_ref());
+                let not_deleted = row_kinds
+                    .iter()
+                    .map(|row_kind| Some(row_kind != Some(LAKESOUL_CDC_DELETE)))
+                    .collect::<BooleanArray>();
+                filter_record_batch(&batch, &not_deleted)
+            }
+            None => Ok(batch),
+        }
+    }
+
+    fn limit_reached(&self) -> bool {
+        self.limit.map_or(false, |limit| self.num_output_rows >= limit)
+    }
+
+    // truncates the last batch within the limit
+    fn apply_limit(&mut self, batch: RecordBatch) -> RecordBatch {
+        let batch = match self.limit {
+            Some(limit) if self.num_output_rows + batch.num_rows() > limit => {
+                batch.slice(0, limit - self.num_output_rows)
+            }
+            _ => batch,
+        };
+        self.num_output_rows += batch.num_rows();
+        batch
+    }
+
     /// If the stream at the given index is not exhausted, and the last batch range for the
     /// stream is finished, poll the stream for the next RecordBatch and create a new
     /// batch range for the stream from the returned result
@@ -229,7 +300,7 @@ impl SortedStreamMerger {
 impl SortedStreamMerger {
     #[inline]
     fn poll_next_inner(self: &mut Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<RecordBatch>>> {
-        if self.aborted {
+        if self.aborted || self.limit_reached() {
             return Poll::Ready(None);
         }
 
@@ -259,6 +330,7 @@ impl SortedStreamMerger {
         loop {
             match self.range_combiner.poll_result() {
                 RangeCombinerResult::Err(e) => {
+                    self.aborted = true;
                     return Poll::Ready(Some(Err(ArrowError(e))));
                 }
                 RangeCombinerResult::None => {
@@ -284,7 +356,14 @@ impl SortedStreamMerger {
                         }
                     }
                 }
-                RangeCombinerResult::RecordBatch(batch) => return Poll::Ready(Some(batch.map_err(ArrowError))),
+                RangeCombinerResult::RecordBatch(batch) => {
+                    let batch = batch.and_then(|batch| self.drop_deletes(batch));
+                    // a batch of only deleted keys is skipped, since empty batches may be taken as the end of reading
+                    if matches!(&batch, Ok(batch) if batch.num_rows() == 0) {
+                        continue;
+                    }
+                    return Poll::Ready(Some(batch.map(|batch| self.apply_limit(batch)).map_err(ArrowError)));
+                }
             }
         }
     }
@@ -311,12 +390,12 @@ mod tests {
 
     use arrow::array::as_primitive_array;
     use arrow::array::ArrayRef;
-    use arrow::array::{Int32Array, StringArray};
+    use arrow::array::{Int32Array, Int64Array, StringArray};
     use arrow::datatypes::Int64Type;
     use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
     use arrow::record_batch::RecordBatch;
     use arrow::util::pretty::print_batches;
-    use arrow_array::Float64Array;
+    use arrow_array::{BooleanArray, Float64Array};
     use datafusion::assert_batches_eq;
     use datafusion::error::Result;
     use datafusion::execution::context::TaskContext;
@@ -325,6 +404,7 @@ mod tests {
     use datafusion::prelude::{SessionConfig, SessionContext};
 
     use comfy_table::{Cell, Table};
+    use futures::StreamExt;
 
     use crate::lakesoul_io_config::LakeSoulIOConfigBuilder;
     use crate::lakesoul_reader::LakeSoulReader;
@@ -529,6 +609,183 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_sorted_stream_merger_with_limit() {
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+        let s1 = create_stream(
+            vec![
+                create_batch_one_col_i32("a", &[1, 3, 5]),
+                create_batch_one_col_i32("a", &[7, 9]),
+            ],
+            task_ctx.clone(),
+        )
+        .await
+        .unwrap();
+        let s2 = create_stream(vec![create_batch_one_col_i32("a", &[2, 3, 4, 8])], task_ctx.clone())
+            .await
+            .unwrap();
+        let schema = s2.stream.schema();
+
+        let merge_stream =
+            SortedStreamMerger::new_from_streams(vec![s1, s2], schema, vec![String::from("a")], 2, vec![])
+                .unwrap()
+                .with_limit(Some(3));
+        let merged = common::collect(Box::pin(merge_stream)).await.unwrap();
+        assert_batches_eq!(
+            &["+---+", "| a |", "+---+", "| 1 |", "| 2 |", "| 3 |", "+---+",],
+            &merged
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sorted_stream_merger_with_sum_overflow() {
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+        let create_batch = |ids: Vec<i32>, values: Vec<i64>| {
+            RecordBatch::try_from_iter(vec![
+                ("id", Arc::new(Int32Array::from(ids)) as ArrayRef),
+                ("v", Arc::new(Int64Array::from(values)) as ArrayRef),
+            ])
+            .unwrap()
+        };
+        let merge = |merge_op: MergeOperator| {
+            let task_ctx = task_ctx.clone();
+            async move {
+                let s1 = create_stream(vec![create_batch(vec![1, 2], vec![1, i64::MAX])], task_ctx.clone()).await?;
+                let s2 = create_stream(vec![create_batch(vec![2], vec![1])], task_ctx).await?;
+                let schema = s1.stream.schema();
+                let merge_stream = SortedStreamMerger::new_from_streams(
+                    vec![s1, s2],
+                    schema,
+                    vec![String::from("id")],
+                    2,
+                    vec![MergeOperator::UseLast, merge_op],
+                )?;
+                common::collect(Box::pin(merge_stream)).await
+            }
+        };
+
+        assert!(merge(MergeOperator::Sum).await.is_err());
+        let merged = merge(MergeOperator::SaturatingSum).await.unwrap();
+        assert_batches_eq!(
+            &[
+                "+----+---------------------+",
+                "| id | v                   |",
+                "+----+---------------------+",
+                "| 1  | 1                   |",
+                "| 2  | 9223372036854775807 |",
+                "+----+---------------------+",
+            ],
+            &merged
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sorted_stream_merger_with_unsupported_merge_operator() {
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+        let create_batch = |ids: Vec<i32>, values: Vec<bool>| {
+            RecordBatch::try_from_iter(vec![
+                ("id", Arc::new(Int32Array::from(ids)) as ArrayRef),
+                ("v", Arc::new(BooleanArray::from(values)) as ArrayRef),
+            ])
+            .unwrap()
+        };
+        let s1 = create_stream(vec![create_batch(vec![1, 2], vec![true, false])], task_ctx.clone())
+            .await
+            .unwrap();
+        let s2 = create_stream(vec![create_batch(vec![2], vec![true])], task_ctx)
+            .await
+            .unwrap();
+        let schema = s1.stream.schema();
+        let mut merge_stream = Box::pin(
+            SortedStreamMerger::new_from_streams(
+                vec![s1, s2],
+                schema,
+                vec![String::from("id")],
+                2,
+                vec![MergeOperator::UseLast, MergeOperator::Sum],
+            )
+            .unwrap(),
+        );
+
+        assert!(merge_stream.next().await.unwrap().is_err());
+        // the merger is aborted by the error
+        assert!(merge_stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sorted_stream_merger_with_cdc_column() {
+        let session_ctx = SessionContext::new();
+        let task_ctx = session_ctx.task_ctx();
+        let create_batch = |ids: Vec<i32>, kinds: Vec<&str>| {
+            RecordBatch::try_from_iter(vec![
+                ("id", Arc::new(Int32Array::from(ids)) as ArrayRef),
+                ("rowKinds", Arc::new(StringArray::from(kinds)) as ArrayRef),
+            ])
+            .unwrap()
+        };
+        let merge = |cdc_column: &'static str, keep_deletes: bool| {
+            let task_ctx = task_ctx.clone();
+            async move {
+                let s1 = create_stream(
+                    vec![create_batch(
+                        vec![1, 2, 3, 4],
+                        vec!["insert", "insert", "insert", "insert"],
+                    )],
+                    task_ctx.clone(),
+                )
+                .await?;
+                let s2 = create_stream(
+                    vec![create_batch(vec![1, 2, 4], vec!["delete", "delete", "update"])],
+                    task_ctx,
+                )
+                .await?;
+                let schema = s1.stream.schema();
+                let merge_stream = SortedStreamMerger::new_from_streams(
+                    vec![s1, s2],
+                    schema,
+                    vec![String::from("id")],
+                    2,
+                    vec![MergeOperator::UseLast, MergeOperator::UseLast],
+                )?
+                .with_cdc_column(Some(cdc_column), keep_deletes)?;
+                common::collect(Box::pin(merge_stream)).await
+            }
+        };
+
+        // the first output batch only has deleted keys and is skipped
+        let merged = merge("rowKinds", false).await.unwrap();
+        assert_eq!(merged.len(), 1);
+        assert_batches_eq!(
+            &[
+                "+----+----------+",
+                "| id | rowKinds |",
+                "+----+----------+",
+                "| 3  | insert   |",
+                "| 4  | update   |",
+                "+----+----------+",
+            ],
+            &merged
+        );
+        let merged = merge("rowKinds", true).await.unwrap();
+        assert_batches_eq!(
+            &[
+                "+----+----------+",
+                "| id | rowKinds |",
+                "+----+----------+",
+                "| 1  | delete   |",
+                "| 2  | delete   |",
+                "| 3  | insert   |",
+                "| 4  | update   |",
+                "+----+----------+",
+            ],
+            &merged
+        );
+        assert!(merge("id", false).await.is_err());
+    }
+
     fn create_batch_i32(names: Vec<&str>, values: Vec<&[i32]>) -> RecordBatch {
         let values = values
             .into_iter()
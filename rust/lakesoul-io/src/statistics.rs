@@ -0,0 +1,231 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Column statistics of written files, collected from parquet footers.
+
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use arrow::compute::cast;
+use arrow_schema::{DataType, SchemaRef};
+use datafusion::error::Result;
+use datafusion::scalar::ScalarValue;
+use parquet::arrow::arrow_to_parquet_schema;
+use parquet::file::metadata::RowGroupMetaData;
+use parquet::file::statistics::Statistics;
+use parquet::format::FileMetaData;
+
+/// Min, max and null count of a top level column over all row groups of a file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnStatistics {
+    pub name: String,
+    /// None if any row group has no min/max, e.g. all values are null, or the type is not supported
+    pub min: Option<ScalarValue>,
+    pub max: Option<ScalarValue>,
+    /// None if any row group has no statistics
+    pub null_count: Option<u64>,
+}
+
+/// Collects statistics of top level primitive columns of `schema` from the footer of a written file.
+/// Nested columns are skipped.
+pub fn column_statistics_from_metadata(schema: SchemaRef, metadata: &FileMetaData) -> Result<Vec<ColumnStatistics>> {
+    let schema_descr = Arc::new(arrow_to_parquet_schema(&schema)?);
+    let row_groups = metadata
+        .row_groups
+        .iter()
+        .map(|row_group| RowGroupMetaData::from_thrift(schema_descr.clone(), row_group.clone()))
+        .collect::<parquet::errors::Result<Vec<_>>>()?;
+
+    let mut column_statistics = vec![];
+    for (idx, column) in schema_descr.columns().iter().enumerate() {
+        let parts = column.path().parts();
+        if parts.len() != 1 {
+            continue;
+        }
+        let Ok(field) = schema.field_with_name(&parts[0]) else {
+            continue;
+        };
+        let mut stats = ColumnStatistics {
+            name: field.name().clone(),
+            min: None,
+            max: None,
+            null_count: Some(0),
+        };
+        let mut min_max_complete = true;
+        for row_group in &row_groups {
+            let Some(row_group_stats) = row_group.column(idx).statistics() else {
+                min_max_complete = false;
+                stats.null_count = None;
+                continue;
+            };
+            stats.null_count = stats.null_count.map(|count| count + row_group_stats.null_count());
+            if row_group.num_rows() == row_group_stats.null_count() as i64 {
+                // no min/max of all null row groups
+                continue;
+            }
+            match (
+                min_to_scalar(row_group_stats, field.data_type()),
+                max_to_scalar(row_group_stats, field.data_type()),
+            ) {
+                (Some(min), Some(max)) => {
+                    stats.min = Some(match stats.min {
+                        Some(cur) if cur.partial_cmp(&min) != Some(Ordering::Greater) => cur,
+                        _ => min,
+                    });
+                    stats.max = Some(match stats.max {
+                        Some(cur) if cur.partial_cmp(&max) != Some(Ordering::Less) => cur,
+                        _ => max,
+                    });
+                }
+                _ => min_max_complete = false,
+            }
+        }
+        if !min_max_complete {
+            stats.min = None;
+            stats.max = None;
+        }
+        column_statistics.push(stats);
+    }
+    Ok(column_statistics)
+}
+
+pub(crate) fn min_to_scalar(stats: &Statistics, data_type: &DataType) -> Option<ScalarValue> {
+    if !stats.has_min_max_set() {
+        return None;
+    }
+    match stats {
+        Statistics::Boolean(s) => Some(ScalarValue::Boolean(Some(*s.min()))),
+        Statistics::Int32(s) => i32_to_scalar(*s.min(), data_type),
+        Statistics::Int64(s) => i64_to_scalar(*s.min(), data_type),
+        Statistics::Float(s) => Some(ScalarValue::Float32(Some(*s.min()))),
+        Statistics::Double(s) => Some(ScalarValue::Float64(Some(*s.min()))),
+        Statistics::ByteArray(s) => bytes_to_scalar(s.min().data(), data_type),
+        Statistics::FixedLenByteArray(s) => bytes_to_scalar(s.min().data(), data_type),
+        Statistics::Int96(_) => None,
+    }
+}
+
+pub(crate) fn max_to_scalar(stats: &Statistics, data_type: &DataType) -> Option<ScalarValue> {
+    if !stats.has_min_max_set() {
+        return None;
+    }
+    match stats {
+        Statistics::Boolean(s) => Some(ScalarValue::Boolean(Some(*s.max()))),
+        Statistics::Int32(s) => i32_to_scalar(*s.max(), data_type),
+        Statistics::Int64(s) => i64_to_scalar(*s.max(), data_type),
+        Statistics::Float(s) => Some(ScalarValue::Float32(Some(*s.max()))),
+        Statistics::Double(s) => Some(ScalarValue::Float64(Some(*s.max()))),
+        Statistics::ByteArray(s) => bytes_to_scalar(s.max().data(), data_type),
+        Statistics::FixedLenByteArray(s) => bytes_to_scalar(s.max().data(), data_type),
+        Statistics::Int96(_) => None,
+    }
+}
+
+fn i32_to_scalar(value: i32, data_type: &DataType) -> Option<ScalarValue> {
+    match data_type {
+        DataType::UInt32 => Some(ScalarValue::UInt32(Some(value as u32))),
+        DataType::Decimal128(precision, scale) => {
+            Some(ScalarValue::Decimal128(Some(value as i128), *precision, *scale))
+        }
+        _ => cast_scalar(ScalarValue::Int32(Some(value)), data_type),
+    }
+}
+
+fn i64_to_scalar(value: i64, data_type: &DataType) -> Option<ScalarValue> {
+    match data_type {
+        DataType::UInt64 => Some(ScalarValue::UInt64(Some(value as u64))),
+        DataType::Decimal128(precision, scale) => {
+            Some(ScalarValue::Decimal128(Some(value as i128), *precision, *scale))
+        }
+        _ => cast_scalar(ScalarValue::Int64(Some(value)), data_type),
+    }
+}
+
+fn bytes_to_scalar(value: &[u8], data_type: &DataType) -> Option<ScalarValue> {
+    match data_type {
+        DataType::Utf8 => Some(ScalarValue::Utf8(Some(String::from_utf8(value.to_vec()).ok()?))),
+        DataType::LargeUtf8 => Some(ScalarValue::LargeUtf8(Some(String::from_utf8(value.to_vec()).ok()?))),
+        DataType::Binary => Some(ScalarValue::Binary(Some(value.to_vec()))),
+        DataType::LargeBinary => Some(ScalarValue::LargeBinary(Some(value.to_vec()))),
+        DataType::FixedSizeBinary(size) => Some(ScalarValue::FixedSizeBinary(*size, Some(value.to_vec()))),
+        DataType::Decimal128(precision, scale) if !value.is_empty() && value.len() <= 16 => {
+            // big endian two's complement
+            let mut bytes = if value[0] & 0x80 != 0 { [0xffu8; 16] } else { [0u8; 16] };
+            bytes[16 - value.len()..].copy_from_slice(value);
+            Some(ScalarValue::Decimal128(
+                Some(i128::from_be_bytes(bytes)),
+                *precision,
+                *scale,
+            ))
+        }
+        _ => None,
+    }
+}
+
+// casts physical values to logical types, e.g. int32 to date32 or int64 to timestamp
+fn cast_scalar(value: ScalarValue, data_type: &DataType) -> Option<ScalarValue> {
+    if value.get_datatype() == *data_type {
+        return Some(value);
+    }
+    let array = cast(&value.to_array(), data_type).ok()?;
+    ScalarValue::try_from_array(&array, 0).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{ArrayRef, Decimal128Array, Int32Array, StringArray, TimestampMicrosecondArray};
+    use arrow::record_batch::RecordBatch;
+    use parquet::arrow::ArrowWriter;
+    use parquet::file::properties::WriterProperties;
+
+    #[test]
+    fn test_column_statistics_from_metadata() -> Result<()> {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(Int32Array::from(vec![Some(3), None, Some(1), Some(7)])) as ArrayRef,
+            ),
+            (
+                "name",
+                Arc::new(StringArray::from(vec![Some("b"), Some("a"), None, Some("c")])) as ArrayRef,
+            ),
+            (
+                "price",
+                Arc::new(
+                    Decimal128Array::from(vec![Some(-1250), Some(99), Some(100), None])
+                        .with_precision_and_scale(10, 2)?,
+                ) as ArrayRef,
+            ),
+            (
+                "ts",
+                Arc::new(TimestampMicrosecondArray::from(vec![None, None, Some(5), Some(9)])) as ArrayRef,
+            ),
+        ])?;
+        let props = WriterProperties::builder().set_max_row_group_size(2).build();
+        let mut writer = ArrowWriter::try_new(vec![], batch.schema(), Some(props))?;
+        writer.write(&batch)?;
+        let metadata = writer.close()?;
+
+        let stats = column_statistics_from_metadata(batch.schema(), &metadata)?;
+        assert_eq!(
+            stats[0],
+            ColumnStatistics {
+                name: "id".to_string(),
+                min: Some(ScalarValue::Int32(Some(1))),
+                max: Some(ScalarValue::Int32(Some(7))),
+                null_count: Some(1),
+            }
+        );
+        assert_eq!(stats[1].min, Some(ScalarValue::Utf8(Some("a".to_string()))));
+        assert_eq!(stats[1].max, Some(ScalarValue::Utf8(Some("c".to_string()))));
+        assert_eq!(stats[2].min, Some(ScalarValue::Decimal128(Some(-1250), 10, 2)));
+        assert_eq!(stats[2].max, Some(ScalarValue::Decimal128(Some(100), 10, 2)));
+        // the first row group of ts is all null
+        assert_eq!(stats[3].min, Some(ScalarValue::TimestampMicrosecond(Some(5), None)));
+        assert_eq!(stats[3].max, Some(ScalarValue::TimestampMicrosecond(Some(9), None)));
+        assert_eq!(stats[3].null_count, Some(2));
+        Ok(())
+    }
+}
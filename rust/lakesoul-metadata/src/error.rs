@@ -0,0 +1,71 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+pub type Result<T, E = LakeSoulMetaDataError> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+pub enum LakeSoulMetaDataError {
+    Postgres(tokio_postgres::Error),
+    Io(std::io::Error),
+    SerdeJson(serde_json::Error),
+    NotFound(String),
+    /// a commit conflicting with concurrent commits of the same partitions
+    Conflict(String),
+    Internal(String),
+}
+
+impl Display for LakeSoulMetaDataError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LakeSoulMetaDataError::Postgres(e) => write!(f, "postgres error: {}", e),
+            LakeSoulMetaDataError::Io(e) => write!(f, "io error: {}", e),
+            LakeSoulMetaDataError::SerdeJson(e) => write!(f, "serde_json error: {}", e),
+            LakeSoulMetaDataError::NotFound(msg) => write!(f, "not found: {}", msg),
+            LakeSoulMetaDataError::Conflict(msg) => write!(f, "commit conflict: {}", msg),
+            LakeSoulMetaDataError::Internal(msg) => write!(f, "internal error: {}", msg),
+        }
+    }
+}
+
+impl Error for LakeSoulMetaDataError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LakeSoulMetaDataError::Postgres(e) => Some(e),
+            LakeSoulMetaDataError::Io(e) => Some(e),
+            LakeSoulMetaDataError::SerdeJson(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<tokio_postgres::Error> for LakeSoulMetaDataError {
+    fn from(err: tokio_postgres::Error) -> Self {
+        LakeSoulMetaDataError::Postgres(err)
+    }
+}
+
+impl From<std::io::Error> for LakeSoulMetaDataError {
+    fn from(err: std::io::Error) -> Self {
+        LakeSoulMetaDataError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for LakeSoulMetaDataError {
+    fn from(err: serde_json::Error) -> Self {
+        LakeSoulMetaDataError::SerdeJson(err)
+    }
+}
+
+impl From<LakeSoulMetaDataError> for std::io::Error {
+    fn from(err: LakeSoulMetaDataError) -> Self {
+        match err {
+            LakeSoulMetaDataError::Io(e) => e,
+            LakeSoulMetaDataError::NotFound(msg) => std::io::Error::new(std::io::ErrorKind::NotFound, msg),
+            e => std::io::Error::other(e),
+        }
+    }
+}
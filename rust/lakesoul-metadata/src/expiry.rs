@@ -0,0 +1,190 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Expiry of partition versions no longer needed to read snapshots within a retention period.
+
+use std::collections::HashSet;
+
+use proto::proto::entity::{self, FileOp, PartitionInfo, TableInfo};
+
+use crate::error::{LakeSoulMetaDataError, Result};
+use crate::{uuid_from_proto, MetaDataClient};
+
+/// Versions and commits of a partition removed by expiry, or to be removed in dry run.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExpiredPartition {
+    pub partition_desc: String,
+    pub versions: Vec<i32>,
+    /// commits referred to by the expired versions only
+    pub commit_ids: Vec<entity::Uuid>,
+}
+
+/// Splits `versions` of a partition into the versions committed before `keep_version`, which are expired,
+/// and the commits referred to by the expired versions only. `versions` are in version order.
+pub fn expired_versions(versions: &[PartitionInfo], keep_version: &PartitionInfo) -> ExpiredPartition {
+    // versions are deleted by timestamp, so versions committed at the same time as the kept one are kept as well
+    let (expired, kept): (Vec<_>, Vec<_>) = versions
+        .iter()
+        .partition(|partition_info| partition_info.timestamp < keep_version.timestamp);
+    let live = kept
+        .iter()
+        .flat_map(|partition_info| partition_info.snapshot.iter().map(uuid_from_proto))
+        .collect::<HashSet<_>>();
+    let mut seen = HashSet::new();
+    let commit_ids = expired
+        .iter()
+        .flat_map(|partition_info| partition_info.snapshot.iter())
+        .filter(|commit_id| {
+            let uuid = uuid_from_proto(commit_id);
+            !live.contains(&uuid) && seen.insert(uuid)
+        })
+        .cloned()
+        .collect();
+    ExpiredPartition {
+        partition_desc: keep_version.partition_desc.clone(),
+        versions: expired.iter().map(|partition_info| partition_info.version).collect(),
+        commit_ids,
+    }
+}
+
+impl MetaDataClient {
+    /// Expires the versions of a partition that are not needed to read its snapshot at any time since
+    /// `expire_before` in milliseconds, i.e. the versions before the latest one committed before `expire_before`,
+    /// together with the DataCommitInfo referred to by them only. The latest version is never expired.
+    /// Nothing is deleted with `dry_run`. Returns None if no version has expired.
+    pub async fn expire_partition_versions(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        expire_before: i64,
+        dry_run: bool,
+    ) -> Result<Option<ExpiredPartition>> {
+        let Some(keep_version) = self
+            .get_latest_version_up_to_time(table_id, partition_desc, expire_before)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let mut versions = self.list_partition_versions(table_id, partition_desc).await?;
+        versions.sort_by_key(|partition_info| partition_info.version);
+        let keep_version = versions
+            .iter()
+            .find(|partition_info| partition_info.version == keep_version)
+            .ok_or_else(|| {
+                LakeSoulMetaDataError::NotFound(format!(
+                    "version {} of partition {} of table {}",
+                    keep_version, partition_desc, table_id
+                ))
+            })?;
+        let expired = expired_versions(&versions, keep_version);
+        if expired.versions.is_empty() {
+            return Ok(None);
+        }
+        if !dry_run {
+            // versions go first, so that no version refers to deleted commits if the commits fail to be deleted
+            self.delete_previous_version_partition(table_id, partition_desc, keep_version.timestamp - 1)
+                .await?;
+            self.delete_data_commit_info_list(table_id, partition_desc, &expired.commit_ids)
+                .await?;
+        }
+        Ok(Some(expired))
+    }
+
+    /// Expires the versions of every partition of a table, see [`MetaDataClient::expire_partition_versions`].
+    pub async fn expire_table_versions(
+        &self,
+        table_info: &TableInfo,
+        expire_before: i64,
+        dry_run: bool,
+    ) -> Result<Vec<ExpiredPartition>> {
+        let mut expired = vec![];
+        for partition_info in self.list_partitions(&table_info.table_id).await? {
+            if let Some(partition) = self
+                .expire_partition_versions(
+                    &table_info.table_id,
+                    &partition_info.partition_desc,
+                    expire_before,
+                    dry_run,
+                )
+                .await?
+            {
+                expired.push(partition);
+            }
+        }
+        Ok(expired)
+    }
+
+    /// Lists the paths of files added by the commits of any version of a table, except the `expired` ones.
+    pub async fn list_referenced_files(
+        &self,
+        table_info: &TableInfo,
+        expired: &[ExpiredPartition],
+    ) -> Result<HashSet<String>> {
+        let mut files = HashSet::new();
+        for latest in self.list_partitions(&table_info.table_id).await? {
+            let expired_versions = expired
+                .iter()
+                .filter(|partition| partition.partition_desc == latest.partition_desc)
+                .flat_map(|partition| partition.versions.iter())
+                .collect::<HashSet<_>>();
+            let mut seen = HashSet::new();
+            let commit_ids = self
+                .list_partition_versions(&table_info.table_id, &latest.partition_desc)
+                .await?
+                .iter()
+                .filter(|partition_info| !expired_versions.contains(&partition_info.version))
+                .flat_map(|partition_info| partition_info.snapshot.iter())
+                .filter(|commit_id| seen.insert(uuid_from_proto(commit_id)))
+                .cloned()
+                .collect::<Vec<_>>();
+            for data_commit_info in self
+                .list_data_commit_info(&table_info.table_id, &latest.partition_desc, &commit_ids)
+                .await?
+            {
+                files.extend(
+                    data_commit_info
+                        .file_ops
+                        .into_iter()
+                        .filter(|file_op| file_op.file_op() == FileOp::Add)
+                        .map(|file_op| file_op.path),
+                );
+            }
+        }
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expired_versions() {
+        let ids = (0..4).map(|low| entity::Uuid { high: 0, low }).collect::<Vec<_>>();
+        let version = |version: i32, timestamp: i64, snapshot: &[usize]| PartitionInfo {
+            partition_desc: "date=20230101".to_string(),
+            version,
+            timestamp,
+            snapshot: snapshot.iter().map(|idx| ids[*idx].clone()).collect(),
+            ..Default::default()
+        };
+        let versions = vec![
+            version(0, 100, &[0]),
+            version(1, 200, &[0, 1]),
+            // compaction of 0 and 1 into 2
+            version(2, 300, &[2]),
+            version(3, 400, &[2, 3]),
+        ];
+        let expired = expired_versions(&versions, &versions[2]);
+        assert_eq!(expired.partition_desc, "date=20230101");
+        assert_eq!(expired.versions, vec![0, 1]);
+        assert_eq!(expired.commit_ids, vec![ids[0].clone(), ids[1].clone()]);
+
+        let expired = expired_versions(&versions, &versions[1]);
+        assert_eq!(expired.versions, vec![0]);
+        assert!(expired.commit_ids.is_empty());
+
+        assert!(expired_versions(&versions, &versions[0]).versions.is_empty());
+    }
+}
@@ -4,6 +4,14 @@
 
 #![feature(io_error_other)]
 #![feature(split_array)]
+pub mod error;
+pub mod expiry;
+pub mod metadata_client;
+pub mod snapshot;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+pub use metadata_client::MetaDataClient;
+
 use std::collections::HashMap;
 use std::str::FromStr;
 
@@ -12,7 +20,7 @@ use prost::Message;
 
 pub use tokio::runtime::{Builder, Runtime};
 
-pub use tokio_postgres::{NoTls, Client, Statement};
+pub use tokio_postgres::{NoTls, Client, Statement, Row};
 use postgres_types::{ToSql, FromSql};
 
 pub const DAO_TYPE_QUERY_ONE_OFFSET : i32 = 0;
@@ -25,6 +33,13 @@ pub const DAO_TYPE_UPDATE_OFFSET : i32 = 500;
 pub const PARAM_DELIM: &str = "__DELIM__";
 pub const PARTITION_DESC_DELIM: &str = "_DELIM_";
 
+pub const LAKESOUL_PARTITION_SPLITTER_OF_RANGE_AND_HASH: &str = ";";
+pub const LAKESOUL_RANGE_PARTITION_SPLITTER: &str = ",";
+pub const LAKESOUL_HASH_PARTITION_SPLITTER: &str = ",";
+pub const LAKESOUL_NON_PARTITION_TABLE_PART_DESC: &str = "-5";
+pub const LAKESOUL_PARTITION_DESC_KV_DELIM: &str = "=";
+pub const LAKESOUL_HASH_BUCKET_NUM: &str = "hashBucketNum";
+
 
 
 enum ResultType {
@@ -158,245 +173,251 @@ fn get_prepared_statement(
     client: &Client,
     prepared :&mut PreparedStatementMap,
     dao_type: &DaoType,
+) -> Result<Statement, tokio_postgres::Error> {
+    runtime.block_on(get_prepared_statement_async(client, prepared, dao_type))
+}
+
+pub(crate) async fn get_prepared_statement_async(
+    client: &Client,
+    prepared :&mut PreparedStatementMap,
+    dao_type: &DaoType,
 ) -> Result<Statement, tokio_postgres::Error> {
     if let Some(statement) = prepared.get(dao_type) {
         Ok(statement.clone())
     } else {
-        let result = runtime.block_on(async {
-            let statement = match dao_type {
-                // Select Namespace
-                DaoType::SelectNamespaceByNamespace => 
-                    "select namespace, properties, comment, domain 
-                    from namespace 
-                    where namespace = $1::TEXT",
-                DaoType::ListNamespaces => 
-                    "select namespace, properties, comment, domain 
-                    from namespace",
-
-                // Select TablePathId
-                DaoType::SelectTablePathIdByTablePath => 
-                    "select table_path, table_id, table_namespace, domain 
-                    from table_path_id 
-                    where table_path = $1::TEXT",
-                DaoType::ListAllTablePath => 
-                    "select table_path, table_id, table_namespace, domain 
-                    from table_path_id",
-                DaoType::ListAllPathTablePathByNamespace => 
-                    "select table_path 
-                    from table_path_id 
-                    where table_namespace = $1::TEXT ",
-
-                // Select TableNameId
-                DaoType::SelectTableNameIdByTableName => 
-                    "select table_name, table_id, table_namespace, domain 
-                    from table_name_id 
-                    where table_name = $1::TEXT and table_namespace = $2::TEXT",
-                DaoType::ListTableNameByNamespace =>
-                    "select table_name, table_id, table_namespace, domain  
-                    from table_name_id 
-                    where table_namespace = $1::TEXT",
-
-                // Select TableInfo
-                DaoType::SelectTableInfoByTableId => 
-                    "select table_id, table_name, table_path, table_schema, properties, partitions, table_namespace, domain  
-                    from table_info 
-                    where table_id = $1::TEXT",
-                DaoType::SelectTableInfoByTableNameAndNameSpace => 
-                    "select table_id, table_name, table_path, table_schema, properties, partitions, table_namespace, domain 
-                    from table_info 
-                    where table_name = $1::TEXT and table_namespace=$2::TEXT",
-                DaoType::SelectTableInfoByTablePath =>
-                    "select table_id, table_name, table_path, table_schema, properties, partitions, table_namespace, domain 
-                    from table_info 
-                    where table_path = $1::TEXT",
-                DaoType::SelectTableInfoByIdAndTablePath => 
-                    "select table_id, table_name, table_path, table_schema, properties, partitions, table_namespace, domain 
-                    from table_info 
-                    where table_id = $1::TEXT and table_path=$2::TEXT",
-
-                // Select PartitionInfo
-                DaoType::SelectPartitionVersionByTableIdAndDescAndVersion =>
-                    "select table_id, partition_desc, version, commit_op, snapshot, expression, domain 
-                    from partition_info from partition_info 
-                    where table_id = $1::TEXT and partition_desc = $2::TEXT and version = $3::INT",
-                DaoType::SelectOnePartitionVersionByTableIdAndDesc =>
-                    "select m.table_id, t.partition_desc, m.version, m.commit_op, m.snapshot, m.expression, m.domain from (
-                        select table_id,partition_desc,max(version) from partition_info 
-                        where table_id = $1::TEXT and partition_desc = $2::TEXT group by table_id, partition_desc) t 
-                        left join partition_info m on t.table_id = m.table_id 
-                        and t.partition_desc = m.partition_desc and t.max = m.version",    
-                DaoType::ListPartitionByTableIdAndDesc =>
-                    "select table_id, partition_desc, version, commit_op, snapshot, timestamp, expression, domain 
-                    from partition_info 
-                    where table_id = $1::TEXT and partition_desc = $2::TEXT ",
-                DaoType::ListPartitionByTableId => 
-                    "select m.table_id, t.partition_desc, m.version, m.commit_op, m.snapshot, m.expression, m.domain 
-                    from (
-                        select table_id,partition_desc,max(version) 
-                        from partition_info 
-                        where table_id = $1::TEXT 
-                        group by table_id,partition_desc) t 
-                    left join partition_info m 
-                    on t.table_id = m.table_id and t.partition_desc = m.partition_desc and t.max = m.version",
-                DaoType::ListPartitionVersionByTableIdAndPartitionDescAndTimestampRange =>
-                    "select table_id, partition_desc, version, commit_op, snapshot, timestamp, expression, domain 
-                    from partition_info 
-                    where table_id = $1::TEXT and partition_desc = $2::TEXT and timestamp >= $3::BIGINT and timestamp < $4::BIGINT",
-                DaoType::ListCommitOpsBetweenVersions =>
-                    "select distinct(commit_op) 
+        let statement = match dao_type {
+            // Select Namespace
+            DaoType::SelectNamespaceByNamespace => 
+                "select namespace, properties, comment, domain 
+                from namespace 
+                where namespace = $1::TEXT",
+            DaoType::ListNamespaces => 
+                "select namespace, properties, comment, domain 
+                from namespace",
+
+            // Select TablePathId
+            DaoType::SelectTablePathIdByTablePath => 
+                "select table_path, table_id, table_namespace, domain 
+                from table_path_id 
+                where table_path = $1::TEXT",
+            DaoType::ListAllTablePath => 
+                "select table_path, table_id, table_namespace, domain 
+                from table_path_id",
+            DaoType::ListAllPathTablePathByNamespace => 
+                "select table_path 
+                from table_path_id 
+                where table_namespace = $1::TEXT ",
+
+            // Select TableNameId
+            DaoType::SelectTableNameIdByTableName => 
+                "select table_name, table_id, table_namespace, domain 
+                from table_name_id 
+                where table_name = $1::TEXT and table_namespace = $2::TEXT",
+            DaoType::ListTableNameByNamespace =>
+                "select table_name, table_id, table_namespace, domain  
+                from table_name_id 
+                where table_namespace = $1::TEXT",
+
+            // Select TableInfo
+            DaoType::SelectTableInfoByTableId => 
+                "select table_id, table_name, table_path, table_schema, properties, partitions, table_namespace, domain  
+                from table_info 
+                where table_id = $1::TEXT",
+            DaoType::SelectTableInfoByTableNameAndNameSpace => 
+                "select table_id, table_name, table_path, table_schema, properties, partitions, table_namespace, domain 
+                from table_info 
+                where table_name = $1::TEXT and table_namespace=$2::TEXT",
+            DaoType::SelectTableInfoByTablePath =>
+                "select table_id, table_name, table_path, table_schema, properties, partitions, table_namespace, domain 
+                from table_info 
+                where table_path = $1::TEXT",
+            DaoType::SelectTableInfoByIdAndTablePath => 
+                "select table_id, table_name, table_path, table_schema, properties, partitions, table_namespace, domain 
+                from table_info 
+                where table_id = $1::TEXT and table_path=$2::TEXT",
+
+            // Select PartitionInfo
+            DaoType::SelectPartitionVersionByTableIdAndDescAndVersion =>
+                "select table_id, partition_desc, version, commit_op, snapshot, expression, domain 
+                from partition_info 
+                where table_id = $1::TEXT and partition_desc = $2::TEXT and version = $3::INT",
+            DaoType::SelectOnePartitionVersionByTableIdAndDesc =>
+                "select m.table_id, t.partition_desc, m.version, m.commit_op, m.snapshot, m.expression, m.domain from (
+                    select table_id,partition_desc,max(version) from partition_info 
+                    where table_id = $1::TEXT and partition_desc = $2::TEXT group by table_id, partition_desc) t 
+                    left join partition_info m on t.table_id = m.table_id 
+                    and t.partition_desc = m.partition_desc and t.max = m.version",    
+            DaoType::ListPartitionByTableIdAndDesc =>
+                "select table_id, partition_desc, version, commit_op, snapshot, timestamp, expression, domain 
+                from partition_info 
+                where table_id = $1::TEXT and partition_desc = $2::TEXT ",
+            DaoType::ListPartitionByTableId => 
+                "select m.table_id, t.partition_desc, m.version, m.commit_op, m.snapshot, m.expression, m.domain 
+                from (
+                    select table_id,partition_desc,max(version) 
                     from partition_info 
-                    where table_id = $1::TEXT and partition_desc = $2::TEXT and version between $3::INT and $4::INT",
-                DaoType::ListPartitionVersionByTableIdAndPartitionDescAndVersionRange =>
-                    "select table_id, partition_desc, version, commit_op, snapshot, timestamp, expression, domain  
-                    from partition_info 
-                    where table_id = $1::TEXT and partition_desc = $2::TEXT and version >= $3::INT and version <= $4::INT",
+                    where table_id = $1::TEXT 
+                    group by table_id,partition_desc) t 
+                left join partition_info m 
+                on t.table_id = m.table_id and t.partition_desc = m.partition_desc and t.max = m.version",
+            DaoType::ListPartitionVersionByTableIdAndPartitionDescAndTimestampRange =>
+                "select table_id, partition_desc, version, commit_op, snapshot, timestamp, expression, domain 
+                from partition_info 
+                where table_id = $1::TEXT and partition_desc = $2::TEXT and timestamp >= $3::BIGINT and timestamp < $4::BIGINT",
+            DaoType::ListCommitOpsBetweenVersions =>
+                "select distinct(commit_op) 
+                from partition_info 
+                where table_id = $1::TEXT and partition_desc = $2::TEXT and version between $3::INT and $4::INT",
+            DaoType::ListPartitionVersionByTableIdAndPartitionDescAndVersionRange =>
+                "select table_id, partition_desc, version, commit_op, snapshot, timestamp, expression, domain  
+                from partition_info 
+                where table_id = $1::TEXT and partition_desc = $2::TEXT and version >= $3::INT and version <= $4::INT",
+
+            // Select DataCommitInfo
+            DaoType::SelectOneDataCommitInfoByTableIdAndPartitionDescAndCommitId =>
+                "select table_id, partition_desc, commit_id, file_ops, commit_op, timestamp, committed, domain 
+                from data_commit_info 
+                where table_id = $1::TEXT and partition_desc = $2::TEXT and commit_id = $3::UUID",
+        
 
-                // Select DataCommitInfo
-                DaoType::SelectOneDataCommitInfoByTableIdAndPartitionDescAndCommitId =>
-                    "select table_id, partition_desc, commit_id, file_ops, commit_op, timestamp, committed, domain 
-                    from data_commit_info 
-                    where table_id = $1::TEXT and partition_desc = $2::TEXT and commit_id = $3::UUID",
+            // Insert
+            DaoType::InsertNamespace => 
+                "insert into namespace(
+                    namespace,
+                    properties, 
+                    comment, 
+                    domain) 
+                values($1::TEXT, $2::JSON, $3::TEXT, $4::TEXT)",
+            DaoType::InsertTableInfo =>
+                "insert into table_info(
+                    table_id, 
+                    table_name, 
+                    table_path, 
+                    table_schema, 
+                    properties, 
+                    partitions, 
+                    table_namespace, 
+                    domain) 
+                values($1::TEXT, $2::TEXT, $3::TEXT, $4::TEXT, $5::JSON, $6::TEXT, $7::TEXT, $8::TEXT)",
+            DaoType::InsertTableNameId => 
+                "insert into table_name_id(
+                    table_id, 
+                    table_name, 
+                    table_namespace, 
+                    domain) 
+                values($1::TEXT, $2::TEXT, $3::TEXT, $4::TEXT)",
+            DaoType::InsertTablePathId =>
+                "insert into table_path_id(
+                    table_id, 
+                    table_path, 
+                    table_namespace, 
+                    domain) 
+                values($1::TEXT, $2::TEXT, $3::TEXT, $4::TEXT)",
+            DaoType::InsertPartitionInfo =>
+                "insert into partition_info(
+                    table_id, 
+                    partition_desc,
+                    version, 
+                    commit_op, 
+                    snapshot,
+                    expression,
+                    domain
+                ) 
+                values($1::TEXT, $2::TEXT, $3::INT, $4::TEXT, $5::_UUID, $6::TEXT, $7::TEXT)",
+            DaoType::InsertDataCommitInfo => 
+                "insert into data_commit_info(
+                    table_id, 
+                    partition_desc,
+                    commit_id, 
+                    file_ops, 
+                    commit_op,
+                    timestamp,
+                    committed,
+                    domain
+                ) 
+                values($1::TEXT, $2::TEXT, $3::UUID, $4::_data_file_op, $5::TEXT, $6::BIGINT, $7::BOOL, $8::TEXT)",
+
+            // Query Scalar
+            DaoType::GetLatestTimestampFromPartitionInfo => 
+                "select max(timestamp) as timestamp 
+                from partition_info 
+                where table_id = $1::TEXT and partition_desc = $2::TEXT",
+            DaoType::GetLatestTimestampFromPartitionInfoWithoutPartitionDesc =>
+                "select max(timestamp) as timestamp 
+                from partition_info 
+                where table_id = $1::TEXT",
+            DaoType::GetLatestVersionUpToTimeFromPartitionInfo =>
+                "select max(version) as version 
+                from partition_info 
+                where table_id = $1::TEXT and partition_desc = $2::TEXT and timestamp < $3::BIGINT",
+            DaoType::GetLatestVersionTimestampUpToTimeFromPartitionInfo =>
+                "select max(timestamp) as timestamp 
+                from partition_info 
+                where table_id = $1::TEXT and partition_desc = $2::TEXT and timestamp < $3::BIGINT",
+
+            // Update / Delete
+            DaoType::DeleteNamespaceByNamespace =>
+                "delete from namespace 
+                where namespace = $1::TEXT ",
+            DaoType::UpdateNamespacePropertiesByNamespace =>
+                "update namespace 
+                set properties = $2::JSON where namespace = $1::TEXT",
+
+            DaoType::DeleteTableNameIdByTableNameAndNamespace =>
+                "delete from table_name_id 
+                where table_name = $1::TEXT and table_namespace = $2::TEXT",
+            DaoType::DeleteTableNameIdByTableId =>
+                "delete from table_name_id 
+                where table_id = $1::TEXT",
+
+            DaoType::DeleteTableInfoByIdAndPath => 
+                "delete from table_info 
+                where table_id = $1::TEXT and table_path = $2::TEXT",
+            DaoType::UpdateTableInfoPropertiesById =>
+                "update table_info 
+                set properties = $2::JSON where table_id = $1::TEXT",
+
+
+            DaoType::DeleteTablePathIdByTablePath => 
+                "delete from table_path_id 
+                where table_path = $1::TEXT ",
+            DaoType::DeleteTablePathIdByTableId => 
+                "delete from table_path_id 
+                where table_id = $1::TEXT ",
+
+            DaoType::DeleteOneDataCommitInfoByTableIdAndPartitionDescAndCommitId => 
+                "delete from data_commit_info 
+                where table_id = $1::TEXT and partition_desc = $2::TEXT and commit_id = $3::UUID ",
+            DaoType::DeleteDataCommitInfoByTableIdAndPartitionDesc => 
+                "delete from data_commit_info 
+                where table_id = $1::TEXT and partition_desc = $2::TEXT",
+            DaoType::DeleteDataCommitInfoByTableId =>
+                "delete from data_commit_info 
+                where table_id = $1::TEXT",
+
+            DaoType::DeletePartitionInfoByTableId =>
+                "delete from partition_info 
+                where table_id = $1::TEXT",
+            DaoType::DeletePartitionInfoByTableIdAndPartitionDesc =>
+                "delete from partition_info 
+                where table_id = $1::TEXT and partition_desc = $2::TEXT",
+            DaoType::DeletePreviousVersionPartition =>
+                "delete from partition_info 
+                where table_id = $1::TEXT and partition_desc = $2::TEXT and timestamp <= $3::BIGINT",
+
+
+            // not prepared
+            DaoType::UpdateTableInfoById |
+            DaoType::TransactionInsertDataCommitInfo |
+            DaoType::TransactionInsertPartitionInfo |
+            DaoType::ListDataCommitInfoByTableIdAndPartitionDescAndCommitList |
+            DaoType::DeleteDataCommitInfoByTableIdAndPartitionDescAndCommitIdList |
+            DaoType::ListPartitionDescByTableIdAndParList => "",
             
+            // _ => todo!(),
 
-                // Insert
-                DaoType::InsertNamespace => 
-                    "insert into namespace(
-                        namespace,
-                        properties, 
-                        comment, 
-                        domain) 
-                    values($1::TEXT, $2::JSON, $3::TEXT, $4::TEXT)",
-                DaoType::InsertTableInfo =>
-                    "insert into table_info(
-                        table_id, 
-                        table_name, 
-                        table_path, 
-                        table_schema, 
-                        properties, 
-                        partitions, 
-                        table_namespace, 
-                        domain) 
-                    values($1::TEXT, $2::TEXT, $3::TEXT, $4::TEXT, $5::JSON, $6::TEXT, $7::TEXT, $8::TEXT)",
-                DaoType::InsertTableNameId => 
-                    "insert into table_name_id(
-                        table_id, 
-                        table_name, 
-                        table_namespace, 
-                        domain) 
-                    values($1::TEXT, $2::TEXT, $3::TEXT, $4::TEXT)",
-                DaoType::InsertTablePathId =>
-                    "insert into table_path_id(
-                        table_id, 
-                        table_path, 
-                        table_namespace, 
-                        domain) 
-                    values($1::TEXT, $2::TEXT, $3::TEXT, $4::TEXT)",
-                DaoType::InsertPartitionInfo =>
-                    "insert into partition_info(
-                        table_id, 
-                        partition_desc,
-                        version, 
-                        commit_op, 
-                        snapshot,
-                        expression,
-                        domain
-                    ) 
-                    values($1::TEXT, $2::TEXT, $3::INT, $4::TEXT, $5::_UUID, $6::TEXT, $7::TEXT)",
-                DaoType::InsertDataCommitInfo => 
-                    "insert into data_commit_info(
-                        table_id, 
-                        partition_desc,
-                        commit_id, 
-                        file_ops, 
-                        commit_op,
-                        timestamp,
-                        committed,
-                        domain
-                    ) 
-                    values($1::TEXT, $2::TEXT, $3::UUID, $4::_data_file_op, $5::TEXT, $6::BIGINT, $7::BOOL, $8::TEXT)",
-
-                // Query Scalar
-                DaoType::GetLatestTimestampFromPartitionInfo => 
-                    "select max(timestamp) as timestamp 
-                    from partition_info 
-                    where table_id = $1::TEXT and partition_desc = $2::TEXT",
-                DaoType::GetLatestTimestampFromPartitionInfoWithoutPartitionDesc =>
-                    "select max(timestamp) as timestamp 
-                    from partition_info 
-                    where table_id = $1::TEXT",
-                DaoType::GetLatestVersionUpToTimeFromPartitionInfo =>
-                    "select max(version) as version 
-                    from partition_info 
-                    where table_id = $1::TEXT and partition_desc = $2::TEXT and timestamp < $3::BIGINT",
-                DaoType::GetLatestVersionTimestampUpToTimeFromPartitionInfo =>
-                    "select max(timestamp) as timestamp 
-                    from partition_info 
-                    where table_id = $1::TEXT and partition_desc = $2::TEXT and timestamp < $3::BIGINT",
-
-                // Update / Delete
-                DaoType::DeleteNamespaceByNamespace =>
-                    "delete from namespace 
-                    where namespace = $1::TEXT ",
-                DaoType::UpdateNamespacePropertiesByNamespace =>
-                    "update namespace 
-                    set properties = $2::JSON where namespace = $1::TEXT",
-
-                DaoType::DeleteTableNameIdByTableNameAndNamespace =>
-                    "delete from table_name_id 
-                    where table_name = $1::TEXT and table_namespace = $2::TEXT",
-                DaoType::DeleteTableNameIdByTableId =>
-                    "delete from table_name_id 
-                    where table_id = $1::TEXT",
-
-                DaoType::DeleteTableInfoByIdAndPath => 
-                    "delete from table_info 
-                    where table_id = $1::TEXT and table_path = $2::TEXT",
-                DaoType::UpdateTableInfoPropertiesById =>
-                    "update table_info 
-                    set properties = $2::JSON where table_id = $1::TEXT",
-
-
-                DaoType::DeleteTablePathIdByTablePath => 
-                    "delete from table_path_id 
-                    where table_path = $1::TEXT ",
-                DaoType::DeleteTablePathIdByTableId => 
-                    "delete from table_path_id 
-                    where table_id = $1::TEXT ",
-
-                DaoType::DeleteOneDataCommitInfoByTableIdAndPartitionDescAndCommitId => 
-                    "delete from data_commit_info 
-                    where table_id = $1::TEXT and partition_desc = $2::TEXT and commit_id = $3::UUID ",
-                DaoType::DeleteDataCommitInfoByTableIdAndPartitionDesc => 
-                    "delete from data_commit_info 
-                    where table_id = $1::TEXT and partition_desc = $2::TEXT",
-                DaoType::DeleteDataCommitInfoByTableId =>
-                    "delete from data_commit_info 
-                    where table_id = $1::TEXT",
-
-                DaoType::DeletePartitionInfoByTableId =>
-                    "delete from partition_info 
-                    where table_id = $1::TEXT",
-                DaoType::DeletePartitionInfoByTableIdAndPartitionDesc =>
-                    "delete from partition_info 
-                    where table_id = $1::TEXT and partition_desc = $2::TEXT",
-                DaoType::DeletePreviousVersionPartition =>
-                    "delete from partition_info 
-                    where table_id = $1::TEXT and partition_desc = $2::TEXT and timestamp <= $3::BIGINT",
-
-
-                // not prepared
-                DaoType::UpdateTableInfoById |
-                DaoType::TransactionInsertDataCommitInfo |
-                DaoType::TransactionInsertPartitionInfo |
-                DaoType::ListDataCommitInfoByTableIdAndPartitionDescAndCommitList |
-                DaoType::DeleteDataCommitInfoByTableIdAndPartitionDescAndCommitIdList |
-                DaoType::ListPartitionDescByTableIdAndParList => "",
-                
-                // _ => todo!(),
-
-            };
-            client.prepare(statement).await
-        });
+        };
+        let result = client.prepare(statement).await;
         match result {
             Ok(statement) => {
                 prepared.insert(*dao_type, statement.clone());
@@ -408,6 +429,111 @@ fn get_prepared_statement(
 }
 
 
+pub(crate) fn uuid_to_proto(uuid: &uuid::Uuid) -> entity::Uuid {
+    let (high, low) = uuid.as_u64_pair();
+    entity::Uuid{high, low}
+}
+
+pub(crate) fn uuid_from_proto(uuid: &entity::Uuid) -> uuid::Uuid {
+    uuid::Uuid::from_u64_pair(uuid.high, uuid.low)
+}
+
+pub(crate) fn namespace_from_row(row: &Row) -> entity::Namespace {
+    entity::Namespace { 
+        namespace: row.get(0), 
+        properties: row.get::<_, serde_json::Value>(1).to_string(), 
+        comment: row.get::<_, Option<String>>(2).unwrap_or(String::from("")), 
+        domain: row.get(3)
+    }
+}
+
+pub(crate) fn table_name_id_from_row(row: &Row) -> entity::TableNameId {
+    entity::TableNameId { 
+        table_name: row.get(0), 
+        table_id: row.get(1), 
+        table_namespace: row.get(2), 
+        domain: row.get(3),
+    }
+}
+
+pub(crate) fn table_path_id_from_row(row: &Row) -> entity::TablePathId {
+    entity::TablePathId { 
+        table_path: row.get(0), 
+        table_id: row.get(1), 
+        table_namespace: row.get(2), 
+        domain: row.get(3),
+    }
+}
+
+pub(crate) fn table_info_from_row(row: &Row) -> entity::TableInfo {
+    entity::TableInfo { 
+        table_id: row.get(0), 
+        table_name: row.get(1),
+        table_path: row.get(2),
+        table_schema: row.get(3),
+        properties: row.get::<_, serde_json::Value>(4).to_string(), 
+        partitions: row.get(5), 
+        table_namespace: row.get(6),
+        domain: row.get(7)
+    }
+}
+
+pub(crate) fn partition_info_from_row(row: &Row) -> entity::PartitionInfo {
+    entity::PartitionInfo { 
+        table_id: row.get(0), 
+        partition_desc: row.get(1),
+        version: row.get::<_, i32>(2), 
+        commit_op: entity::CommitOp::from_str_name(row.get(3)).unwrap() as i32,
+        snapshot: row.get::<_, Vec<uuid::Uuid>>(4)
+                    .iter()
+                    .map(uuid_to_proto)
+                    .collect::<Vec<entity::Uuid>>(), 
+        timestamp: row.get::<_, i64>(5), 
+        expression: row.get::<_, Option<String>>(6).unwrap_or(String::from("")),
+        domain: row.get(7),
+    }
+}
+
+pub(crate) fn partition_info_without_timestamp_from_row(row: &Row) -> entity::PartitionInfo {
+    entity::PartitionInfo { 
+        table_id: row.get(0), 
+        partition_desc: row.get(1),
+        version: row.get::<_, i32>(2), 
+        commit_op: entity::CommitOp::from_str_name(row.get(3)).unwrap() as i32,
+        snapshot: row.get::<_, Vec<uuid::Uuid>>(4)
+                    .iter()
+                    .map(uuid_to_proto)
+                    .collect::<Vec<entity::Uuid>>(), 
+        expression: row.get::<_, Option<String>>(5).unwrap_or(String::from("")),
+        domain: row.get(6),
+        ..Default::default() 
+    }
+}
+
+pub(crate) fn partition_info_with_only_commit_op_from_row(row: &Row) -> entity::PartitionInfo {
+    entity::PartitionInfo {
+        commit_op: entity::CommitOp::from_str_name(row.get(0)).unwrap() as i32,
+        ..Default::default() 
+    }
+}
+
+pub(crate) fn data_commit_info_from_row(row: &Row) -> entity::DataCommitInfo {
+    entity::DataCommitInfo { 
+        table_id: row.get(0), 
+        partition_desc: row.get(1),
+        commit_id: Some(uuid_to_proto(&row.get::<_, uuid::Uuid>(2))),
+        file_ops: row.get::<_, Vec<DataFileOp>>(3)
+            .iter()
+            .map(|data_file_op| data_file_op.as_proto_data_file_op())
+            .collect::<Vec<entity::DataFileOp>>(),
+        commit_op: entity::CommitOp::from_str_name(row.get(4)).unwrap() as i32,
+        timestamp: row.get(5),
+        committed: row.get(6),
+        domain: row.get(7),
+    }
+}
+
+
 pub fn execute_query(
     runtime: &Runtime,
     client: &Client,
@@ -638,12 +764,7 @@ pub fn execute_query(
             let table_name_id :Vec<entity::TableNameId> = 
                 rows
                     .iter()
-                    .map(|row|proto::proto::entity::TableNameId { 
-                        table_name: row.get(0), 
-                        table_id: row.get(1), 
-                        table_namespace: row.get(2), 
-                        domain: row.get(3),
-                    })
+                    .map(table_name_id_from_row)
                     .collect();
             proto::proto::entity::JniWrapper {
                 table_name_id, 
@@ -654,12 +775,7 @@ pub fn execute_query(
             let table_path_id :Vec<entity::TablePathId> = 
                 rows
                     .iter()
-                    .map(|row|proto::proto::entity::TablePathId { 
-                        table_path: row.get(0), 
-                        table_id: row.get(1), 
-                        table_namespace: row.get(2), 
-                        domain: row.get(3),
-                    })
+                    .map(table_path_id_from_row)
                     .collect();
             proto::proto::entity::JniWrapper {
                 table_path_id, 
@@ -685,12 +801,7 @@ pub fn execute_query(
             let namespace:Vec<entity::Namespace> = 
                 rows
                     .iter()
-                    .map(|row|proto::proto::entity::Namespace { 
-                        namespace: row.get(0), 
-                        properties: row.get::<_, serde_json::Value>(1).to_string(), 
-                        comment: row.get::<_, Option<String>>(2).unwrap_or(String::from("")), 
-                        domain: row.get(3)
-                    })
+                    .map(namespace_from_row)
                     .collect();
             proto::proto::entity::JniWrapper {
                 namespace,
@@ -701,16 +812,7 @@ pub fn execute_query(
             let table_info:Vec<entity::TableInfo> = 
                 rows
                     .iter()
-                    .map(|row|proto::proto::entity::TableInfo { 
-                        table_id: row.get(0), 
-                        table_name: row.get(1),
-                        table_path: row.get(2),
-                        table_schema: row.get(3),
-                        properties: row.get::<_, serde_json::Value>(4).to_string(), 
-                        partitions: row.get(5), 
-                        table_namespace: row.get(6),
-                        domain: row.get(7)
-                    })
+                    .map(table_info_from_row)
                     .collect();
             proto::proto::entity::JniWrapper {
                 table_info,
@@ -721,24 +823,7 @@ pub fn execute_query(
             let partition_info:Vec<entity::PartitionInfo> = 
                 rows
                     .iter()
-                    .map(|row|{
-                        proto::proto::entity::PartitionInfo { 
-                            table_id: row.get(0), 
-                            partition_desc: row.get(1),
-                            version: row.get::<_, i32>(2), 
-                            commit_op: proto::proto::entity::CommitOp::from_str_name(row.get(3)).unwrap() as i32,
-                            snapshot: row.get::<_, Vec<uuid::Uuid>>(4)
-                                        .iter()
-                                        .map(|uuid| {
-                                            let (high, low) = uuid.as_u64_pair(); 
-                                            entity::Uuid{high, low}
-                                        })
-                                        .collect::<Vec<entity::Uuid>>(), 
-                            timestamp: row.get::<_, i64>(5), 
-                            expression: row.get::<_, Option<String>>(6).unwrap_or(String::from("")),
-                            domain: row.get(7),
-                        }
-                    })
+                    .map(partition_info_from_row)
                     .collect();
             proto::proto::entity::JniWrapper {
                 partition_info,
@@ -750,24 +835,7 @@ pub fn execute_query(
             let partition_info:Vec<entity::PartitionInfo> = 
                 rows
                     .iter()
-                    .map(|row|{
-                        proto::proto::entity::PartitionInfo { 
-                            table_id: row.get(0), 
-                            partition_desc: row.get(1),
-                            version: row.get::<_, i32>(2), 
-                            commit_op: proto::proto::entity::CommitOp::from_str_name(row.get(3)).unwrap() as i32,
-                            snapshot: row.get::<_, Vec<uuid::Uuid>>(4)
-                                        .iter()
-                                        .map(|uuid| {
-                                            let (high, low) = uuid.as_u64_pair(); 
-                                            entity::Uuid{high, low}
-                                        })
-                                        .collect::<Vec<entity::Uuid>>(), 
-                            expression: row.get::<_, Option<String>>(5).unwrap_or(String::from("")),
-                            domain: row.get(6),
-                            ..Default::default() 
-                        }
-                    })
+                    .map(partition_info_without_timestamp_from_row)
                     .collect();
             proto::proto::entity::JniWrapper {
                 partition_info,
@@ -778,12 +846,7 @@ pub fn execute_query(
             let partition_info:Vec<entity::PartitionInfo> = 
                 rows
                     .iter()
-                    .map(|row|{
-                        proto::proto::entity::PartitionInfo {
-                            commit_op: proto::proto::entity::CommitOp::from_str_name(row.get(0)).unwrap() as i32,
-                            ..Default::default() 
-                        }
-                    })
+                    .map(partition_info_with_only_commit_op_from_row)
                     .collect();
             proto::proto::entity::JniWrapper {
                 partition_info,
@@ -794,24 +857,7 @@ pub fn execute_query(
             let data_commit_info:Vec<entity::DataCommitInfo> = 
                 rows
                     .iter()
-                    .map(|row|{
-                        proto::proto::entity::DataCommitInfo { 
-                            table_id: row.get(0), 
-                            partition_desc: row.get(1),
-                            commit_id: {
-                                let (high, low)=row.get::<_, uuid::Uuid>(2).as_u64_pair();  
-                                Some(entity::Uuid{high, low})
-                            },
-                            file_ops: row.get::<_, Vec<DataFileOp>>(3)
-                                .iter()
-                                .map(|data_file_op| data_file_op.as_proto_data_file_op())
-                                .collect::<Vec<entity::DataFileOp>>(),
-                            commit_op: proto::proto::entity::CommitOp::from_str_name(row.get(4)).unwrap() as i32,
-                            timestamp: row.get(5),
-                            committed: row.get(6),
-                            domain: row.get(7),
-                        }
-                    })
+                    .map(data_commit_info_from_row)
                     .collect();
             proto::proto::entity::JniWrapper {
                 data_commit_info,
@@ -1212,7 +1258,7 @@ pub fn execute_update(
 
             let statement = format!(
                 "delete from data_commit_info 
-                where table_id = $1::TEXT and partition_desc = $2::TEXT and commit_id in commit_id in ({}) ", uuid_str_list);
+                where table_id = $1::TEXT and partition_desc = $2::TEXT and commit_id in ({}) ", uuid_str_list);
 
             runtime.block_on(async{
                 let statement = client.prepare(&statement).await?;
@@ -1287,9 +1333,9 @@ pub fn execute_query_scalar(
             });
             match result {
                 Ok(Some(row)) => {
-                    let ts = row.get::<_, Option<i64>>(0);
-                    match ts {
-                        Some(ts) => Ok(Some(format!("{}", ts))),
+                    let version = row.get::<_, Option<i32>>(0);
+                    match version {
+                        Some(version) => Ok(Some(format!("{}", version))),
                         None => Ok(None)
                     }
                 }
@@ -1339,6 +1385,37 @@ pub fn clean_meta_for_test(
     }
 }
 
+/// Splits `TableInfo.partitions` of the form "range_col1,range_col2;hash_col1,hash_col2"
+/// into range partition columns and hash partition (primary key) columns.
+pub fn parse_table_info_partitions(partitions: &str) -> (Vec<String>, Vec<String>) {
+    let split_cols = |cols: &str, splitter: &str| {
+        cols.split(splitter)
+            .filter(|col| !col.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<String>>()
+    };
+    match partitions.split_once(LAKESOUL_PARTITION_SPLITTER_OF_RANGE_AND_HASH) {
+        Some((range, hash)) => (
+            split_cols(range, LAKESOUL_RANGE_PARTITION_SPLITTER),
+            split_cols(hash, LAKESOUL_HASH_PARTITION_SPLITTER),
+        ),
+        None => (split_cols(partitions, LAKESOUL_RANGE_PARTITION_SPLITTER), vec![]),
+    }
+}
+
+/// Parses a partition desc of the form "col1=value1,col2=value2" into column and value pairs.
+/// The desc of a non-partitioned table yields nothing.
+pub fn parse_partition_desc(partition_desc: &str) -> Vec<(String, String)> {
+    if partition_desc == LAKESOUL_NON_PARTITION_TABLE_PART_DESC {
+        return vec![];
+    }
+    partition_desc
+        .split(LAKESOUL_RANGE_PARTITION_SPLITTER)
+        .filter_map(|kv| kv.split_once(LAKESOUL_PARTITION_DESC_KV_DELIM))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
 fn convert_to_io_error(err:tokio_postgres::Error) -> std::io::Error {
     let msg = err.to_string();
     match err.into_source() {
@@ -1375,6 +1452,22 @@ pub fn create_connection(
 mod tests {
     use proto::proto::entity;
     use prost::Message;
+    use crate::{parse_partition_desc, parse_table_info_partitions};
+
+    #[test]
+    fn test_parse_partitions() {
+        assert_eq!(parse_table_info_partitions("date,region;id"), (vec!["date".to_string(), "region".to_string()], vec!["id".to_string()]));
+        assert_eq!(parse_table_info_partitions(";id,name"), (vec![], vec!["id".to_string(), "name".to_string()]));
+        assert_eq!(parse_table_info_partitions("date;"), (vec!["date".to_string()], vec![]));
+        assert_eq!(parse_table_info_partitions(""), (vec![], vec![]));
+
+        assert_eq!(parse_partition_desc("-5"), vec![]);
+        assert_eq!(parse_partition_desc("date=20230101,region=china"), vec![
+            ("date".to_string(), "20230101".to_string()),
+            ("region".to_string(), "china".to_string()),
+        ]);
+    }
+
     #[tokio::test]
     async fn test_entity() -> std::io::Result<()> {
         let namespace = entity::Namespace {
@@ -0,0 +1,847 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+
+use postgres_types::ToSql;
+use proto::proto::entity::{
+    self, CommitOp, DataCommitInfo, MetaInfo, Namespace, PartitionInfo, TableInfo, TableNameId, TablePathId,
+};
+use tokio::sync::Mutex;
+use tokio_postgres::error::SqlState;
+
+use crate::error::{LakeSoulMetaDataError, Result};
+use crate::{
+    data_commit_info_from_row, get_prepared_statement_async, namespace_from_row, partition_info_from_row,
+    partition_info_with_only_commit_op_from_row, partition_info_without_timestamp_from_row, table_info_from_row,
+    table_name_id_from_row, table_path_id_from_row, uuid_from_proto, uuid_to_proto, Client, DaoType, DataFileOp, NoTls,
+    PreparedStatementMap, Row,
+};
+
+/// Times a commit is retried after losing the race for a new partition version.
+pub const MAX_COMMIT_ATTEMPTS: usize = 5;
+
+/// Typed async access to LakeSoul metadata, reusing the prepared statements of [`DaoType`].
+pub struct MetaDataClient {
+    client: Mutex<Client>,
+    prepared: Mutex<PreparedStatementMap>,
+}
+
+impl MetaDataClient {
+    /// Connects with a postgres connection string, e.g. "host=127.0.0.1 port=5432 dbname=lakesoul_test user=lakesoul_test password=lakesoul_test".
+    /// The connection task is spawned on the current tokio runtime.
+    pub async fn from_config(config: String) -> Result<Self> {
+        let (client, connection) = tokio_postgres::connect(config.as_str(), NoTls).await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                log::error!("metadata connection error: {}", e);
+            }
+        });
+        Ok(Self::from_client(client))
+    }
+
+    pub fn from_client(client: Client) -> Self {
+        MetaDataClient {
+            client: Mutex::new(client),
+            prepared: Mutex::new(PreparedStatementMap::new()),
+        }
+    }
+
+    async fn query(&self, dao_type: DaoType, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>> {
+        let client = self.client.lock().await;
+        let statement = {
+            let mut prepared = self.prepared.lock().await;
+            get_prepared_statement_async(&client, &mut prepared, &dao_type).await?
+        };
+        Ok(client.query(&statement, params).await?)
+    }
+
+    async fn query_opt(&self, dao_type: DaoType, params: &[&(dyn ToSql + Sync)]) -> Result<Option<Row>> {
+        let client = self.client.lock().await;
+        let statement = {
+            let mut prepared = self.prepared.lock().await;
+            get_prepared_statement_async(&client, &mut prepared, &dao_type).await?
+        };
+        Ok(client.query_opt(&statement, params).await?)
+    }
+
+    async fn execute(&self, dao_type: DaoType, params: &[&(dyn ToSql + Sync)]) -> Result<u64> {
+        let client = self.client.lock().await;
+        let statement = {
+            let mut prepared = self.prepared.lock().await;
+            get_prepared_statement_async(&client, &mut prepared, &dao_type).await?
+        };
+        Ok(client.execute(&statement, params).await?)
+    }
+
+    // ==== Namespace ====
+
+    pub async fn create_namespace(&self, namespace: Namespace) -> Result<()> {
+        let properties: serde_json::Value = serde_json::from_str(&namespace.properties)?;
+        self.execute(
+            DaoType::InsertNamespace,
+            &[&namespace.namespace, &properties, &namespace.comment, &namespace.domain],
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_namespace_by_namespace(&self, namespace: &str) -> Result<Option<Namespace>> {
+        let row = self.query_opt(DaoType::SelectNamespaceByNamespace, &[&namespace]).await?;
+        Ok(row.as_ref().map(namespace_from_row))
+    }
+
+    pub async fn list_namespaces(&self) -> Result<Vec<Namespace>> {
+        let rows = self.query(DaoType::ListNamespaces, &[]).await?;
+        Ok(rows.iter().map(namespace_from_row).collect())
+    }
+
+    // ==== Table ====
+
+    /// Creates the table info together with its name and path mappings in one transaction.
+    pub async fn create_table(&self, table_info: TableInfo) -> Result<()> {
+        let properties: serde_json::Value = serde_json::from_str(&table_info.properties)?;
+        let mut client = self.client.lock().await;
+        let statements = {
+            let mut prepared = self.prepared.lock().await;
+            let mut statements = Vec::with_capacity(3);
+            for dao_type in [DaoType::InsertTableNameId, DaoType::InsertTablePathId, DaoType::InsertTableInfo] {
+                statements.push(get_prepared_statement_async(&client, &mut prepared, &dao_type).await?);
+            }
+            statements
+        };
+        let transaction = client.transaction().await?;
+        transaction
+            .execute(
+                &statements[0],
+                &[
+                    &table_info.table_id,
+                    &table_info.table_name,
+                    &table_info.table_namespace,
+                    &table_info.domain,
+                ],
+            )
+            .await?;
+        transaction
+            .execute(
+                &statements[1],
+                &[
+                    &table_info.table_id,
+                    &table_info.table_path,
+                    &table_info.table_namespace,
+                    &table_info.domain,
+                ],
+            )
+            .await?;
+        transaction
+            .execute(
+                &statements[2],
+                &[
+                    &table_info.table_id,
+                    &table_info.table_name,
+                    &table_info.table_path,
+                    &table_info.table_schema,
+                    &properties,
+                    &table_info.partitions,
+                    &table_info.table_namespace,
+                    &table_info.domain,
+                ],
+            )
+            .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_table_info_by_table_id(&self, table_id: &str) -> Result<Option<TableInfo>> {
+        let row = self.query_opt(DaoType::SelectTableInfoByTableId, &[&table_id]).await?;
+        Ok(row.as_ref().map(table_info_from_row))
+    }
+
+    pub async fn get_table_info_by_name(&self, table_name: &str, namespace: &str) -> Result<Option<TableInfo>> {
+        let row = self
+            .query_opt(DaoType::SelectTableInfoByTableNameAndNameSpace, &[&table_name, &namespace])
+            .await?;
+        Ok(row.as_ref().map(table_info_from_row))
+    }
+
+    pub async fn get_table_info_by_table_path(&self, table_path: &str) -> Result<Option<TableInfo>> {
+        let row = self.query_opt(DaoType::SelectTableInfoByTablePath, &[&table_path]).await?;
+        Ok(row.as_ref().map(table_info_from_row))
+    }
+
+    pub async fn get_table_name_id_by_table_name(
+        &self,
+        table_name: &str,
+        namespace: &str,
+    ) -> Result<Option<TableNameId>> {
+        let row = self
+            .query_opt(DaoType::SelectTableNameIdByTableName, &[&table_name, &namespace])
+            .await?;
+        Ok(row.as_ref().map(table_name_id_from_row))
+    }
+
+    pub async fn list_table_name_by_namespace(&self, namespace: &str) -> Result<Vec<TableNameId>> {
+        let rows = self.query(DaoType::ListTableNameByNamespace, &[&namespace]).await?;
+        Ok(rows.iter().map(table_name_id_from_row).collect())
+    }
+
+    pub async fn get_table_path_id_by_table_path(&self, table_path: &str) -> Result<Option<TablePathId>> {
+        let row = self.query_opt(DaoType::SelectTablePathIdByTablePath, &[&table_path]).await?;
+        Ok(row.as_ref().map(table_path_id_from_row))
+    }
+
+    // ==== PartitionInfo ====
+
+    /// Lists the latest version of every partition of a table.
+    pub async fn list_partitions(&self, table_id: &str) -> Result<Vec<PartitionInfo>> {
+        let rows = self.query(DaoType::ListPartitionByTableId, &[&table_id]).await?;
+        Ok(rows.iter().map(partition_info_without_timestamp_from_row).collect())
+    }
+
+    /// Gets the latest version of a partition.
+    pub async fn get_partition_info(&self, table_id: &str, partition_desc: &str) -> Result<Option<PartitionInfo>> {
+        let row = self
+            .query_opt(DaoType::SelectOnePartitionVersionByTableIdAndDesc, &[&table_id, &partition_desc])
+            .await?;
+        Ok(row.as_ref().map(partition_info_without_timestamp_from_row))
+    }
+
+    pub async fn get_partition_info_by_version(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        version: i32,
+    ) -> Result<Option<PartitionInfo>> {
+        let row = self
+            .query_opt(
+                DaoType::SelectPartitionVersionByTableIdAndDescAndVersion,
+                &[&table_id, &partition_desc, &version],
+            )
+            .await?;
+        Ok(row.as_ref().map(partition_info_without_timestamp_from_row))
+    }
+
+    /// Lists all versions of a partition.
+    pub async fn list_partition_versions(&self, table_id: &str, partition_desc: &str) -> Result<Vec<PartitionInfo>> {
+        let rows = self
+            .query(DaoType::ListPartitionByTableIdAndDesc, &[&table_id, &partition_desc])
+            .await?;
+        Ok(rows.iter().map(partition_info_from_row).collect())
+    }
+
+    /// Lists versions of a partition within `[start_version, end_version]`.
+    pub async fn list_partition_versions_by_version_range(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        start_version: i32,
+        end_version: i32,
+    ) -> Result<Vec<PartitionInfo>> {
+        let rows = self
+            .query(
+                DaoType::ListPartitionVersionByTableIdAndPartitionDescAndVersionRange,
+                &[&table_id, &partition_desc, &start_version, &end_version],
+            )
+            .await?;
+        Ok(rows.iter().map(partition_info_from_row).collect())
+    }
+
+    /// Lists versions of a partition committed within `[start_timestamp, end_timestamp)`.
+    pub async fn list_partition_versions_by_timestamp_range(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        start_timestamp: i64,
+        end_timestamp: i64,
+    ) -> Result<Vec<PartitionInfo>> {
+        let rows = self
+            .query(
+                DaoType::ListPartitionVersionByTableIdAndPartitionDescAndTimestampRange,
+                &[&table_id, &partition_desc, &start_timestamp, &end_timestamp],
+            )
+            .await?;
+        Ok(rows.iter().map(partition_info_from_row).collect())
+    }
+
+    /// Lists distinct commit ops of a partition within `[first_version, last_version]`.
+    pub async fn list_commit_ops_between_versions(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        first_version: i32,
+        last_version: i32,
+    ) -> Result<Vec<CommitOp>> {
+        let rows = self
+            .query(
+                DaoType::ListCommitOpsBetweenVersions,
+                &[&table_id, &partition_desc, &first_version, &last_version],
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| partition_info_with_only_commit_op_from_row(row).commit_op())
+            .collect())
+    }
+
+    /// Gets the latest version of a partition committed before `timestamp`.
+    pub async fn get_latest_version_up_to_time(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        timestamp: i64,
+    ) -> Result<Option<i32>> {
+        let row = self
+            .query_opt(
+                DaoType::GetLatestVersionUpToTimeFromPartitionInfo,
+                &[&table_id, &partition_desc, &timestamp],
+            )
+            .await?;
+        Ok(row.and_then(|row| row.get::<_, Option<i32>>(0)))
+    }
+
+    /// Deletes the versions of a partition committed at or before `timestamp`, returning the number deleted.
+    pub async fn delete_previous_version_partition(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        timestamp: i64,
+    ) -> Result<u64> {
+        self.execute(
+            DaoType::DeletePreviousVersionPartition,
+            &[&table_id, &partition_desc, &timestamp],
+        )
+        .await
+    }
+
+    /// Inserts new partition versions and marks their snapshots committed in one transaction.
+    /// Fails if any of the versions already exists.
+    pub async fn transaction_insert_partition_info(&self, partition_info_list: Vec<PartitionInfo>) -> Result<()> {
+        let mut client = self.client.lock().await;
+        let statement = {
+            let mut prepared = self.prepared.lock().await;
+            get_prepared_statement_async(&client, &mut prepared, &DaoType::InsertPartitionInfo).await?
+        };
+        let transaction = client.transaction().await?;
+        for partition_info in &partition_info_list {
+            let snapshot = partition_info
+                .snapshot
+                .iter()
+                .map(uuid_from_proto)
+                .collect::<Vec<uuid::Uuid>>();
+            transaction
+                .execute(
+                    &statement,
+                    &[
+                        &partition_info.table_id,
+                        &partition_info.partition_desc,
+                        &partition_info.version,
+                        &partition_info.commit_op().as_str_name(),
+                        &snapshot,
+                        &partition_info.expression,
+                        &partition_info.domain,
+                    ],
+                )
+                .await?;
+            transaction
+                .execute(
+                    "update data_commit_info set committed = 'true'
+                    where table_id = $1::TEXT and partition_desc = $2::TEXT and commit_id = any($3::_UUID)",
+                    &[&partition_info.table_id, &partition_info.partition_desc, &snapshot],
+                )
+                .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    // ==== DataCommitInfo ====
+
+    pub async fn insert_data_commit_info(&self, data_commit_info: DataCommitInfo) -> Result<()> {
+        let file_ops = data_commit_info
+            .file_ops
+            .iter()
+            .map(DataFileOp::from_proto_data_file_op)
+            .collect::<Vec<DataFileOp>>();
+        let commit_id = data_commit_info
+            .commit_id
+            .as_ref()
+            .map(uuid_from_proto)
+            .ok_or_else(|| LakeSoulMetaDataError::Internal("commit_id of DataCommitInfo is missing".to_string()))?;
+        self.execute(
+            DaoType::InsertDataCommitInfo,
+            &[
+                &data_commit_info.table_id,
+                &data_commit_info.partition_desc,
+                &commit_id,
+                &file_ops,
+                &data_commit_info.commit_op().as_str_name(),
+                &data_commit_info.timestamp,
+                &data_commit_info.committed,
+                &data_commit_info.domain,
+            ],
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_data_commit_info(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        commit_id: &entity::Uuid,
+    ) -> Result<Option<DataCommitInfo>> {
+        let row = self
+            .query_opt(
+                DaoType::SelectOneDataCommitInfoByTableIdAndPartitionDescAndCommitId,
+                &[&table_id, &partition_desc, &uuid_from_proto(commit_id)],
+            )
+            .await?;
+        Ok(row.as_ref().map(data_commit_info_from_row))
+    }
+
+    /// Lists the DataCommitInfo of `commit_ids` in the same order. Missing commits are skipped.
+    pub async fn list_data_commit_info(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        commit_ids: &[entity::Uuid],
+    ) -> Result<Vec<DataCommitInfo>> {
+        let uuid_list = commit_ids.iter().map(uuid_from_proto).collect::<Vec<uuid::Uuid>>();
+        let rows = {
+            let client = self.client.lock().await;
+            client
+                .query(
+                    "select table_id, partition_desc, commit_id, file_ops, commit_op, timestamp, committed, domain
+                    from data_commit_info
+                    where table_id = $1::TEXT and partition_desc = $2::TEXT and commit_id = any($3::_UUID)",
+                    &[&table_id, &partition_desc, &uuid_list],
+                )
+                .await?
+        };
+        let mut data_commit_info_map = rows
+            .iter()
+            .map(data_commit_info_from_row)
+            .map(|info| (info.commit_id.as_ref().map(uuid_from_proto), info))
+            .collect::<HashMap<_, _>>();
+        Ok(uuid_list
+            .iter()
+            .filter_map(|uuid| data_commit_info_map.remove(&Some(*uuid)))
+            .collect())
+    }
+
+    /// Deletes the DataCommitInfo of `commit_ids`, returning the number deleted.
+    pub async fn delete_data_commit_info_list(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        commit_ids: &[entity::Uuid],
+    ) -> Result<u64> {
+        let uuid_list = commit_ids.iter().map(uuid_from_proto).collect::<Vec<uuid::Uuid>>();
+        let client = self.client.lock().await;
+        Ok(client
+            .execute(
+                "delete from data_commit_info
+                where table_id = $1::TEXT and partition_desc = $2::TEXT and commit_id = any($3::_UUID)",
+                &[&table_id, &partition_desc, &uuid_list],
+            )
+            .await?)
+    }
+
+    // ==== Commit ====
+
+    /// Inserts `data_commit_info` if absent and commits it as a new version of its partition.
+    /// Does nothing if the commit has been committed already.
+    pub async fn commit_data_commit_info(&self, data_commit_info: DataCommitInfo) -> Result<()> {
+        let commit_id = data_commit_info
+            .commit_id
+            .clone()
+            .ok_or_else(|| LakeSoulMetaDataError::Internal("commit_id of DataCommitInfo is missing".to_string()))?;
+        match self
+            .get_data_commit_info(&data_commit_info.table_id, &data_commit_info.partition_desc, &commit_id)
+            .await?
+        {
+            Some(committed) if committed.committed => return Ok(()),
+            Some(_) => {}
+            None => self.insert_data_commit_info(data_commit_info.clone()).await?,
+        }
+        let table_info = self
+            .get_table_info_by_table_id(&data_commit_info.table_id)
+            .await?
+            .ok_or_else(|| LakeSoulMetaDataError::NotFound(format!("table {}", data_commit_info.table_id)))?;
+        let meta_info = MetaInfo {
+            list_partition: vec![PartitionInfo {
+                table_id: data_commit_info.table_id.clone(),
+                partition_desc: data_commit_info.partition_desc.clone(),
+                snapshot: vec![commit_id],
+                commit_op: data_commit_info.commit_op,
+                domain: data_commit_info.domain.clone(),
+                ..Default::default()
+            }],
+            table_info: Some(table_info),
+            read_partition_info: vec![],
+        };
+        self.commit_data(meta_info, data_commit_info.commit_op()).await?;
+        Ok(())
+    }
+
+    /// Commits each partition of `meta_info` as the next version of that partition.
+    ///
+    /// Append and merge commits add the new snapshot to the current one.
+    /// Compaction and update commits replace the snapshot read by the writer, given by `read_partition_info`,
+    /// keeping the commits that happened since then. Partitions without read info are taken as read at their
+    /// current version. Concurrent commits are handled as in the Java `DBManager.commitData`:
+    /// - an update fails if the partition has been updated, or compacted along with other commits, since read
+    /// - a compaction of a partition updated or compacted since read is skipped
+    /// - an append fails if a merge has been committed concurrently, and vice versa
+    ///
+    /// Other concurrent commits are rebased onto, retrying up to [`MAX_COMMIT_ATTEMPTS`] times.
+    /// Conflicts are reported as [`LakeSoulMetaDataError::Conflict`].
+    ///
+    /// Returns the new versions of the committed partitions, leaving out the skipped compactions.
+    /// Their timestamps are set by the database and are not filled in.
+    pub async fn commit_data(&self, meta_info: MetaInfo, commit_op: CommitOp) -> Result<Vec<PartitionInfo>> {
+        if commit_op == CommitOp::DeleteCommit {
+            return Err(LakeSoulMetaDataError::Internal(format!(
+                "unsupported commit op {}",
+                commit_op.as_str_name()
+            )));
+        }
+        let table_info = meta_info
+            .table_info
+            .ok_or_else(|| LakeSoulMetaDataError::Internal("table_info of MetaInfo is missing".to_string()))?;
+        let read_versions = meta_info
+            .read_partition_info
+            .iter()
+            .map(|partition_info| (partition_info.partition_desc.clone(), partition_info.version))
+            .collect::<HashMap<_, _>>();
+        // versions each partition was rebased onto by the last failed attempt
+        let mut base_versions: HashMap<String, i32> = HashMap::new();
+        for _ in 0..MAX_COMMIT_ATTEMPTS {
+            let mut new_partition_list = Vec::with_capacity(meta_info.list_partition.len());
+            for partition_info in &meta_info.list_partition {
+                let cur_partition_info = self
+                    .get_or_create_cur_partition_info(&table_info, &partition_info.partition_desc)
+                    .await?;
+                let new_partition_info = match commit_op {
+                    CommitOp::AppendCommit | CommitOp::MergeCommit => {
+                        if let Some(&base_version) = base_versions.get(&partition_info.partition_desc) {
+                            self.check_concurrent_commits(&table_info, &cur_partition_info, base_version, commit_op)
+                                .await?;
+                        }
+                        Some(PartitionInfo {
+                            snapshot: cur_partition_info
+                                .snapshot
+                                .iter()
+                                .chain(&partition_info.snapshot)
+                                .cloned()
+                                .collect(),
+                            ..cur_partition_info.clone()
+                        })
+                    }
+                    _ => {
+                        let read_version = read_versions
+                            .get(&partition_info.partition_desc)
+                            .copied()
+                            .unwrap_or(cur_partition_info.version);
+                        self.rebase_overwrite(
+                            &table_info,
+                            &cur_partition_info,
+                            partition_info,
+                            read_version,
+                            commit_op,
+                        )
+                        .await?
+                    }
+                };
+                if let Some(new_partition_info) = new_partition_info {
+                    new_partition_list.push(PartitionInfo {
+                        version: cur_partition_info.version + 1,
+                        commit_op: commit_op as i32,
+                        expression: partition_info.expression.clone(),
+                        ..new_partition_info
+                    });
+                }
+            }
+            if new_partition_list.is_empty() {
+                return Ok(new_partition_list);
+            }
+            let versions = new_partition_list
+                .iter()
+                .map(|partition_info| (partition_info.partition_desc.clone(), partition_info.version - 1))
+                .collect::<Vec<_>>();
+            match self.transaction_insert_partition_info(new_partition_list.clone()).await {
+                Ok(()) => return Ok(new_partition_list),
+                // another writer has committed one of the versions first
+                Err(LakeSoulMetaDataError::Postgres(e)) if e.code() == Some(&SqlState::UNIQUE_VIOLATION) => {
+                    base_versions.extend(versions);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(LakeSoulMetaDataError::Conflict(format!(
+            "{} of table {} failed after {} attempts",
+            commit_op.as_str_name(),
+            table_info.table_path,
+            MAX_COMMIT_ATTEMPTS
+        )))
+    }
+
+    // Checks the commits of a partition between `base_version` and its current version
+    // before rebasing an append or merge commit onto them.
+    async fn check_concurrent_commits(
+        &self,
+        table_info: &TableInfo,
+        cur_partition_info: &PartitionInfo,
+        base_version: i32,
+        commit_op: CommitOp,
+    ) -> Result<()> {
+        if cur_partition_info.version <= base_version {
+            return Ok(());
+        }
+        let middle_commit_ops = self
+            .list_commit_ops_between_versions(
+                &table_info.table_id,
+                &cur_partition_info.partition_desc,
+                base_version + 1,
+                cur_partition_info.version,
+            )
+            .await?;
+        let conflicting_op = match commit_op {
+            CommitOp::AppendCommit => CommitOp::MergeCommit,
+            _ => CommitOp::AppendCommit,
+        };
+        if middle_commit_ops.contains(&conflicting_op) {
+            return Err(conflict_error(
+                table_info,
+                cur_partition_info,
+                commit_op,
+                &middle_commit_ops,
+            ));
+        }
+        Ok(())
+    }
+
+    // Computes the new snapshot of a compaction or update commit of `partition_info` made on `read_version`.
+    // Returns None if the compaction is obsolete and the partition should be skipped.
+    async fn rebase_overwrite(
+        &self,
+        table_info: &TableInfo,
+        cur_partition_info: &PartitionInfo,
+        partition_info: &PartitionInfo,
+        read_version: i32,
+        commit_op: CommitOp,
+    ) -> Result<Option<PartitionInfo>> {
+        if read_version >= cur_partition_info.version {
+            return Ok(Some(PartitionInfo {
+                snapshot: partition_info.snapshot.clone(),
+                ..cur_partition_info.clone()
+            }));
+        }
+        let middle_commit_ops = self
+            .list_commit_ops_between_versions(
+                &table_info.table_id,
+                &partition_info.partition_desc,
+                read_version + 1,
+                cur_partition_info.version,
+            )
+            .await?;
+        let overwritten = middle_commit_ops.contains(&CommitOp::UpdateCommit);
+        let compacted = middle_commit_ops.contains(&CommitOp::CompactionCommit);
+        let snapshot = match commit_op {
+            CommitOp::UpdateCommit if overwritten || (compacted && middle_commit_ops.len() > 1) => {
+                return Err(conflict_error(
+                    table_info,
+                    cur_partition_info,
+                    commit_op,
+                    &middle_commit_ops,
+                ));
+            }
+            // the compaction has only rewritten the data read by the update
+            CommitOp::UpdateCommit if compacted => partition_info.snapshot.clone(),
+            CommitOp::CompactionCommit if overwritten || compacted => return Ok(None),
+            _ => {
+                // keep the commits appended since read
+                let read_snapshot = match read_version {
+                    version if version < 0 => vec![],
+                    version => self
+                        .get_partition_info_by_version(&table_info.table_id, &partition_info.partition_desc, version)
+                        .await?
+                        .map(|read_partition_info| read_partition_info.snapshot)
+                        .unwrap_or_default(),
+                };
+                partition_info
+                    .snapshot
+                    .iter()
+                    .chain(
+                        cur_partition_info
+                            .snapshot
+                            .iter()
+                            .filter(|commit_id| !read_snapshot.contains(commit_id)),
+                    )
+                    .cloned()
+                    .collect()
+            }
+        };
+        Ok(Some(PartitionInfo {
+            snapshot,
+            ..cur_partition_info.clone()
+        }))
+    }
+
+    /// Gets the latest version of a partition, or an empty partition with version -1 if it does not exist yet.
+    pub(crate) async fn get_or_create_cur_partition_info(
+        &self,
+        table_info: &TableInfo,
+        partition_desc: &str,
+    ) -> Result<PartitionInfo> {
+        Ok(self
+            .get_partition_info(&table_info.table_id, partition_desc)
+            .await?
+            .unwrap_or_else(|| PartitionInfo {
+                table_id: table_info.table_id.clone(),
+                partition_desc: partition_desc.to_string(),
+                version: -1,
+                domain: table_info.domain.clone(),
+                ..Default::default()
+            }))
+    }
+}
+
+fn conflict_error(
+    table_info: &TableInfo,
+    cur_partition_info: &PartitionInfo,
+    commit_op: CommitOp,
+    middle_commit_ops: &[CommitOp],
+) -> LakeSoulMetaDataError {
+    LakeSoulMetaDataError::Conflict(format!(
+        "{} of partition {} of table {} conflicts with concurrent commits [{}] up to version {}",
+        commit_op.as_str_name(),
+        cur_partition_info.partition_desc,
+        table_info.table_path,
+        middle_commit_ops
+            .iter()
+            .map(|commit_op| commit_op.as_str_name())
+            .collect::<Vec<_>>()
+            .join(", "),
+        cur_partition_info.version
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_test_table, test_client};
+    use proto::proto::entity::{DataFileOp, FileOp};
+
+    fn data_commit_info(table_id: &str, path: &str, commit_op: CommitOp) -> DataCommitInfo {
+        DataCommitInfo {
+            table_id: table_id.to_string(),
+            partition_desc: "-5".to_string(),
+            commit_id: Some(uuid_to_proto(&uuid::Uuid::new_v4())),
+            file_ops: vec![DataFileOp {
+                path: path.to_string(),
+                file_op: FileOp::Add as i32,
+                size: 1024,
+                file_exist_cols: "id,value".to_string(),
+            }],
+            commit_op: commit_op as i32,
+            domain: "public".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commit_data() -> Result<()> {
+        let Some(client) = test_client().await? else {
+            return Ok(());
+        };
+        let table_path = format!("file:///tmp/lakesoul/{}", uuid::Uuid::new_v4());
+        let table_id = create_test_table(&client, &table_path, "{}", ";").await?.table_id;
+        let table_info = client.get_table_info_by_name(&table_id, "default").await?.unwrap();
+        assert_eq!(table_info.table_id, table_id);
+
+        let first = data_commit_info(&table_id, "file:///tmp/a.parquet", CommitOp::AppendCommit);
+        let second = data_commit_info(&table_id, "file:///tmp/b.parquet", CommitOp::AppendCommit);
+        client.commit_data_commit_info(first.clone()).await?;
+        client.commit_data_commit_info(second.clone()).await?;
+
+        let partitions = client.list_partitions(&table_id).await?;
+        assert_eq!(partitions.len(), 1);
+        assert_eq!(partitions[0].version, 1);
+        assert_eq!(
+            partitions[0].snapshot,
+            vec![first.commit_id.clone().unwrap(), second.commit_id.clone().unwrap()]
+        );
+
+        let data_commit_info_list = client
+            .list_data_commit_info(&table_id, "-5", &partitions[0].snapshot)
+            .await?;
+        assert_eq!(data_commit_info_list.len(), 2);
+        assert!(data_commit_info_list.iter().all(|info| info.committed));
+        assert_eq!(data_commit_info_list[1].file_ops[0].path, "file:///tmp/b.parquet");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_commit_conflict() -> Result<()> {
+        let Some(client) = test_client().await? else {
+            return Ok(());
+        };
+        let table_path = format!("file:///tmp/lakesoul/{}", uuid::Uuid::new_v4());
+        let table_info = create_test_table(&client, &table_path, "{}", ";").await?;
+        let table_id = table_info.table_id.clone();
+        let meta_info = |data_commit_info: &DataCommitInfo, read_partition_info: Vec<PartitionInfo>| MetaInfo {
+            list_partition: vec![PartitionInfo {
+                table_id: table_id.clone(),
+                partition_desc: "-5".to_string(),
+                snapshot: vec![data_commit_info.commit_id.clone().unwrap()],
+                ..Default::default()
+            }],
+            table_info: Some(table_info.clone()),
+            read_partition_info,
+        };
+
+        let first = data_commit_info(&table_id, "file:///tmp/a.parquet", CommitOp::AppendCommit);
+        client.commit_data_commit_info(first.clone()).await?;
+        let read = client.get_partition_info(&table_id, "-5").await?.unwrap();
+
+        // an append committed after the compaction read the partition is kept
+        let second = data_commit_info(&table_id, "file:///tmp/b.parquet", CommitOp::AppendCommit);
+        client.commit_data_commit_info(second.clone()).await?;
+        let compaction = data_commit_info(&table_id, "file:///tmp/c.parquet", CommitOp::CompactionCommit);
+        client.insert_data_commit_info(compaction.clone()).await?;
+        let committed = client
+            .commit_data(meta_info(&compaction, vec![read.clone()]), CommitOp::CompactionCommit)
+            .await?;
+        let cur = client.get_partition_info(&table_id, "-5").await?.unwrap();
+        assert_eq!(committed.len(), 1);
+        assert_eq!((committed[0].version, &committed[0].snapshot), (cur.version, &cur.snapshot));
+        assert_eq!(cur.version, 2);
+        assert_eq!(
+            cur.snapshot,
+            vec![compaction.commit_id.clone().unwrap(), second.commit_id.clone().unwrap()]
+        );
+
+        // a compaction of the same version is obsolete now
+        let stale_compaction = data_commit_info(&table_id, "file:///tmp/d.parquet", CommitOp::CompactionCommit);
+        client.insert_data_commit_info(stale_compaction.clone()).await?;
+        let committed = client
+            .commit_data(
+                meta_info(&stale_compaction, vec![read.clone()]),
+                CommitOp::CompactionCommit,
+            )
+            .await?;
+        assert!(committed.is_empty());
+        assert_eq!(client.get_partition_info(&table_id, "-5").await?.unwrap().version, 2);
+
+        // an update of the same version conflicts with the append and the compaction
+        let update = data_commit_info(&table_id, "file:///tmp/e.parquet", CommitOp::UpdateCommit);
+        client.insert_data_commit_info(update.clone()).await?;
+        let result = client
+            .commit_data(meta_info(&update, vec![read]), CommitOp::UpdateCommit)
+            .await;
+        assert!(matches!(result, Err(LakeSoulMetaDataError::Conflict(_))));
+        Ok(())
+    }
+}
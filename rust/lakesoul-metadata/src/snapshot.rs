@@ -0,0 +1,314 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashSet;
+
+use proto::proto::entity::{self, CommitOp, DataCommitInfo, DataFileOp, FileOp, PartitionInfo, TableInfo};
+
+use crate::error::{LakeSoulMetaDataError, Result};
+use crate::{uuid_from_proto, MetaDataClient};
+
+/// Which version of each partition to read.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SnapshotMode {
+    /// the latest version
+    #[default]
+    Latest,
+    /// the given version, or the latest one if the partition has not reached it
+    VersionAsOf(i32),
+    /// the latest version committed before the given timestamp in milliseconds
+    TimestampAsOf(i64),
+}
+
+/// Window of partition versions to read incrementally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IncrementalRange {
+    /// versions within `(start_version, end_version]`, i.e. the changes since `start_version`
+    Versions(i32, i32),
+    /// versions committed within `[start_timestamp, end_timestamp)` in milliseconds
+    Timestamps(i64, i64),
+}
+
+/// Live data files of one partition version.
+#[derive(Clone, Debug, Default)]
+pub struct PartitionSnapshot {
+    pub partition_info: PartitionInfo,
+    /// add ops of live files, in commit order
+    pub files: Vec<DataFileOp>,
+}
+
+impl PartitionSnapshot {
+    pub fn partition_desc(&self) -> &str {
+        &self.partition_info.partition_desc
+    }
+
+    pub fn file_paths(&self) -> Vec<String> {
+        self.files.iter().map(|file| file.path.clone()).collect()
+    }
+}
+
+/// Applies add and del file ops of `data_commit_info_list` in order and returns the files still alive.
+pub fn apply_file_ops(data_commit_info_list: &[DataCommitInfo]) -> Vec<DataFileOp> {
+    let mut files: Vec<DataFileOp> = vec![];
+    for data_commit_info in data_commit_info_list {
+        for file_op in &data_commit_info.file_ops {
+            match file_op.file_op() {
+                FileOp::Add => {
+                    files.retain(|file| file.path != file_op.path);
+                    files.push(file_op.clone());
+                }
+                FileOp::Del => files.retain(|file| file.path != file_op.path),
+            }
+        }
+    }
+    files
+}
+
+/// Returns the commits added by `versions`, in commit order, on top of the snapshot of `base`.
+/// Compaction commits only rewrite data already committed, so they are skipped. Update commits rewrite
+/// the partition and cannot be read incrementally.
+pub fn incremental_commit_ids(base: Option<&PartitionInfo>, versions: &[PartitionInfo]) -> Result<Vec<entity::Uuid>> {
+    let mut seen = base
+        .iter()
+        .flat_map(|base| base.snapshot.iter().map(uuid_from_proto))
+        .collect::<HashSet<_>>();
+    let mut commit_ids = vec![];
+    for partition_info in versions {
+        let snapshot = match partition_info.commit_op() {
+            CommitOp::UpdateCommit => {
+                return Err(LakeSoulMetaDataError::Internal(format!(
+                    "update commit of version {} of partition {} of table {} cannot be read incrementally",
+                    partition_info.version, partition_info.partition_desc, partition_info.table_id
+                )))
+            }
+            // the compaction commit comes first, followed by the commits kept since the compacted version
+            CommitOp::CompactionCommit => {
+                if let Some(compaction) = partition_info.snapshot.first() {
+                    seen.insert(uuid_from_proto(compaction));
+                }
+                partition_info.snapshot.get(1..).unwrap_or_default()
+            }
+            CommitOp::AppendCommit | CommitOp::MergeCommit => &partition_info.snapshot[..],
+        };
+        for commit_id in snapshot {
+            if seen.insert(uuid_from_proto(commit_id)) {
+                commit_ids.push(commit_id.clone());
+            }
+        }
+    }
+    Ok(commit_ids)
+}
+
+impl MetaDataClient {
+    /// Resolves the partition version to read according to `mode`.
+    /// Returns None if the partition has no version visible in this mode.
+    pub async fn resolve_partition_version(
+        &self,
+        latest: PartitionInfo,
+        mode: SnapshotMode,
+    ) -> Result<Option<PartitionInfo>> {
+        let version = match mode {
+            SnapshotMode::Latest => return Ok(Some(latest)),
+            SnapshotMode::VersionAsOf(version) if version >= latest.version => return Ok(Some(latest)),
+            SnapshotMode::VersionAsOf(version) => Some(version),
+            SnapshotMode::TimestampAsOf(timestamp) => {
+                self.get_latest_version_up_to_time(&latest.table_id, &latest.partition_desc, timestamp)
+                    .await?
+            }
+        };
+        match version {
+            None => Ok(None),
+            Some(version) if version < 0 => Ok(None),
+            Some(version) => self
+                .get_partition_info_by_version(&latest.table_id, &latest.partition_desc, version)
+                .await?
+                .map(Some)
+                .ok_or_else(|| {
+                    LakeSoulMetaDataError::NotFound(format!(
+                        "version {} of partition {} of table {}",
+                        version, latest.partition_desc, latest.table_id
+                    ))
+                }),
+        }
+    }
+
+    /// Resolves the live data files of a partition version from its snapshot.
+    pub async fn resolve_partition_snapshot(&self, partition_info: PartitionInfo) -> Result<PartitionSnapshot> {
+        let data_commit_info_list = self
+            .list_data_commit_info(
+                &partition_info.table_id,
+                &partition_info.partition_desc,
+                &partition_info.snapshot,
+            )
+            .await?;
+        if data_commit_info_list.len() != partition_info.snapshot.len() {
+            return Err(LakeSoulMetaDataError::NotFound(format!(
+                "data commit info of version {} of partition {} of table {}",
+                partition_info.version, partition_info.partition_desc, partition_info.table_id
+            )));
+        }
+        Ok(PartitionSnapshot {
+            files: apply_file_ops(&data_commit_info_list),
+            partition_info,
+        })
+    }
+
+    /// Resolves the data files added to a partition by the versions within `range`.
+    /// Returns None if no version of the partition is within `range`.
+    /// The partition info of the result is the last version within `range`.
+    pub async fn resolve_partition_incremental(
+        &self,
+        table_id: &str,
+        partition_desc: &str,
+        range: IncrementalRange,
+    ) -> Result<Option<PartitionSnapshot>> {
+        let mut versions = match range {
+            IncrementalRange::Versions(start_version, end_version) => {
+                self.list_partition_versions_by_version_range(table_id, partition_desc, start_version + 1, end_version)
+                    .await?
+            }
+            IncrementalRange::Timestamps(start_timestamp, end_timestamp) => {
+                self.list_partition_versions_by_timestamp_range(
+                    table_id,
+                    partition_desc,
+                    start_timestamp,
+                    end_timestamp,
+                )
+                .await?
+            }
+        };
+        versions.sort_by_key(|partition_info| partition_info.version);
+        let Some(first) = versions.first() else {
+            return Ok(None);
+        };
+        let base_version = first.version - 1;
+        let base = if base_version < 0 {
+            None
+        } else {
+            Some(
+                self.get_partition_info_by_version(table_id, partition_desc, base_version)
+                    .await?
+                    .ok_or_else(|| {
+                        LakeSoulMetaDataError::NotFound(format!(
+                            "version {} of partition {} of table {}",
+                            base_version, partition_desc, table_id
+                        ))
+                    })?,
+            )
+        };
+        let commit_ids = incremental_commit_ids(base.as_ref(), &versions)?;
+        let data_commit_info_list = self
+            .list_data_commit_info(table_id, partition_desc, &commit_ids)
+            .await?;
+        if data_commit_info_list.len() != commit_ids.len() {
+            return Err(LakeSoulMetaDataError::NotFound(format!(
+                "data commit info within {:?} of partition {} of table {}",
+                range, partition_desc, table_id
+            )));
+        }
+        Ok(Some(PartitionSnapshot {
+            files: apply_file_ops(&data_commit_info_list),
+            partition_info: versions.pop().unwrap_or_default(),
+        }))
+    }
+
+    /// Resolves the live data files of every partition of a table according to `mode`.
+    /// Partitions without a version visible in this mode are skipped.
+    pub async fn resolve_table_snapshot(
+        &self,
+        table_info: &TableInfo,
+        mode: SnapshotMode,
+    ) -> Result<Vec<PartitionSnapshot>> {
+        let mut snapshots = vec![];
+        for latest in self.list_partitions(&table_info.table_id).await? {
+            if let Some(partition_info) = self.resolve_partition_version(latest, mode).await? {
+                snapshots.push(self.resolve_partition_snapshot(partition_info).await?);
+            }
+        }
+        Ok(snapshots)
+    }
+
+    /// Resolves the data files added to every partition of a table by the versions within `range`.
+    /// Partitions without a version within `range` are skipped.
+    pub async fn resolve_table_incremental(
+        &self,
+        table_info: &TableInfo,
+        range: IncrementalRange,
+    ) -> Result<Vec<PartitionSnapshot>> {
+        let mut snapshots = vec![];
+        for latest in self.list_partitions(&table_info.table_id).await? {
+            if let Some(snapshot) = self
+                .resolve_partition_incremental(&table_info.table_id, &latest.partition_desc, range)
+                .await?
+            {
+                snapshots.push(snapshot);
+            }
+        }
+        Ok(snapshots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data_commit_info(file_ops: Vec<(&str, FileOp)>) -> DataCommitInfo {
+        DataCommitInfo {
+            file_ops: file_ops
+                .into_iter()
+                .map(|(path, file_op)| DataFileOp {
+                    path: path.to_string(),
+                    file_op: file_op as i32,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_apply_file_ops() {
+        let commits = vec![
+            data_commit_info(vec![("a", FileOp::Add), ("b", FileOp::Add)]),
+            data_commit_info(vec![("c", FileOp::Add)]),
+            // compaction replacing a and b
+            data_commit_info(vec![("a", FileOp::Del), ("b", FileOp::Del), ("d", FileOp::Add)]),
+            data_commit_info(vec![("e", FileOp::Add)]),
+        ];
+        let files = apply_file_ops(&commits)
+            .into_iter()
+            .map(|file| file.path)
+            .collect::<Vec<_>>();
+        assert_eq!(files, vec!["c", "d", "e"]);
+    }
+
+    #[test]
+    fn test_incremental_commit_ids() -> Result<()> {
+        let ids = (0..5).map(|low| entity::Uuid { high: 0, low }).collect::<Vec<_>>();
+        let version = |version: i32, commit_op: CommitOp, snapshot: &[usize]| PartitionInfo {
+            version,
+            commit_op: commit_op as i32,
+            snapshot: snapshot.iter().map(|idx| ids[*idx].clone()).collect(),
+            ..Default::default()
+        };
+        let base = version(0, CommitOp::AppendCommit, &[0]);
+        let versions = vec![
+            version(1, CommitOp::AppendCommit, &[0, 1]),
+            // compaction of 0 and 1 into 2, keeping 3 committed concurrently
+            version(2, CommitOp::CompactionCommit, &[2, 3]),
+            version(3, CommitOp::MergeCommit, &[2, 3, 4]),
+        ];
+        assert_eq!(
+            incremental_commit_ids(Some(&base), &versions)?,
+            vec![ids[1].clone(), ids[3].clone(), ids[4].clone()]
+        );
+        assert_eq!(
+            incremental_commit_ids(None, &versions[..1])?,
+            vec![ids[0].clone(), ids[1].clone()]
+        );
+        let updated = vec![version(1, CommitOp::UpdateCommit, &[1])];
+        assert!(incremental_commit_ids(Some(&base), &updated).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+// SPDX-FileCopyrightText: 2023 LakeSoul Contributors
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers of tests against a LakeSoul metadata database.
+
+use proto::proto::entity::TableInfo;
+
+use crate::error::Result;
+use crate::MetaDataClient;
+
+/// Environment variable with the connection string of the metadata database of tests, like
+/// "host=127.0.0.1 port=5432 dbname=lakesoul_test user=lakesoul_test password=lakesoul_test".
+/// The database must have been initialized with script/meta_init.sql.
+pub const TEST_PG_CONFIG_ENV: &str = "LAKESOUL_TEST_PG_CONFIG";
+
+/// Connects to the metadata database of tests, or returns None if [`TEST_PG_CONFIG_ENV`] is unset,
+/// in which case the test is skipped by returning early.
+pub async fn test_client() -> Result<Option<MetaDataClient>> {
+    match std::env::var(TEST_PG_CONFIG_ENV) {
+        Ok(config) => Ok(Some(MetaDataClient::from_config(config).await?)),
+        Err(_) => {
+            println!("skipped since {} is not set", TEST_PG_CONFIG_ENV);
+            Ok(None)
+        }
+    }
+}
+
+/// Creates a table with a unique id and name in the default namespace of the metadata database of tests.
+/// `partitions` is given as `range1,range2;hash1,hash2`, and `table_schema` as a spark schema json.
+pub async fn create_test_table(
+    client: &MetaDataClient,
+    table_path: &str,
+    table_schema: &str,
+    partitions: &str,
+) -> Result<TableInfo> {
+    let table_id = format!("table_{}", uuid::Uuid::new_v4().simple());
+    let table_info = TableInfo {
+        table_id: table_id.clone(),
+        table_namespace: "default".to_string(),
+        table_name: table_id,
+        table_path: table_path.to_string(),
+        table_schema: table_schema.to_string(),
+        properties: "{}".to_string(),
+        partitions: partitions.to_string(),
+        domain: "public".to_string(),
+    };
+    client.create_table(table_info.clone()).await?;
+    Ok(table_info)
+}